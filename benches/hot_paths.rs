@@ -0,0 +1,136 @@
+//! Frame parsing, Echo Reply construction and checksum computation
+//!
+//! Run with `cargo bench`. See `firmware/examples/cycles.rs` for the on-target (Cortex-M)
+//! equivalent.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use jnet::{checksum, ether, icmp, ipv4, mac, udp};
+
+const SIZES: &[u16] = &[64, 512, 1472];
+
+const MAC_SRC: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x00]);
+const MAC_DEST: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x01]);
+
+const IP_SRC: ipv4::Addr = ipv4::Addr([192, 168, 1, 33]);
+const IP_DEST: ipv4::Addr = ipv4::Addr([192, 168, 1, 1]);
+
+// UDP datagram with a `payload_len` bytes long payload
+fn udp_frame(buffer: &mut [u8], payload_len: u16) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(MAC_SRC);
+    eth.set_destination(MAC_DEST);
+
+    eth.ipv4(|ip| {
+        ip.set_source(IP_SRC);
+        ip.set_destination(IP_DEST);
+
+        ip.udp(|udp| {
+            udp.set_source(1337);
+            udp.set_destination(1338);
+            udp.set_payload(&vec![0xaa; usize::from(payload_len)]);
+        });
+    });
+
+    eth.as_bytes().len()
+}
+
+// Echo Request with a `payload_len` bytes long payload
+fn echo_request(buffer: &mut [u8], payload_len: u16) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(MAC_SRC);
+    eth.set_destination(MAC_DEST);
+
+    eth.ipv4(|ip| {
+        ip.set_source(IP_SRC);
+        ip.set_destination(IP_DEST);
+
+        ip.echo_request(|icmp| {
+            icmp.set_identifier(1);
+            icmp.set_sequence_number(2);
+            icmp.set_payload_pattern(icmp::Pattern::Incrementing, payload_len);
+        });
+    });
+
+    eth.as_bytes().len()
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for payload_len in SIZES {
+        let mut buffer = [0; ether::MAX_FRAME_SIZE as usize];
+        let len = udp_frame(&mut buffer, *payload_len);
+        let frame = &buffer[..len];
+
+        group.bench_with_input(
+            BenchmarkId::new("ether+ipv4+udp", payload_len),
+            frame,
+            |b, frame| {
+                b.iter(|| {
+                    let eth = ether::Frame::parse(black_box(frame)).unwrap();
+                    let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+                    let udp = udp::Packet::parse(ip.payload()).unwrap();
+                    udp.get_destination()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn echo_reply(c: &mut Criterion) {
+    let mut group = c.benchmark_group("echo_reply");
+    for payload_len in SIZES {
+        let mut request = [0; ether::MAX_FRAME_SIZE as usize];
+        let len = echo_request(&mut request, *payload_len);
+        let request = &request[..len];
+        let mut buffer = [0; ether::MAX_FRAME_SIZE as usize];
+
+        group.bench_with_input(
+            BenchmarkId::new("in-place", payload_len),
+            request,
+            |b, request| {
+                b.iter(|| {
+                    let buffer = &mut buffer[..request.len()];
+                    buffer.copy_from_slice(request);
+
+                    // same steps as the responder in `firmware/examples/ipv4.rs`
+                    let mut eth = ether::Frame::parse(buffer).unwrap();
+                    let mut ip = ipv4::Packet::parse(eth.payload_mut()).unwrap();
+                    let src_ip = ip.get_source();
+                    let request = icmp::Message::parse(ip.payload_mut())
+                        .unwrap()
+                        .downcast::<icmp::EchoRequest>()
+                        .unwrap();
+                    let _reply: icmp::Message<_, icmp::EchoReply, _> = request.into();
+
+                    let mut ip = ip.set_source(IP_DEST);
+                    ip.set_destination(src_ip);
+                    let _ip = ip.update_checksum();
+
+                    eth.set_destination(MAC_SRC);
+                    eth.set_source(MAC_DEST);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn internet_checksum(c: &mut Criterion) {
+    let mut group = c.benchmark_group("checksum");
+    for len in SIZES {
+        let bytes = vec![0xaa; usize::from(*len)];
+
+        group.bench_with_input(BenchmarkId::new("internet", len), &bytes[..], |b, bytes| {
+            b.iter(|| {
+                let mut state = checksum::State::new();
+                state.push(black_box(bytes));
+                state.finish()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, echo_reply, internet_checksum);
+criterion_main!(benches);
@@ -0,0 +1,172 @@
+//! Cycle counts of the hot paths
+//!
+//! Measures, using the DWT cycle counter, how long it takes to parse an Ethernet + IPv4 + UDP
+//! frame, to turn an Echo Request into an Echo Reply in place and to compute the Internet checksum
+//! of 64, 512 and 1472 bytes long payloads. The results are reported over semihosting.
+//!
+//! See `benches/hot_paths.rs` for the host equivalent.
+
+#![deny(rust_2018_compatibility)]
+#![deny(rust_2018_idioms)]
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![feature(proc_macro_hygiene)]
+#![no_main]
+#![no_std]
+
+#[allow(unused_extern_crates)]
+extern crate panic_abort;
+#[allow(unused_extern_crates)]
+extern crate stm32f103xx_hal;
+
+use blue_pill::ItmLogger;
+use cast::usize;
+use cortex_m::peripheral::DWT;
+use cortex_m_rt::entry;
+use cortex_m_semihosting::hprintln;
+use jnet::{checksum, ether, icmp, ipv4, mac, udp};
+use stlog::{
+    global_logger,
+    spanned::{error, info},
+};
+
+#[global_logger]
+static LOGGER: ItmLogger = ItmLogger;
+
+const SIZES: [u16; 3] = [64, 512, 1472];
+
+const MAC_SRC: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x00]);
+const MAC_DEST: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x01]);
+
+const IP_SRC: ipv4::Addr = ipv4::Addr([192, 168, 1, 33]);
+const IP_DEST: ipv4::Addr = ipv4::Addr([192, 168, 1, 1]);
+
+const BUFFER_SIZE: usize = ether::MAX_FRAME_SIZE as usize;
+
+#[entry]
+fn main() -> ! {
+    info!("Initializing ..");
+
+    let mut core = cortex_m::Peripherals::take().unwrap_or_else(|| {
+        error!("cortex_m::Peripherals::take failed");
+
+        blue_pill::fatal();
+    });
+
+    core.DCB.enable_trace();
+    core.DWT.enable_cycle_counter();
+
+    info!("Done with initialization");
+
+    let mut request = [0; BUFFER_SIZE];
+    let mut buffer = [0; BUFFER_SIZE];
+    for payload_len in SIZES.iter().cloned() {
+        let len = udp_frame(&mut request, payload_len);
+        let cycles = measure(|| {
+            let eth = ether::Frame::parse(&request[..len]).ok()?;
+            let ip = ipv4::Packet::parse(eth.payload()).ok()?;
+            udp::Packet::parse(ip.payload()).ok()?;
+            Some(())
+        });
+        report("parse", payload_len, cycles);
+
+        let len = echo_request(&mut request, payload_len);
+        buffer[..len].copy_from_slice(&request[..len]);
+        let cycles = measure(|| echo_reply(&mut buffer[..len]));
+        report("echo_reply", payload_len, cycles);
+
+        let bytes = &request[..usize(payload_len)];
+        let cycles = measure(|| {
+            let mut state = checksum::State::new();
+            state.push(bytes);
+            Some(state.finish())
+        });
+        report("checksum", payload_len, cycles);
+    }
+
+    info!("Done");
+
+    loop {}
+}
+
+// Returns the number of cycles spent in `f` or `None` if `f` failed
+fn measure<T>(f: impl FnOnce() -> Option<T>) -> Option<u32> {
+    let start = DWT::get_cycle_count();
+    let ok = f().is_some();
+    let end = DWT::get_cycle_count();
+
+    if ok {
+        Some(end.wrapping_sub(start))
+    } else {
+        None
+    }
+}
+
+fn report(name: &str, payload_len: u16, cycles: Option<u32>) {
+    if let Some(cycles) = cycles {
+        hprintln!("{}/{}: {} cycles", name, payload_len, cycles).ok();
+    } else {
+        error!("measured operation failed");
+    }
+}
+
+// UDP datagram with a `payload_len` bytes long payload
+fn udp_frame(buffer: &mut [u8], payload_len: u16) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(MAC_SRC);
+    eth.set_destination(MAC_DEST);
+
+    eth.ipv4(|ip| {
+        ip.set_source(IP_SRC);
+        ip.set_destination(IP_DEST);
+
+        ip.udp(|udp| {
+            udp.set_source(1337);
+            udp.set_destination(1338);
+            udp.set_payload(&[0xaa; BUFFER_SIZE][..usize(payload_len)]);
+        });
+    });
+
+    eth.as_bytes().len()
+}
+
+// Echo Request with a `payload_len` bytes long payload
+fn echo_request(buffer: &mut [u8], payload_len: u16) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(MAC_SRC);
+    eth.set_destination(MAC_DEST);
+
+    eth.ipv4(|ip| {
+        ip.set_source(IP_SRC);
+        ip.set_destination(IP_DEST);
+
+        ip.echo_request(|icmp| {
+            icmp.set_identifier(1);
+            icmp.set_sequence_number(2);
+            icmp.set_payload_pattern(icmp::Pattern::Incrementing, payload_len);
+        });
+    });
+
+    eth.as_bytes().len()
+}
+
+// same steps as the responder in `examples/ipv4.rs`
+fn echo_reply(buffer: &mut [u8]) -> Option<()> {
+    let mut eth = ether::Frame::parse(buffer).ok()?;
+    let mut ip = ipv4::Packet::parse(eth.payload_mut()).ok()?;
+    let src_ip = ip.get_source();
+    let request = icmp::Message::parse(ip.payload_mut())
+        .ok()?
+        .downcast::<icmp::EchoRequest>()
+        .ok()?;
+    let _reply: icmp::Message<_, icmp::EchoReply, _> = request.into();
+
+    let mut ip = ip.set_source(IP_DEST);
+    ip.set_destination(src_ip);
+    let _ip = ip.update_checksum();
+
+    eth.set_destination(MAC_SRC);
+    eth.set_source(MAC_DEST);
+
+    Some(())
+}
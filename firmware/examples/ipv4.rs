@@ -219,6 +219,19 @@ fn on_new_packet<'a>(
 
                         return Action::ArpReply(eth);
                     }
+
+                    // are they asking for our IP address? (InARP)
+                    if arp.inarp_reply(MAC, IP).is_ok() {
+                        info!("InARP request addressed to us");
+
+                        let tha = arp.get_tha();
+
+                        // update the Ethernet header
+                        eth.set_destination(tha);
+                        eth.set_source(MAC);
+
+                        return Action::ArpReply(eth);
+                    }
                 } else {
                     error!("not an IPv4-over-Ethernet ARP packet");
                 }
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+use cortex_m::asm;
+use cortex_m_rt::{entry, exception};
+use panic_never::force_eval;
+
+use jnet::dhcpv6;
+
+const LEN: usize = 128;
+static mut BUFFER: [u8; LEN] = [0; LEN];
+
+#[exception]
+unsafe fn SysTick() {
+    if let Ok(m) = dhcpv6::Message::parse(&BUFFER[..]) {
+        force_eval!(m.get_message_type());
+        force_eval!(m.get_transaction_id());
+        force_eval!(m.get_client_id());
+        force_eval!(m.get_server_id());
+        force_eval!(m.get_status_code());
+        force_eval!(m.get_information_refresh_time());
+
+        for addr in m.dns_servers() {
+            force_eval!(addr);
+        }
+
+        for server in m.ntp_servers() {
+            force_eval!(server);
+        }
+
+        for opt in m.options() {
+            force_eval!(opt.get_code());
+            force_eval!(opt.contents());
+        }
+    } else {
+        asm::nop();
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
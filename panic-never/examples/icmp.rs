@@ -5,16 +5,23 @@ use cortex_m::asm;
 use cortex_m_rt::{entry, exception};
 use panic_never::force_eval;
 
-use jnet::{icmp, Unknown, Valid};
+use jnet::{
+    icmp::{self, RouterAdvertisement},
+    Unknown, Valid,
+};
 
 const LEN: usize = 128;
 static mut BUFFER: [u8; LEN] = [0; LEN];
 static mut MESSAGE: Option<icmp::Message<&'static mut [u8], Unknown, Valid>> = None;
+static mut RA: Option<icmp::Message<&'static mut [u8], RouterAdvertisement, Valid>> = None;
 
 #[exception]
 unsafe fn SysTick() {
     if let Ok(m) = icmp::Message::parse(&mut BUFFER[..]) {
-        MESSAGE = Some(m);
+        match m.downcast::<RouterAdvertisement>() {
+            Ok(ra) => RA = Some(ra),
+            Err(m) => MESSAGE = Some(m),
+        }
     } else {
         asm::nop();
     }
@@ -28,6 +35,18 @@ unsafe fn SVCall() {
         force_eval!(m.payload());
         force_eval!(m.len());
     }
+
+    if let Some(ra) = RA.take() {
+        force_eval!(ra.get_num_addrs());
+        force_eval!(ra.get_addr_entry_size());
+        force_eval!(ra.get_lifetime());
+        force_eval!(ra.default_router());
+
+        for (addr, preference) in ra.addresses() {
+            force_eval!(addr);
+            force_eval!(preference);
+        }
+    }
 }
 
 #[entry]
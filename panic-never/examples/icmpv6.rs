@@ -6,7 +6,10 @@ use cortex_m_rt::{entry, exception};
 use panic_never::force_eval;
 
 use jnet::{
-    icmpv6::{self, EchoReply, EchoRequest, NeighborAdvertisement, NeighborSolicitation},
+    icmpv6::{
+        self, EchoReply, EchoRequest, MulticastListenerReport, NeighborAdvertisement,
+        NeighborSolicitation, PacketTooBig, RouterAdvertisement,
+    },
     ipv6, Unknown,
 };
 
@@ -16,6 +19,9 @@ static mut NA: Option<icmpv6::Message<&'static mut [u8], NeighborAdvertisement>>
 static mut NS: Option<icmpv6::Message<&'static mut [u8], NeighborSolicitation>> = None;
 static mut ERQ: Option<icmpv6::Message<&'static mut [u8], EchoRequest>> = None;
 static mut ERP: Option<icmpv6::Message<&'static mut [u8], EchoReply>> = None;
+static mut PTB: Option<icmpv6::Message<&'static mut [u8], PacketTooBig>> = None;
+static mut RA: Option<icmpv6::Message<&'static mut [u8], RouterAdvertisement>> = None;
+static mut MLR: Option<icmpv6::Message<&'static mut [u8], MulticastListenerReport>> = None;
 static mut U: Option<icmpv6::Message<&'static mut [u8], Unknown>> = None;
 
 #[exception]
@@ -29,7 +35,16 @@ unsafe fn SysTick() {
                     Ok(erq) => ERQ = Some(erq),
                     Err(m) => match m.downcast::<EchoReply>() {
                         Ok(erp) => ERP = Some(erp),
-                        Err(u) => U = Some(u),
+                        Err(m) => match m.downcast::<PacketTooBig>() {
+                            Ok(ptb) => PTB = Some(ptb),
+                            Err(m) => match m.downcast::<RouterAdvertisement>() {
+                                Ok(ra) => RA = Some(ra),
+                                Err(m) => match m.downcast::<MulticastListenerReport>() {
+                                    Ok(mlr) => MLR = Some(mlr),
+                                    Err(u) => U = Some(u),
+                                },
+                            },
+                        },
                     },
                 },
             },
@@ -78,6 +93,61 @@ unsafe fn SVCall() {
         force_eval!(erp.payload());
     }
 
+    if let Some(ptb) = PTB.take() {
+        force_eval!(ptb.get_mtu());
+        force_eval!(ptb.get_invoking_destination());
+        force_eval!(ptb.invoking_packet());
+    }
+
+    if let Some(ra) = RA.take() {
+        force_eval!(ra.get_cur_hop_limit());
+        force_eval!(ra.get_managed());
+        force_eval!(ra.get_other());
+        force_eval!(ra.get_router_lifetime());
+        force_eval!(ra.get_reachable_time());
+        force_eval!(ra.get_retrans_timer());
+        force_eval!(ra.get_source_ll());
+        force_eval!(ra.get_mtu());
+
+        for pi in ra.prefixes() {
+            force_eval!(pi.get_prefix_length());
+            force_eval!(pi.get_on_link());
+            force_eval!(pi.get_autonomous());
+            force_eval!(pi.get_valid_lifetime());
+            force_eval!(pi.get_preferred_lifetime());
+            force_eval!(pi.get_prefix());
+        }
+
+        for rdnss in ra.rdnss() {
+            force_eval!(rdnss.get_lifetime());
+            for addr in rdnss.addrs() {
+                force_eval!(addr);
+            }
+        }
+
+        for dnssl in ra.dnssl() {
+            force_eval!(dnssl.get_lifetime());
+            for domain in dnssl.domains() {
+                for label in domain.labels() {
+                    force_eval!(label);
+                }
+            }
+        }
+    }
+
+    if let Some(mlr) = MLR.take() {
+        force_eval!(mlr.get_number_of_records());
+
+        for record in mlr.records() {
+            force_eval!(record.get_type());
+            force_eval!(record.get_multicast_address());
+            force_eval!(record.aux_data());
+            for source in record.sources() {
+                force_eval!(source);
+            }
+        }
+    }
+
     if let Some(u) = U.take() {
         force_eval!(u.get_type());
         force_eval!(u.get_code());
@@ -35,6 +35,7 @@ unsafe fn SVCall() {
         force_eval!(f.get_dest_addr());
         force_eval!(f.get_src_pan_id());
         force_eval!(f.get_src_addr());
+        force_eval!(f.get_aux_security_header());
         force_eval!(f.header());
         force_eval!(f.payload());
     }
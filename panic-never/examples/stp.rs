@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use cortex_m::asm;
+use cortex_m_rt::{entry, exception};
+use panic_never::force_eval;
+
+use jnet::stp;
+
+const LEN: usize = 128;
+static mut BUFFER: [u8; LEN] = [0; LEN];
+static mut BPDU: Option<stp::Bpdu<&'static mut [u8], stp::Config>> = None;
+
+#[exception]
+unsafe fn SysTick() {
+    if let Ok(b) = stp::Bpdu::parse(&mut BUFFER[..]) {
+        force_eval!(b.get_version());
+        force_eval!(b.get_type());
+
+        if let Ok(b) = b.downcast() {
+            BPDU = Some(b);
+        }
+    } else {
+        asm::nop();
+    }
+}
+
+#[exception]
+unsafe fn SVCall() {
+    if let Some(b) = BPDU.take() {
+        force_eval!(b.get_flags());
+        force_eval!(b.get_topology_change());
+        force_eval!(b.get_topology_change_ack());
+        force_eval!(b.get_root_id());
+        force_eval!(b.get_root_path_cost());
+        force_eval!(b.get_bridge_id());
+        force_eval!(b.get_port_id());
+        force_eval!(b.get_message_age());
+        force_eval!(b.get_max_age());
+        force_eval!(b.get_hello_time());
+        force_eval!(b.get_forward_delay());
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+use cortex_m::asm;
+use cortex_m_rt::{entry, exception};
+use panic_never::force_eval;
+
+use jnet::{ipv4, ipv6, tcp};
+
+const LEN: usize = 128;
+static mut BUFFER: [u8; LEN] = [0; LEN];
+
+#[exception]
+unsafe fn SysTick() {
+    if let Ok(s) = tcp::Segment::parse(&BUFFER[..]) {
+        force_eval!(s.get_source());
+        force_eval!(s.get_destination());
+        force_eval!(s.get_sequence_number());
+        force_eval!(s.get_acknowledgment_number());
+        force_eval!(s.get_data_offset());
+        force_eval!(s.get_flags());
+        force_eval!(s.get_syn());
+        force_eval!(s.get_ack());
+        force_eval!(s.get_fin());
+        force_eval!(s.get_rst());
+        force_eval!(s.get_window());
+        force_eval!(s.get_urgent_pointer());
+        force_eval!(s.len());
+        force_eval!(s.options());
+        force_eval!(s.payload());
+        force_eval!(s.verify_ipv4_checksum(ipv4::Addr::UNSPECIFIED, ipv4::Addr::BROADCAST));
+        force_eval!(s.verify_ipv6_checksum(ipv6::Addr::UNSPECIFIED, ipv6::Addr::ALL_NODES));
+
+        for opt in s.options_iter() {
+            force_eval!(opt.get_kind());
+            force_eval!(opt.contents());
+            force_eval!(opt.mss());
+            force_eval!(opt.window_scale());
+        }
+    } else {
+        asm::nop();
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+use cortex_m::asm;
+use cortex_m_rt::{entry, exception};
+use panic_never::force_eval;
+
+use jnet::udplite;
+
+const LEN: usize = 128;
+static mut BUFFER: [u8; LEN] = [0; LEN];
+static mut PACKET: Option<udplite::Packet<&'static mut [u8]>> = None;
+
+#[exception]
+unsafe fn SysTick() {
+    if let Ok(p) = udplite::Packet::parse(&mut BUFFER[..]) {
+        PACKET = Some(p);
+    } else {
+        asm::nop();
+    }
+}
+
+#[exception]
+unsafe fn SVCall() {
+    if let Some(p) = PACKET.take() {
+        force_eval!(p.get_source());
+        force_eval!(p.get_destination());
+        force_eval!(p.get_checksum_coverage());
+        force_eval!(p.len());
+        force_eval!(p.payload());
+        force_eval!(p.covered());
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+use cortex_m::asm;
+use cortex_m_rt::{entry, exception};
+use panic_never::force_eval;
+
+use jnet::vrrp;
+
+const LEN: usize = 128;
+static mut BUFFER: [u8; LEN] = [0; LEN];
+
+#[exception]
+unsafe fn SysTick() {
+    if let Ok(p) = vrrp::Packet::parse(&BUFFER[..]) {
+        force_eval!(p.get_version());
+        force_eval!(p.get_type());
+        force_eval!(p.get_vrid());
+        force_eval!(p.get_priority());
+        force_eval!(p.get_count());
+        force_eval!(p.get_auth_type());
+        force_eval!(p.get_advertisement_interval());
+        force_eval!(p.get_auth_data());
+        force_eval!(p.verify_checksum());
+
+        for addr in p.ip_addrs() {
+            force_eval!(addr);
+        }
+    } else {
+        asm::nop();
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    loop {}
+}
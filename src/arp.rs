@@ -3,8 +3,10 @@
 //! # References
 //!
 //! - [RFC 826: An Ethernet Address Resolution Protocol][rfc]
+//! - [RFC 2390: Inverse Address Resolution Protocol][rfc2390]
 //!
 //! [rfc]: https://tools.ietf.org/html/rfc826
+//! [rfc2390]: https://tools.ietf.org/html/rfc2390
 
 use core::fmt;
 use core::marker::PhantomData;
@@ -38,6 +40,9 @@ const SPA: Range<usize> = 14..18;
 const THA: Range<usize> = 18..24;
 const TPA: Range<usize> = 24..28;
 
+// An Ethernet/IPv4 ARP packet (28 bytes) padded to the minimum Ethernet payload size
+const MAX_ETHERNET_IPV4_SIZE: usize = 46;
+
 /// [Type state] The Ethernet hardware type
 pub enum Ethernet {}
 
@@ -193,6 +198,43 @@ where
         self.set_tha(mac::Addr([0; 6]));
         self.set_tpa(addr);
     }
+
+    /// InARP request
+    ///
+    /// Asks the host at hardware address `addr` for its protocol address. Shortcut for setting
+    /// these fields
+    ///
+    /// - OPER = InRequest
+    /// - THA = addr
+    /// - TPA = 0.0.0.0
+    pub fn inarp_request(&mut self, addr: mac::Addr) {
+        self.set_oper(Operation::InRequest);
+
+        self.set_tha(addr);
+        self.set_tpa(ipv4::Addr::UNSPECIFIED);
+    }
+
+    /// Turns an InARP request addressed to `mac` into the reply, in place
+    ///
+    /// The requester's SHA and SPA become the THA and TPA; SHA and SPA are set to `mac` and `ip`.
+    /// Returns `Err`, leaving the packet untouched, if this is not an InARP request or if its THA
+    /// is not `mac`
+    pub fn inarp_reply(&mut self, mac: mac::Addr, ip: ipv4::Addr) -> Result<(), ()> {
+        if self.get_oper() != Operation::InRequest || self.get_tha() != mac {
+            return Err(());
+        }
+
+        let tha = self.get_sha();
+        let tpa = self.get_spa();
+
+        self.set_oper(Operation::InReply);
+        self.set_sha(mac);
+        self.set_spa(ip);
+        self.set_tha(tha);
+        self.set_tpa(tpa);
+
+        Ok(())
+    }
 }
 
 /* Unknown - Unknown */
@@ -250,6 +292,10 @@ where
     B: AsSlice<Element = u8>,
 {
     /// Parses bytes into an ARP packet
+    ///
+    /// This rejects packets with a zero HLEN or PLEN, packets whose HLEN (PLEN) doesn't match the
+    /// address size of a known HTYPE (PTYPE) and packets whose canonical length doesn't fit in a
+    /// `u8`
     pub fn parse(bytes: B) -> Result<Self, B> {
         if bytes.as_slice().len() < usize(HEADER_SIZE) {
             // too small; header doesn't fit
@@ -265,8 +311,29 @@ where
         let hlen = p.get_hlen();
         let plen = p.get_plen();
 
+        if hlen == 0 || plen == 0 {
+            // addresses can't be empty
+            return Err(p.buffer);
+        }
+
+        let consistent = match p.get_htype() {
+            HardwareType::Ethernet => hlen == 6,
+            HardwareType::Unknown(_) => true,
+        } && match p.get_ptype() {
+            ether::Type::Ipv4 => plen == 4,
+            ether::Type::Ipv6 => plen == 16,
+            _ => true,
+        };
+
+        if !consistent {
+            return Err(p.buffer);
+        }
+
         let payload_len = 2 * (usize(hlen) + usize(plen));
-        if p.as_slice().len() < usize(HEADER_SIZE) + payload_len {
+        if usize(HEADER_SIZE) + payload_len > usize(u8::max_value()) {
+            // `len` would overflow
+            Err(p.buffer)
+        } else if p.as_slice().len() < usize(HEADER_SIZE) + payload_len {
             // too small; payload doesn't fit
             Err(p.buffer)
         } else {
@@ -289,6 +356,83 @@ where
     pub fn set_ptype(&mut self, ptype: ether::Type) {
         NE::write_u16(&mut self.as_mut_slice()[PTYPE], ptype.into());
     }
+
+    /// Mutable view into the SHA (Sender Hardware Address) field of the payload
+    pub fn sha_mut(&mut self) -> &mut [u8] {
+        let end = usize(self.get_hlen());
+
+        unsafe { self.payload_mut().rtm(..end) }
+    }
+
+    /// Mutable view into the SPA (Sender Protocol Address) field of the payload
+    pub fn spa_mut(&mut self) -> &mut [u8] {
+        let start = usize(self.get_hlen());
+        let end = start + usize(self.get_plen());
+
+        unsafe { self.payload_mut().rm(start..end) }
+    }
+
+    /// Mutable view into the THA (Target Hardware Address) field of the payload
+    pub fn tha_mut(&mut self) -> &mut [u8] {
+        let start = usize(self.get_hlen()) + usize(self.get_plen());
+        let end = start + usize(self.get_hlen());
+
+        unsafe { self.payload_mut().rm(start..end) }
+    }
+
+    /// Mutable view into the TPA (Target Protocol Address) field of the payload
+    pub fn tpa_mut(&mut self) -> &mut [u8] {
+        let start = 2 * usize(self.get_hlen()) + usize(self.get_plen());
+        let end = start + usize(self.get_plen());
+
+        unsafe { self.payload_mut().rm(start..end) }
+    }
+}
+
+impl<B> Packet<B, Unknown, Unknown>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u8>,
+{
+    /* Constructors */
+    /// Transforms the given buffer into an ARP packet with arbitrary hardware and protocol types
+    ///
+    /// This function populates the HTYPE, PTYPE, HLEN and PLEN fields and sets OPER to Request.
+    /// The buffer will be truncated to the size of the packet; the address fields are left
+    /// untouched. Use `Packet::new` for Ethernet / IPv4 packets
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if `hlen` or `plen` is zero, if the length of the packet doesn't
+    /// fit in a `u8` or if the given buffer is not large enough to contain the packet
+    pub fn new_raw(
+        mut buffer: B,
+        htype: HardwareType,
+        ptype: ether::Type,
+        hlen: u8,
+        plen: u8,
+    ) -> Self {
+        assert!(hlen != 0 && plen != 0);
+
+        let len = usize(HEADER_SIZE) + 2 * (usize(hlen) + usize(plen));
+        assert!(len <= usize(u8::max_value()));
+        assert!(buffer.as_slice().len() >= len);
+
+        // NOTE(cast) checked above
+        buffer.truncate(len as u8);
+        let mut packet = Packet {
+            buffer,
+            _htype: PhantomData,
+            _ptype: PhantomData,
+        };
+
+        packet.set_htype(htype);
+        packet.set_ptype(ptype);
+        packet.as_mut_slice()[HLEN] = hlen;
+        packet.as_mut_slice()[PLEN] = plen;
+        packet.set_oper(Operation::Request);
+
+        packet
+    }
 }
 
 impl<B> TryFrom<Packet<B, Unknown, Unknown>> for Packet<B, Ethernet, Ipv4>
@@ -302,6 +446,15 @@ where
             && p.get_ptype() == ether::Type::Ipv4
             && p.get_hlen() == 6
             && p.get_plen() == 4
+            // at most the minimum Ethernet payload; anything bigger is not padding
+            && p.as_slice().len() <= MAX_ETHERNET_IPV4_SIZE
+            && match p.get_oper() {
+                Operation::Request
+                | Operation::Reply
+                | Operation::InRequest
+                | Operation::InReply => true,
+                _ => false,
+            }
         {
             Ok(Packet {
                 buffer: p.buffer,
@@ -358,7 +511,12 @@ where
 
     /// Returns the OPER (OPERation) field of the header
     pub fn get_oper(&self) -> Operation {
-        NE::read_u16(&self.header_()[OPER]).into()
+        self.get_oper_raw().into()
+    }
+
+    /// Returns the raw value of the OPER (OPERation) field of the header
+    pub fn get_oper_raw(&self) -> u16 {
+        NE::read_u16(&self.header_()[OPER])
     }
 
     /// View into the payload
@@ -484,6 +642,10 @@ full_range!(
         Request = 1,
         /// Reply operation
         Reply = 2,
+        /// InARP request operation
+        InRequest = 8,
+        /// InARP reply operation
+        InReply = 9,
     }
 );
 
@@ -555,4 +717,105 @@ mod tests {
         assert_eq!(packet.get_tha(), &TARGET_MAC.0);
         assert_eq!(packet.get_tpa(), &TARGET_IP.0);
     }
+
+    #[test]
+    fn generic() {
+        const SHA: [u8; 6] = [1, 2, 3, 4, 5, 6];
+        const SPA: [u8; 16] = [0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        const TPA: [u8; 16] = [0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+
+        // IPv6 over Ethernet
+        let mut buf = [0; 64];
+        let mut packet = arp::Packet::new_raw(
+            &mut buf[..],
+            arp::HardwareType::Ethernet,
+            ether::Type::Ipv6,
+            6,
+            16,
+        );
+        packet.sha_mut().copy_from_slice(&SHA);
+        packet.spa_mut().copy_from_slice(&SPA);
+        packet.tpa_mut().copy_from_slice(&TPA);
+        assert_eq!(packet.len(), 52);
+        assert_eq!(packet.free().len(), 52);
+
+        let packet = arp::Packet::parse(&buf[..52]).unwrap();
+        assert_eq!(packet.get_ptype(), ether::Type::Ipv6);
+        assert_eq!(packet.get_oper(), arp::Operation::Request);
+        assert_eq!(packet.get_sha(), &SHA);
+        assert_eq!(packet.get_spa(), &SPA);
+        assert_eq!(packet.get_tha(), &[0; 6]);
+        assert_eq!(packet.get_tpa(), &TPA);
+        assert!(packet.downcast().is_err());
+    }
+
+    #[test]
+    fn inarp() {
+        let mut array = [0; SIZE];
+        let mut eth = ether::Frame::new(&mut array[..]);
+        eth.set_destination(TARGET_MAC);
+        eth.set_source(SENDER_MAC);
+        eth.arp(|arp| {
+            arp.set_sha(SENDER_MAC);
+            arp.set_spa(SENDER_IP);
+            arp.inarp_request(TARGET_MAC);
+        });
+        let len = eth.len();
+
+        let mut eth = ether::Frame::parse(&mut array[..usize::from(len)]).unwrap();
+        let mut arp = arp::Packet::parse(eth.payload_mut())
+            .unwrap()
+            .downcast()
+            .unwrap();
+        assert_eq!(arp.get_oper(), arp::Operation::InRequest);
+        assert_eq!(arp.get_tpa(), ipv4::Addr::UNSPECIFIED);
+
+        // not for us
+        assert!(arp.inarp_reply(SENDER_MAC, TARGET_IP).is_err());
+        assert_eq!(arp.get_oper(), arp::Operation::InRequest);
+
+        arp.inarp_reply(TARGET_MAC, TARGET_IP).unwrap();
+        assert_eq!(arp.get_oper(), arp::Operation::InReply);
+        assert_eq!(arp.get_sha(), TARGET_MAC);
+        assert_eq!(arp.get_spa(), TARGET_IP);
+        assert_eq!(arp.get_tha(), SENDER_MAC);
+        assert_eq!(arp.get_tpa(), SENDER_IP);
+
+        // replies are not answered
+        assert!(arp.inarp_reply(TARGET_MAC, TARGET_IP).is_err());
+    }
+
+    #[test]
+    fn validation() {
+        let eth = ether::Frame::parse(&BYTES[..]).unwrap();
+        assert!(arp::Packet::parse(eth.payload())
+            .unwrap()
+            .downcast()
+            .is_ok());
+
+        let mut bytes = *BYTES;
+        let payload = &mut bytes[usize::from(ether::HEADER_SIZE)..];
+
+        // HLEN doesn't match HTYPE
+        payload[4] = 8;
+        assert!(arp::Packet::parse(&payload[..]).is_err());
+
+        // zero PLEN
+        payload[4] = 6;
+        payload[5] = 0;
+        assert!(arp::Packet::parse(&payload[..]).is_err());
+
+        // unknown operation
+        payload[5] = 4;
+        payload[7] = 3;
+        let packet = arp::Packet::parse(&payload[..]).unwrap();
+        assert_eq!(packet.get_oper_raw(), 3);
+        assert!(packet.downcast().is_err());
+
+        // oversized
+        payload[7] = 2;
+        let mut big = [0; 64];
+        big[..payload.len()].copy_from_slice(payload);
+        assert!(arp::Packet::parse(&big[..]).unwrap().downcast().is_err());
+    }
 }
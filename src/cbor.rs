@@ -0,0 +1,101 @@
+//! Minimal CBOR encoder (see RFC 7049)
+
+/* Major types */
+pub const UNSIGNED: u8 = 0;
+pub const NEGATIVE: u8 = 1;
+pub const BYTES: u8 = 2;
+pub const TEXT: u8 = 3;
+pub const ARRAY: u8 = 4;
+pub const MAP: u8 = 5;
+pub const SIMPLE: u8 = 7;
+
+/* Simple values and floating-point numbers */
+const FALSE: u8 = 20;
+const TRUE: u8 = 21;
+const FLOAT32: u8 = 26;
+const FLOAT64: u8 = 27;
+
+pub struct Encoder<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Encoder<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Encoder { buffer, len: 0 }
+    }
+
+    /// Returns the encoded bytes
+    pub fn finish(self) -> &'a [u8] {
+        let Encoder { buffer, len } = self;
+        &buffer[..len]
+    }
+
+    /// Encodes the initial byte (and argument) of a data item
+    pub fn head(&mut self, major: u8, value: u64) -> Result<(), ()> {
+        let major = major << 5;
+
+        // NOTE(cast) all the casts below are checked by the range comparisons
+        if value < 24 {
+            self.raw(&[major | value as u8])
+        } else if value < 0x100 {
+            self.raw(&[major | 24, value as u8])
+        } else if value < 0x1_0000 {
+            self.raw(&[major | 25])?;
+            self.raw(&(value as u16).to_be_bytes())
+        } else if value < 0x1_0000_0000 {
+            self.raw(&[major | 26])?;
+            self.raw(&(value as u32).to_be_bytes())
+        } else {
+            self.raw(&[major | 27])?;
+            self.raw(&value.to_be_bytes())
+        }
+    }
+
+    pub fn int(&mut self, value: i64) -> Result<(), ()> {
+        if value >= 0 {
+            self.head(UNSIGNED, value as u64)
+        } else {
+            // -1 - value
+            self.head(NEGATIVE, !value as u64)
+        }
+    }
+
+    /// Encodes `value` as a single precision float if that's lossless
+    pub fn float(&mut self, value: f64) -> Result<(), ()> {
+        let single = value as f32;
+
+        if f64::from(single) == value || value.is_nan() {
+            self.raw(&[SIMPLE << 5 | FLOAT32])?;
+            self.raw(&single.to_bits().to_be_bytes())
+        } else {
+            self.raw(&[SIMPLE << 5 | FLOAT64])?;
+            self.raw(&value.to_bits().to_be_bytes())
+        }
+    }
+
+    pub fn bool(&mut self, value: bool) -> Result<(), ()> {
+        self.raw(&[SIMPLE << 5 | if value { TRUE } else { FALSE }])
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        self.head(BYTES, bytes.len() as u64)?;
+        self.raw(bytes)
+    }
+
+    pub fn text(&mut self, text: &str) -> Result<(), ()> {
+        self.head(TEXT, text.len() as u64)?;
+        self.raw(text.as_bytes())
+    }
+
+    /// Copies already encoded bytes
+    pub fn raw(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        let end = self.len + bytes.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(())?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
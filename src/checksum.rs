@@ -0,0 +1,203 @@
+//! Internet checksum
+//!
+//! This is the checksum used by IPv4, ICMP, ICMPv6, UDP and UDP-Lite, among others
+//!
+//! # References
+//!
+//! - [RFC 1071: Computing the Internet Checksum][rfc]
+//!
+//! [rfc]: https://tools.ietf.org/html/rfc1071
+//!
+//! # Example
+//!
+//! ```
+//! use jnet::{checksum, ipv4};
+//!
+//! let header = [
+//!     0x45, 0x00, 0x00, 0x73, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0xb8, 0x61, 0xc0, 0xa8, 0x00,
+//!     0x01, 0xc0, 0xa8, 0x00, 0xc7,
+//! ];
+//!
+//! // compute the checksum, skipping the checksum field
+//! let mut state = checksum::State::new();
+//! state.push(&header[..10]);
+//! state.push(&header[12..]);
+//! assert_eq!(state.finish(), 0xb861);
+//!
+//! // the checksum of data that includes a valid checksum field is zero
+//! let mut state = checksum::State::new();
+//! state.push(&header);
+//! assert_eq!(state.finish(), 0);
+//! ```
+
+use byteorder::{ByteOrder, NetworkEndian as NE};
+use cast::{u16, u32};
+
+use crate::{ipv4, ipv6, traits::UxxExt};
+
+/// Running state of an Internet checksum computation
+///
+/// Data can be pushed in pieces of any length; an odd byte left over by one `push` is combined
+/// with the first byte of the next one
+#[derive(Clone, Copy, Debug, Default)]
+pub struct State {
+    sum: u32,
+    // odd byte left over by the last `push`
+    odd: Option<u8>,
+}
+
+impl State {
+    /// Starts a new checksum computation
+    pub fn new() -> Self {
+        State { sum: 0, odd: None }
+    }
+
+    /// Starts a new checksum computation with the IPv4 pseudo-header used by UDP and UDP-Lite
+    ///
+    /// `len` is the length of the transport layer packet (header + payload)
+    pub fn ipv4_pseudo_header(
+        src: ipv4::Addr,
+        dest: ipv4::Addr,
+        protocol: ipv4::Protocol,
+        len: u16,
+    ) -> Self {
+        let mut state = State::new();
+        state.push(&src.0);
+        state.push(&dest.0);
+        state.push_u16(u16(u8::from(protocol)));
+        state.push_u16(len);
+        state
+    }
+
+    /// Starts a new checksum computation with the IPv6 pseudo-header used by ICMPv6, UDP and
+    /// UDP-Lite (see Section 8.1 of RFC 8200)
+    ///
+    /// `len` is the length of the upper layer packet (header + payload)
+    pub fn ipv6_pseudo_header(
+        src: ipv6::Addr,
+        dest: ipv6::Addr,
+        next_header: ipv6::NextHeader,
+        len: u32,
+    ) -> Self {
+        let mut state = State::new();
+        state.push(&src.0);
+        state.push(&dest.0);
+        state.push_u16(len.high());
+        state.push_u16(len.low());
+        state.push_u16(u16(u8::from(next_header)));
+        state
+    }
+
+    /// Adds `bytes` to the checksum
+    pub fn push(&mut self, mut bytes: &[u8]) {
+        if let Some(odd) = self.odd.take() {
+            if let Some((first, rest)) = bytes.split_first() {
+                self.add(u16(odd) << 8 | u16(*first));
+                bytes = rest;
+            } else {
+                self.odd = Some(odd);
+                return;
+            }
+        }
+
+        let mut chunks = bytes.chunks_exact(2);
+        for chunk in &mut chunks {
+            self.add(NE::read_u16(chunk));
+        }
+
+        self.odd = chunks.remainder().first().cloned();
+    }
+
+    /// Adds a 16-bit word to the checksum
+    ///
+    /// NOTE this must not be called after an odd number of bytes have been `push`-ed
+    pub fn push_u16(&mut self, word: u16) {
+        debug_assert!(self.odd.is_none());
+
+        self.add(word);
+    }
+
+    /// Finishes the computation and returns the checksum
+    ///
+    /// An odd trailing byte is padded with zero
+    pub fn finish(mut self) -> u16 {
+        if let Some(odd) = self.odd.take() {
+            self.add(u16(odd) << 8);
+        }
+
+        let mut sum = self.sum;
+        while sum.high() != 0 {
+            sum = u32(sum.low()) + u32(sum.high());
+        }
+
+        !sum.low()
+    }
+
+    fn add(&mut self, word: u16) {
+        // end-around carry (one's complement addition)
+        let (sum, carry) = self.sum.overflowing_add(u32(word));
+        self.sum = sum + u32::from(carry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::State;
+    use crate::{ipv4, ipv6};
+
+    #[test]
+    fn odd_pieces() {
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x05];
+
+        let mut whole = State::new();
+        whole.push(&bytes);
+
+        let mut pieces = State::new();
+        pieces.push(&bytes[..1]);
+        pieces.push(&[]);
+        pieces.push(&bytes[1..4]);
+        pieces.push(&bytes[4..]);
+
+        // 0x0102 + 0x0304 + 0x0500
+        assert_eq!(whole.finish(), !0x0906);
+        assert_eq!(pieces.finish(), !0x0906);
+    }
+
+    #[test]
+    fn pseudo_header() {
+        let src = ipv4::Addr([192, 168, 0, 1]);
+        let dest = ipv4::Addr([192, 168, 0, 199]);
+
+        let mut state = State::new();
+        state.push(&src.0);
+        state.push(&dest.0);
+        state.push(&[0, 17, 0, 8]);
+        assert_eq!(
+            State::ipv4_pseudo_header(src, dest, ipv4::Protocol::Udp, 8).finish(),
+            state.finish()
+        );
+
+        let src = ipv6::Addr::LOOPBACK;
+        let dest = ipv6::Addr::UNSPECIFIED;
+
+        let mut state = State::new();
+        state.push(&src.0);
+        state.push(&dest.0);
+        state.push(&[0, 1, 0, 8, 0, 58]);
+        assert_eq!(
+            State::ipv6_pseudo_header(src, dest, ipv6::NextHeader::Ipv6Icmp, 0x1_0008).finish(),
+            state.finish()
+        );
+    }
+
+    #[test]
+    fn carry() {
+        let mut state = State::new();
+        for _ in 0..100_000 {
+            state.push_u16(0xffff);
+        }
+        state.push_u16(1);
+
+        assert_eq!(state.finish(), !1);
+    }
+}
@@ -5,6 +5,10 @@
 //! - [RFC 7252: The Constrained Application Protocol (CoAP)][rfc]
 //!
 //! [rfc]: https://tools.ietf.org/html/rfc7252
+//!
+//! - [RFC 8613: Object Security for Constrained RESTful Environments (OSCORE)][rfc8613]
+//!
+//! [rfc8613]: https://tools.ietf.org/html/rfc8613
 
 use core::{fmt, marker::PhantomData, ops::Range, option::Option as CoreOption, str};
 
@@ -13,11 +17,25 @@ use byteorder::{ByteOrder, NetworkEndian as NE};
 use cast::{u16, u8, usize};
 use owning_slice::Truncate;
 
-use crate::traits::{TryFrom, UncheckedIndex};
+use crate::{
+    ipv4, ipv6,
+    rng::Rng,
+    traits::{TryFrom, UncheckedIndex},
+};
+
+pub mod oscore;
 
 /// CoAP default UDP port
 pub const PORT: u16 = 5683;
 
+// Section 12.8
+/// "All CoAP Nodes" IPv4 multicast address
+pub const ALL_COAP_NODES_IPV4: ipv4::Addr = ipv4::Addr([224, 0, 1, 187]);
+
+/// "All CoAP Nodes" link-local IPv6 multicast address
+pub const ALL_COAP_NODES_IPV6: ipv6::Addr =
+    ipv6::Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xfd]);
+
 /* Message format */
 const VER_T_TKL: usize = 0;
 mod tkl {
@@ -86,9 +104,83 @@ const LENGTH16: u8 = 14;
 // const ACK_RANDOM_FACTOR: f32 = 1.5;
 // const MAX_RETRANSMIT: u8 = 4;
 // const NSTART: u8 = 1;
-// const DEFAULT_LEISURE: u16 = 5_000; // ms
 // const PROBING_RATE: u8 = 1; // byte / second
 
+/// Default Leisure, in milliseconds: the period over which responses to a multicast request are
+/// spread (see Section 8.2 of RFC 7252)
+pub const DEFAULT_LEISURE: u32 = 5_000;
+
+/// Computes the Leisure, in milliseconds, from an estimate of the response size `s` (bytes), of
+/// the size of the multicast group `g` and of the data rate `r` (bytes / second) of the link
+///
+/// Returns `DEFAULT_LEISURE` if `r` is zero. See Section 8.2 of RFC 7252
+pub fn leisure(s: u32, g: u32, r: u32) -> u32 {
+    if r == 0 {
+        DEFAULT_LEISURE
+    } else {
+        // NOTE(cast) saturates at `u32::MAX` milliseconds
+        let ms = (u64::from(s) * u64::from(g)).saturating_mul(1_000) / u64::from(r);
+        if ms > u64::from(u32::max_value()) {
+            u32::max_value()
+        } else {
+            ms as u32
+        }
+    }
+}
+
+/// Picks a random delay, in milliseconds, before sending the response to a multicast request
+///
+/// The delay is (roughly) uniformly distributed over `0..leisure`. Returns `0` if `leisure` is `0`
+pub fn response_delay<R>(leisure: u32, rng: &mut R) -> u32
+where
+    R: Rng,
+{
+    if leisure == 0 {
+        0
+    } else {
+        rng.next_u32() % leisure
+    }
+}
+
+/// Message ID generator
+///
+/// Message IDs are handed out sequentially but, as recommended in Section 4.4 of RFC 7252, the
+/// sequence starts at a random value so IDs are not reused across reboots
+pub struct MessageIds {
+    next: u16,
+}
+
+impl MessageIds {
+    /// Creates a generator that starts at a random Message ID
+    pub fn new<R>(rng: &mut R) -> Self
+    where
+        R: Rng,
+    {
+        MessageIds {
+            // NOTE(cast) intended truncation
+            next: rng.next_u32() as u16,
+        }
+    }
+
+    /// Returns the next Message ID
+    pub fn next_id(&mut self) -> u16 {
+        let id = self.next;
+        self.next = id.wrapping_add(1);
+        id
+    }
+}
+
+/// Should a server send a response with code `code` to a request that arrived via multicast?
+///
+/// Servers shouldn't reply to a multicast request with an Empty ACK or Reset message (see Section
+/// 8.2 of RFC 7252) and should suppress error responses, which would only flood the requester
+/// (see Section 2.7 of RFC 7390). Responses that are sent must be Non-confirmable and should be
+/// delayed by `response_delay`
+pub fn respond_to_multicast(code: Code) -> bool {
+    // only success (2.xx) responses
+    code.class() == 2
+}
+
 /// CoAP (version 1) message
 // NOTE Invariants
 // - Options are always valid. For example, this means that the reserved bit pattern (0b1111)
@@ -191,6 +283,78 @@ where
         }
     }
 
+    /// Checks that all the critical options of this message are in the `supported` list
+    ///
+    /// Returns the first unrecognized critical option, if any. As per Section 5.4.1 of RFC 7252,
+    /// a Confirmable request that contains such an option must be answered with a 4.02 (Bad
+    /// Option) response; any other message must be rejected. Unrecognized elective options can be
+    /// silently ignored.
+    pub fn check_critical_options(&self, supported: &[OptionNumber]) -> Result<(), OptionNumber> {
+        for opt in self.options() {
+            let number = opt.number();
+            if number.is_critical() && !supported.contains(&number) {
+                return Err(number);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the path and query encoded in the Uri-Path and Uri-Query options of this message
+    /// into `buf` and returns it as a string, e.g. `/sensors/temp?unit=c`
+    ///
+    /// Characters that are not allowed in the path or query components of a URI are
+    /// percent-encoded (see Section 6.5 of RFC 7252). A message without Uri-Path options has the
+    /// path `/`.
+    ///
+    /// Returns `Err` if `buf` is too small to hold the URI
+    pub fn uri<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str, ()> {
+        let mut len = 0;
+        let mut push = |byte: u8| -> Result<(), ()> {
+            *buf.get_mut(len).ok_or(())? = byte;
+            len += 1;
+            Ok(())
+        };
+
+        let mut has_path = false;
+        let mut has_query = false;
+        for opt in self.options() {
+            let number = opt.number();
+            let query = if number == OptionNumber::UriPath {
+                push(b'/')?;
+                has_path = true;
+                false
+            } else if number == OptionNumber::UriQuery {
+                if !has_path {
+                    push(b'/')?;
+                    has_path = true;
+                }
+                push(if has_query { b'&' } else { b'?' })?;
+                has_query = true;
+                true
+            } else {
+                continue;
+            };
+
+            for byte in opt.value() {
+                if is_uri_char(*byte, query) {
+                    push(*byte)?;
+                } else {
+                    push(b'%')?;
+                    push(HEX[usize(*byte >> 4)])?;
+                    push(HEX[usize(*byte & 0xf)])?;
+                }
+            }
+        }
+
+        if !has_path {
+            push(b'/')?;
+        }
+
+        // NOTE(unsafe) only ASCII characters have been written into `buf`
+        Ok(unsafe { str::from_utf8_unchecked(&buf[..len]) })
+    }
+
     /* Private */
     fn as_slice(&self) -> &[u8] {
         self.buffer.as_slice()
@@ -248,6 +412,16 @@ where
         unsafe { self.as_mut_slice().rm(start..end) }
     }
 
+    /// Fills the Token field with random bytes
+    ///
+    /// Tokens of requests must be hard to guess; see Section 5.3.1 of RFC 7252
+    pub fn set_random_token<R>(&mut self, rng: &mut R)
+    where
+        R: Rng,
+    {
+        rng.fill_bytes(self.token_mut())
+    }
+
     /* Private */
     fn as_mut_slice(&mut self) -> &mut [u8] {
         self.buffer.as_mut_slice()
@@ -453,6 +627,56 @@ where
     /// - if `number` is smaller than the highest option number already contained in the message
     /// - if there's no space in the message to add the option
     pub fn add_option(&mut self, number: OptionNumber, value: &[u8]) {
+        let len = u16(value.len()).unwrap();
+        self.add_option_with(number, len, |buf| buf.copy_from_slice(value));
+    }
+
+    /// Adds the Uri-Path and Uri-Query options that encode `uri`
+    ///
+    /// `uri` is the path and query part of a CoAP URI, e.g. `/sensors/temp?unit=c`. Each path
+    /// segment becomes a Uri-Path option and each `&`-separated query argument becomes a Uri-Query
+    /// option; percent-encoded characters are decoded (see Section 6.4 of RFC 7252).
+    ///
+    /// Returns `Err` if `uri` doesn't start with `/`, contains an invalid percent-encoding or a
+    /// segment that's longer than 255 bytes; in that case no option is added.
+    ///
+    /// *HEADS UP* This method will cause the first bytes of the payload to be lost
+    ///
+    /// # Panics
+    ///
+    /// This method panics
+    ///
+    /// - if the message already contains an option with a number higher than Uri-Path
+    /// - if there's no space in the message to add the options
+    pub fn add_uri(&mut self, uri: &str) -> Result<(), ()> {
+        // validate everything before adding any option
+        uri_options(uri.as_bytes(), |_, segment| {
+            if percent_decoded_len(segment)? > 255 {
+                Err(())
+            } else {
+                Ok(())
+            }
+        })?;
+
+        uri_options(uri.as_bytes(), |number, segment| {
+            let len = percent_decoded_len(segment)?;
+            self.add_option_with(number, len, |buf| percent_decode(segment, buf));
+            Ok(())
+        })
+    }
+
+    /// Removes all the options this message has
+    pub fn clear_options(&mut self) {
+        self.number = 0;
+        self.marker = u16(self.options_start());
+    }
+
+    /* Private */
+    // adds an option with a value of length `len` and lets `f` fill in the value
+    fn add_option_with<F>(&mut self, number: OptionNumber, len: u16, f: F)
+    where
+        F: FnOnce(&mut [u8]),
+    {
         /// Number of bytes required to encode `x`
         fn nbytes(x: u16) -> u16 {
             if x < OFFSET8 {
@@ -468,7 +692,6 @@ where
         let nr: u16 = number.into();
         let delta = nr.checked_sub(self.number).unwrap();
 
-        let len = u16(value.len()).unwrap();
         let sz = 1 + nbytes(delta) + nbytes(len) + len;
 
         let start = usize(self.marker);
@@ -511,13 +734,7 @@ where
         }
 
         // fill in the value
-        self.as_mut_slice()[cursor..end].copy_from_slice(value);
-    }
-
-    /// Removes all the options this message has
-    pub fn clear_options(&mut self) {
-        self.number = 0;
-        self.marker = u16(self.options_start());
+        f(&mut self.as_mut_slice()[cursor..end]);
     }
 }
 
@@ -648,6 +865,101 @@ where
     }
 }
 
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+// calls `f` on each, still percent-encoded, Uri-Path and Uri-Query segment of `uri`
+fn uri_options<F>(uri: &[u8], mut f: F) -> Result<(), ()>
+where
+    F: FnMut(OptionNumber, &[u8]) -> Result<(), ()>,
+{
+    let uri = match uri.split_first() {
+        Some((b'/', uri)) => uri,
+        _ => return Err(()),
+    };
+
+    let (path, query) = match uri.iter().position(|byte| *byte == b'?') {
+        Some(pos) => (&uri[..pos], &uri[pos + 1..]),
+        None => (uri, &[][..]),
+    };
+
+    // `/` alone has no Uri-Path options
+    if !path.is_empty() {
+        for segment in path.split(|byte| *byte == b'/') {
+            f(OptionNumber::UriPath, segment)?;
+        }
+    }
+
+    if !query.is_empty() {
+        for argument in query.split(|byte| *byte == b'&') {
+            f(OptionNumber::UriQuery, argument)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn hex_value(byte: u8) -> CoreOption<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+// length of `s` after percent-decoding it
+fn percent_decoded_len(s: &[u8]) -> Result<u16, ()> {
+    let mut len: usize = 0;
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'%' {
+            match (s.get(i + 1), s.get(i + 2)) {
+                (Some(hi), Some(lo)) if hex_value(*hi).is_some() && hex_value(*lo).is_some() => {}
+                _ => return Err(()),
+            }
+
+            i += 3;
+        } else {
+            i += 1;
+        }
+
+        len += 1;
+    }
+
+    u16(len).map_err(|_| ())
+}
+
+// NOTE `s` must have been validated with `percent_decoded_len`
+fn percent_decode(s: &[u8], out: &mut [u8]) {
+    let mut bytes = s.iter();
+    for byte in out {
+        *byte = match bytes.next() {
+            Some(b'%') => {
+                let hi = bytes.next().cloned().and_then(hex_value).unwrap_or(0);
+                let lo = bytes.next().cloned().and_then(hex_value).unwrap_or(0);
+                hi << 4 | lo
+            }
+            Some(byte) => *byte,
+            None => 0,
+        };
+    }
+}
+
+// can `byte` appear, without percent-encoding, in a path segment or (if `query` is `true`) in a
+// query argument? (see Section 3.3 and 3.4 of RFC 3986)
+fn is_uri_char(byte: u8, query: bool) -> bool {
+    match byte {
+        // unreserved
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => true,
+        // sub-delims (`&` separates query arguments)
+        b'!' | b'$' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'=' => true,
+        b'&' => !query,
+        b':' | b'@' => true,
+        b'/' | b'?' => query,
+        _ => false,
+    }
+}
+
 /// A CoAP option
 pub struct Option<'a> {
     number: u16,
@@ -932,6 +1244,8 @@ full_range!(
         UriPort = 7,
         /// Location-Path
         LocationPath = 8,
+        /// OSCORE (see RFC 8613)
+        Oscore = 9,
         /// Uri-Path
         UriPath = 11,
         /// Content-Format
@@ -976,7 +1290,13 @@ impl OptionNumber {
 
     /// Is this option UnSafe to forward?
     pub fn is_unsafe(&self) -> bool {
-        u16::from(*self) & 2 == 1
+        u16::from(*self) & 2 != 0
+    }
+
+    /// Is this a safe-to-forward option that is not part of the Cache-Key? (see Section 5.4.6 of
+    /// RFC 7252)
+    pub fn is_no_cache_key(&self) -> bool {
+        u16::from(*self) & 0x1e == 0x1c
     }
 }
 
@@ -996,6 +1316,10 @@ full_range!(
         ApplicationExi = 47,
         /// application/json
         ApplicationJson = 50,
+        /// application/senml+json
+        ApplicationSenmlJson = 110,
+        /// application/senml+cbor
+        ApplicationSenmlCbor = 112,
     }
 );
 
@@ -1072,6 +1396,125 @@ mod tests {
         assert!(coap.options().next().is_none());
     }
 
+    #[test]
+    fn option_classes() {
+        use crate::coap::OptionNumber;
+
+        assert!(OptionNumber::UriHost.is_critical());
+        assert!(OptionNumber::UriHost.is_unsafe());
+        assert!(!OptionNumber::ETag.is_critical());
+        assert!(!OptionNumber::ETag.is_unsafe());
+        assert!(OptionNumber::ProxyUri.is_unsafe());
+        assert!(!OptionNumber::MaxAge.is_critical());
+        assert!(OptionNumber::MaxAge.is_unsafe());
+        assert!(OptionNumber::Size1.is_no_cache_key());
+        assert!(!OptionNumber::UriQuery.is_no_cache_key());
+
+        let mut buf = [0; 64];
+        let mut coap = coap::Message::new(&mut buf[..], 0);
+        coap.add_option(OptionNumber::ETag, &[1]);
+        coap.add_option(OptionNumber::UriPath, b"a");
+        coap.add_option(OptionNumber::from(2049), &[]);
+
+        let supported = [OptionNumber::UriPath];
+        assert_eq!(
+            coap.check_critical_options(&supported),
+            Err(OptionNumber::from(2049))
+        );
+
+        coap.clear_options();
+        coap.add_option(OptionNumber::ETag, &[1]);
+        coap.add_option(OptionNumber::UriPath, b"a");
+        coap.add_option(OptionNumber::from(2048), &[]);
+        assert_eq!(coap.check_critical_options(&supported), Ok(()));
+        assert_eq!(coap.check_critical_options(&[]), Err(OptionNumber::UriPath));
+    }
+
+    #[test]
+    fn multicast() {
+        assert_eq!(coap::leisure(100, 20, 1_000), 2_000);
+        assert_eq!(coap::leisure(100, 20, 0), coap::DEFAULT_LEISURE);
+        assert_eq!(
+            coap::leisure(u32::max_value(), u32::max_value(), 1),
+            u32::max_value()
+        );
+
+        let mut rng = crate::rng::tests::XorShift(1);
+        // the first number is 270_369
+        assert_eq!(coap::response_delay(5_000, &mut rng), 369);
+        assert_eq!(coap::response_delay(0, &mut rng), 0);
+        for _ in 0..16 {
+            assert!(coap::response_delay(100, &mut rng) < 100);
+        }
+
+        assert!(coap::respond_to_multicast(coap::Response::Content.into()));
+        assert!(!coap::respond_to_multicast(coap::Response::NotFound.into()));
+        assert!(!coap::respond_to_multicast(
+            coap::Response::InternalServerError.into()
+        ));
+        assert!(!coap::respond_to_multicast(coap::Code::EMPTY));
+    }
+
+    #[test]
+    fn randomness() {
+        use crate::rng::tests::XorShift;
+
+        let mut rng = XorShift(1);
+        let mut ids = coap::MessageIds::new(&mut rng);
+        let first = ids.next_id();
+        assert_eq!(ids.next_id(), first.wrapping_add(1));
+
+        // a different seed starts elsewhere
+        assert!(coap::MessageIds::new(&mut XorShift(2)).next_id() != first);
+
+        let mut buf = [0; 16];
+        let mut coap = coap::Message::new(&mut buf[..], 8);
+        coap.set_random_token(&mut rng);
+        let mut token = [0; 8];
+        token.copy_from_slice(coap.token());
+        coap.set_random_token(&mut rng);
+        assert!(coap.token() != &token[..]);
+    }
+
+    #[test]
+    fn uri() {
+        let mut buf = [0; 128];
+        let mut coap = coap::Message::new(&mut buf[..], 0);
+
+        assert!(coap.add_uri("sensors").is_err());
+        assert!(coap.add_uri("/sensors/temp%2").is_err());
+        assert!(coap.add_uri("/sensors?unit=%zz").is_err());
+        assert!(coap.options().next().is_none());
+
+        coap.add_uri("/sensors/temp%20%2Fc?unit=c&q=a/b").unwrap();
+
+        let mut options = coap.options();
+        let opt = options.next().unwrap();
+        assert_eq!(opt.number(), coap::OptionNumber::UriPath);
+        assert_eq!(opt.value(), b"sensors");
+        let opt = options.next().unwrap();
+        assert_eq!(opt.number(), coap::OptionNumber::UriPath);
+        assert_eq!(opt.value(), b"temp /c");
+        let opt = options.next().unwrap();
+        assert_eq!(opt.number(), coap::OptionNumber::UriQuery);
+        assert_eq!(opt.value(), b"unit=c");
+        let opt = options.next().unwrap();
+        assert_eq!(opt.number(), coap::OptionNumber::UriQuery);
+        assert_eq!(opt.value(), b"q=a/b");
+        assert!(options.next().is_none());
+
+        let m = coap.no_payload();
+        let mut uri = [0; 64];
+        assert_eq!(m.uri(&mut uri), Ok("/sensors/temp%20%2Fc?unit=c&q=a/b"));
+        assert!(m.uri(&mut uri[..8]).is_err());
+
+        let mut buf = [0; 128];
+        let mut coap = coap::Message::new(&mut buf[..], 0);
+        coap.add_uri("/").unwrap();
+        assert!(coap.options().next().is_none());
+        assert_eq!(coap.uri(&mut uri), Ok("/"));
+    }
+
     #[test]
     fn parse() {
         const TYPE: coap::Type = coap::Type::Confirmable;
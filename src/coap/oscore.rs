@@ -0,0 +1,461 @@
+//! OSCORE: Object Security for Constrained RESTful Environments
+//!
+//! This module provides the framing of OSCORE protected messages: the OSCORE option value, the
+//! AEAD nonce and the Additional Authenticated Data. The cryptographic algorithm is provided by
+//! the application through the `Aead` trait.
+//!
+//! Deriving the security context (Sender Key, Recipient Key and Common IV) from the master secret,
+//! building the plaintext (inner Code, Class E options and payload) and replay protection are
+//! left to the application.
+//!
+//! # References
+//!
+//! - [RFC 8613: Object Security for Constrained RESTful Environments (OSCORE)][rfc]
+//!
+//! [rfc]: https://tools.ietf.org/html/rfc8613
+
+use crate::cbor;
+
+/* OSCORE option value (Section 6.1) */
+const FLAGS: usize = 0;
+
+mod n {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: u8 = 0;
+    pub const SIZE: u8 = 3;
+}
+
+const K: u8 = 1 << 3;
+const H: u8 = 1 << 4;
+// reserved bits and the extension bit
+const RESERVED: u8 = 0b1110_0000;
+
+/// Maximum length of the Partial IV
+pub const MAX_PARTIAL_IV_SIZE: u8 = 5;
+
+/// Maximum nonce length supported by `seal` and `open`
+pub const MAX_NONCE_SIZE: usize = 16;
+
+// Maximum length of the Additional Authenticated Data supported by `seal` and `open`
+const MAX_AAD_SIZE: usize = 64;
+
+/// The AEAD algorithm used to protect messages
+pub trait Aead {
+    /// COSE algorithm identifier (e.g. `10` for AES-CCM-16-64-128)
+    const ALG: i32;
+
+    /// Length of the nonce, in bytes
+    const NONCE_SIZE: usize;
+
+    /// Length of the authentication tag, in bytes
+    const TAG_SIZE: usize;
+
+    /// Encrypts, in place, the plaintext in `buffer[..buffer.len() - TAG_SIZE]` and writes the
+    /// authentication tag into the last `TAG_SIZE` bytes of `buffer`
+    fn seal(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]);
+
+    /// Verifies the authentication tag at the end of `buffer` and decrypts, in place, the
+    /// ciphertext that precedes it
+    ///
+    /// Returns `Err` if the authentication fails
+    fn open(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Result<(), ()>;
+}
+
+/// The value of an OSCORE option
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OptionValue<'a> {
+    partial_iv: &'a [u8],
+    kid_context: Option<&'a [u8]>,
+    kid: Option<&'a [u8]>,
+}
+
+impl<'a> OptionValue<'a> {
+    /// Creates a new OSCORE option value
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if
+    ///
+    /// - `partial_iv` is longer than `MAX_PARTIAL_IV_SIZE`
+    /// - `kid_context` is longer than 255 bytes
+    pub fn new(partial_iv: &'a [u8], kid_context: Option<&'a [u8]>, kid: Option<&'a [u8]>) -> Self {
+        assert!(partial_iv.len() <= usize::from(MAX_PARTIAL_IV_SIZE));
+        assert!(kid_context.map(|ctxt| ctxt.len() <= 255).unwrap_or(true));
+
+        OptionValue {
+            partial_iv,
+            kid_context,
+            kid,
+        }
+    }
+
+    /// Parses the value of an OSCORE option
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ()> {
+        let flags = match bytes.get(FLAGS) {
+            Some(flags) => *flags,
+            // an empty value means that all the flags are zero
+            None => 0,
+        };
+
+        if flags & RESERVED != 0 {
+            return Err(());
+        }
+
+        let n = usize::from(get!(flags, n));
+        if n > usize::from(MAX_PARTIAL_IV_SIZE) {
+            return Err(());
+        }
+
+        let rest = bytes.get(FLAGS + 1..).unwrap_or(&[]);
+        if rest.len() < n {
+            return Err(());
+        }
+        let (partial_iv, mut rest) = rest.split_at(n);
+
+        let kid_context = if flags & H != 0 {
+            let (s, tail) = rest.split_first().ok_or(())?;
+            let s = usize::from(*s);
+
+            if tail.len() < s {
+                return Err(());
+            }
+
+            let (kid_context, tail) = tail.split_at(s);
+            rest = tail;
+            Some(kid_context)
+        } else {
+            None
+        };
+
+        let kid = if flags & K != 0 {
+            Some(rest)
+        } else if rest.is_empty() {
+            None
+        } else {
+            return Err(());
+        };
+
+        Ok(OptionValue {
+            partial_iv,
+            kid_context,
+            kid,
+        })
+    }
+
+    /// Returns the Partial IV
+    pub fn partial_iv(&self) -> &'a [u8] {
+        self.partial_iv
+    }
+
+    /// Returns the 'kid context', if present
+    pub fn kid_context(&self) -> Option<&'a [u8]> {
+        self.kid_context
+    }
+
+    /// Returns the Key ID, if present
+    pub fn kid(&self) -> Option<&'a [u8]> {
+        self.kid
+    }
+
+    /// Returns the length of the encoded option value
+    pub fn len(&self) -> usize {
+        let len = self.partial_iv.len()
+            + self.kid_context.map(|ctxt| 1 + ctxt.len()).unwrap_or(0)
+            + self.kid.map(|kid| kid.len()).unwrap_or(0);
+
+        if len == 0 && self.kid.is_none() {
+            // all the flags are zero so the value is empty
+            0
+        } else {
+            1 + len
+        }
+    }
+
+    /// Encodes this option value into `buffer` and returns the encoded bytes
+    ///
+    /// Returns `Err` if `buffer` is too small
+    pub fn write<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b [u8], ()> {
+        let len = self.len();
+        if buffer.len() < len {
+            return Err(());
+        }
+
+        let buffer = &mut buffer[..len];
+        if len == 0 {
+            return Ok(buffer);
+        }
+
+        let mut flags = 0;
+        // NOTE(cast) `new` checked that the Partial IV fits in the `n` field
+        set!(flags, n, self.partial_iv.len() as u8);
+
+        let mut cursor = FLAGS + 1;
+        buffer[cursor..cursor + self.partial_iv.len()].copy_from_slice(self.partial_iv);
+        cursor += self.partial_iv.len();
+
+        if let Some(kid_context) = self.kid_context {
+            flags |= H;
+            // NOTE(cast) `new` checked that the 'kid context' is at most 255 bytes long
+            buffer[cursor] = kid_context.len() as u8;
+            cursor += 1;
+            buffer[cursor..cursor + kid_context.len()].copy_from_slice(kid_context);
+            cursor += kid_context.len();
+        }
+
+        if let Some(kid) = self.kid {
+            flags |= K;
+            buffer[cursor..].copy_from_slice(kid);
+        }
+
+        buffer[FLAGS] = flags;
+
+        Ok(buffer)
+    }
+}
+
+/// Computes the AEAD nonce from the Common IV, the ID of the endpoint that generated the Partial
+/// IV and the Partial IV (see Section 5.2 of RFC 8613)
+///
+/// The nonce has the length of `common_iv`, which must be the nonce length of the AEAD algorithm
+///
+/// Returns `Err` if `nonce` is too small, `common_iv` is shorter than 7 bytes, `id_piv` is longer
+/// than `common_iv.len() - 6` bytes or `partial_iv` is longer than `MAX_PARTIAL_IV_SIZE`
+pub fn nonce<'b>(
+    common_iv: &[u8],
+    id_piv: &[u8],
+    partial_iv: &[u8],
+    nonce: &'b mut [u8],
+) -> Result<&'b [u8], ()> {
+    let len = common_iv.len();
+    let piv_size = usize::from(MAX_PARTIAL_IV_SIZE);
+    if nonce.len() < len
+        || len < piv_size + 2
+        || id_piv.len() > len - piv_size - 1
+        || partial_iv.len() > piv_size
+    {
+        return Err(());
+    }
+
+    let nonce = &mut nonce[..len];
+    for byte in nonce.iter_mut() {
+        *byte = 0;
+    }
+
+    // NOTE(cast) `id_piv` is shorter than `common_iv`
+    nonce[0] = id_piv.len() as u8;
+    let id_end = len - piv_size;
+    nonce[id_end - id_piv.len()..id_end].copy_from_slice(id_piv);
+    nonce[len - partial_iv.len()..].copy_from_slice(partial_iv);
+
+    for (byte, iv) in nonce.iter_mut().zip(common_iv) {
+        *byte ^= iv;
+    }
+
+    Ok(nonce)
+}
+
+/// Computes the Additional Authenticated Data from the AEAD algorithm identifier and the Key ID
+/// and Partial IV of the request (see Section 5.4 of RFC 8613)
+///
+/// This assumes that no Class I options are used
+///
+/// Returns `Err` if `aad` is too small
+pub fn aad<'b>(
+    alg: i32,
+    request_kid: &[u8],
+    request_piv: &[u8],
+    aad: &'b mut [u8],
+) -> Result<&'b [u8], ()> {
+    // external_aad = [ oscore_version: 1, algorithms: [alg_aead], request_kid, request_piv, options ]
+    let mut external = [0; MAX_AAD_SIZE];
+    let mut cbor = cbor::Encoder::new(&mut external);
+    cbor.head(cbor::ARRAY, 5)?;
+    cbor.head(cbor::UNSIGNED, 1)?;
+    cbor.head(cbor::ARRAY, 1)?;
+    cbor.int(i64::from(alg))?;
+    cbor.bytes(request_kid)?;
+    cbor.bytes(request_piv)?;
+    cbor.bytes(&[])?;
+    let external = cbor.finish();
+
+    // Enc_structure = [ context: "Encrypt0", protected: h'', external_aad: bstr ]
+    let mut cbor = cbor::Encoder::new(aad);
+    cbor.head(cbor::ARRAY, 3)?;
+    cbor.text("Encrypt0")?;
+    cbor.bytes(&[])?;
+    cbor.bytes(external)?;
+
+    Ok(cbor.finish())
+}
+
+/// Encrypts the plaintext in `buffer[..buffer.len() - A::TAG_SIZE]` in place and appends the
+/// authentication tag, producing the payload of the OSCORE message
+///
+/// `id_piv` and `partial_iv` are used to compute the nonce (see `nonce`); `request_kid` and
+/// `request_piv` are used to compute the AAD (see `aad`). When protecting a request they are the
+/// Sender ID and the Partial IV of the request.
+///
+/// Returns `Err` if the nonce or the AAD can't be computed
+pub fn seal<A>(
+    aead: &A,
+    common_iv: &[u8],
+    id_piv: &[u8],
+    partial_iv: &[u8],
+    request_kid: &[u8],
+    request_piv: &[u8],
+    buffer: &mut [u8],
+) -> Result<(), ()>
+where
+    A: Aead,
+{
+    if buffer.len() < A::TAG_SIZE || common_iv.len() != A::NONCE_SIZE {
+        return Err(());
+    }
+
+    let mut nonce_buf = [0; MAX_NONCE_SIZE];
+    let nonce = nonce(common_iv, id_piv, partial_iv, &mut nonce_buf)?;
+    let mut aad_buf = [0; MAX_AAD_SIZE];
+    let aad = aad(A::ALG, request_kid, request_piv, &mut aad_buf)?;
+
+    aead.seal(nonce, aad, buffer);
+    Ok(())
+}
+
+/// Verifies and decrypts, in place, the payload of an OSCORE message
+///
+/// On success, the plaintext is in `buffer[..buffer.len() - A::TAG_SIZE]`. See `seal` for the
+/// meaning of the other arguments.
+///
+/// Returns `Err` if the nonce or the AAD can't be computed or if the authentication fails
+pub fn open<A>(
+    aead: &A,
+    common_iv: &[u8],
+    id_piv: &[u8],
+    partial_iv: &[u8],
+    request_kid: &[u8],
+    request_piv: &[u8],
+    buffer: &mut [u8],
+) -> Result<(), ()>
+where
+    A: Aead,
+{
+    if buffer.len() < A::TAG_SIZE || common_iv.len() != A::NONCE_SIZE {
+        return Err(());
+    }
+
+    let mut nonce_buf = [0; MAX_NONCE_SIZE];
+    let nonce = nonce(common_iv, id_piv, partial_iv, &mut nonce_buf)?;
+    let mut aad_buf = [0; MAX_AAD_SIZE];
+    let aad = aad(A::ALG, request_kid, request_piv, &mut aad_buf)?;
+
+    aead.open(nonce, aad, buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Aead, OptionValue};
+    use crate::coap::oscore;
+
+    // Test Vector 4 of RFC 8613 (Appendix C.4)
+    const COMMON_IV: [u8; 13] = [
+        0x46, 0x22, 0xd4, 0xdd, 0x6d, 0x94, 0x41, 0x68, 0xee, 0xfb, 0x54, 0x98, 0x7c,
+    ];
+
+    // NOT secure; only exercises the framing
+    struct Xor;
+
+    impl Aead for Xor {
+        const ALG: i32 = 10;
+        const NONCE_SIZE: usize = 13;
+        const TAG_SIZE: usize = 1;
+
+        fn seal(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) {
+            let (text, tag) = buffer.split_at_mut(buffer.len() - 1);
+            tag[0] = aad.iter().chain(text.iter()).fold(0, |acc, b| acc ^ b);
+            for (byte, n) in text.iter_mut().zip(nonce.iter().cycle()) {
+                *byte ^= n;
+            }
+        }
+
+        fn open(&self, nonce: &[u8], aad: &[u8], buffer: &mut [u8]) -> Result<(), ()> {
+            let (text, tag) = buffer.split_at_mut(buffer.len() - 1);
+            for (byte, n) in text.iter_mut().zip(nonce.iter().cycle()) {
+                *byte ^= n;
+            }
+
+            if tag[0] == aad.iter().chain(text.iter()).fold(0, |acc, b| acc ^ b) {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    #[test]
+    fn option_value() {
+        let value = OptionValue::new(&[0x14], None, Some(&[][..]));
+        assert_eq!(value.len(), 2);
+
+        let mut buf = [0; 16];
+        let bytes = value.write(&mut buf).unwrap();
+        assert_eq!(bytes, [0x09, 0x14]);
+        assert_eq!(OptionValue::parse(bytes), Ok(value));
+
+        let value = OptionValue::new(&[0x05], Some(&[0x37, 0xcb][..]), Some(&[0x01][..]));
+        let bytes = value.write(&mut buf).unwrap();
+        assert_eq!(bytes, [0x19, 0x05, 0x02, 0x37, 0xcb, 0x01]);
+        assert_eq!(OptionValue::parse(bytes), Ok(value));
+
+        // responses usually have an empty OSCORE option
+        let value = OptionValue::new(&[], None, None);
+        assert_eq!(value.write(&mut buf).unwrap().len(), 0);
+        assert_eq!(OptionValue::parse(&[]), Ok(value));
+
+        // reserved Partial IV length
+        assert!(OptionValue::parse(&[0x06, 0, 0, 0, 0, 0, 0]).is_err());
+        // truncated Partial IV
+        assert!(OptionValue::parse(&[0x02, 0]).is_err());
+        // trailing bytes without the `k` flag
+        assert!(OptionValue::parse(&[0x01, 0, 0]).is_err());
+        // too small
+        assert!(OptionValue::new(&[0x14], None, Some(&[][..]))
+            .write(&mut buf[..1])
+            .is_err());
+    }
+
+    #[test]
+    fn nonce_and_aad() {
+        let mut nonce = [0; 13];
+        assert_eq!(
+            oscore::nonce(&COMMON_IV, &[], &[0x14], &mut nonce).unwrap(),
+            [0x46, 0x22, 0xd4, 0xdd, 0x6d, 0x94, 0x41, 0x68, 0xee, 0xfb, 0x54, 0x98, 0x68]
+        );
+
+        let mut aad = [0; 32];
+        assert_eq!(
+            oscore::aad(10, &[], &[0x14], &mut aad).unwrap(),
+            [
+                0x83, 0x68, 0x45, 0x6e, 0x63, 0x72, 0x79, 0x70, 0x74, 0x30, 0x40, 0x48, 0x85, 0x01,
+                0x81, 0x0a, 0x40, 0x41, 0x14, 0x40,
+            ]
+        );
+        assert!(oscore::aad(10, &[], &[0x14], &mut aad[..19]).is_err());
+    }
+
+    #[test]
+    fn seal_open() {
+        const PLAINTEXT: &[u8] = &[0x01, 0xb3, 0x74, 0x76, 0x31];
+
+        let mut buf = [0; 6];
+        buf[..5].copy_from_slice(PLAINTEXT);
+
+        oscore::seal(&Xor, &COMMON_IV, &[], &[0x14], &[], &[0x14], &mut buf).unwrap();
+        assert!(buf[..5] != *PLAINTEXT);
+
+        let mut tampered = buf;
+        assert!(oscore::open(&Xor, &COMMON_IV, &[], &[0x15], &[], &[0x15], &mut tampered).is_err());
+
+        oscore::open(&Xor, &COMMON_IV, &[], &[0x14], &[], &[0x14], &mut buf).unwrap();
+        assert_eq!(&buf[..5], PLAINTEXT);
+    }
+}
@@ -0,0 +1,679 @@
+//! DHCPv6: Dynamic Host Configuration Protocol for IPv6
+//!
+//! NOTE only the messages needed for stateless configuration (Information-Request / Reply; see
+//! Section 6.1 of RFC 8415) are supported. Retransmission of requests and refreshing the
+//! configuration after the 'Information Refresh Time' are left to the application.
+//!
+//! # References
+//!
+//! - [RFC 8415: Dynamic Host Configuration Protocol for IPv6 (DHCPv6)][rfc]
+//!
+//! [rfc]: https://tools.ietf.org/html/rfc8415
+
+use core::{
+    fmt,
+    ops::{Range, RangeFrom},
+    slice::ChunksExact,
+};
+
+use as_slice::{AsMutSlice, AsSlice};
+use byteorder::{ByteOrder, NetworkEndian as NE};
+use cast::{u16, usize};
+use owning_slice::Truncate;
+
+use crate::{ipv6, mac, rng::Rng, traits::UncheckedIndex};
+
+/// UDP port on which clients listen for messages
+pub const CLIENT_PORT: u16 = 546;
+
+/// UDP port on which servers and relay agents listen for messages
+pub const SERVER_PORT: u16 = 547;
+
+/// Link-scoped multicast address used by clients to reach all the servers and relay agents
+pub const ALL_DHCP_RELAY_AGENTS_AND_SERVERS: ipv6::Addr =
+    ipv6::Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2]);
+
+/// Default 'Information Refresh Time', in seconds (see Section 21.23 of RFC 8415)
+pub const IRT_DEFAULT: u32 = 86_400;
+
+/// Minimum 'Information Refresh Time', in seconds
+pub const IRT_MINIMUM: u32 = 600;
+
+/// Returns a DUID based on the given link-layer (MAC) address (DUID-LL; see Section 11.4 of RFC
+/// 8415)
+///
+/// This is suitable for use as the 'Client Identifier' of devices without stable storage
+pub fn duid_ll(addr: mac::Addr) -> [u8; 10] {
+    let mut duid = [0; 10];
+    NE::write_u16(&mut duid[0..2], DUID_LL);
+    NE::write_u16(&mut duid[2..4], HARDWARE_TYPE_ETHERNET);
+    duid[4..].copy_from_slice(&addr.0);
+    duid
+}
+
+/// Returns a random 'transaction-id'
+///
+/// A new transaction ID must be picked for every message exchange (retransmissions reuse it); see
+/// Section 16.1 of RFC 8415
+pub fn transaction_id<R>(rng: &mut R) -> u32
+where
+    R: Rng,
+{
+    rng.next_u32() & 0xff_ffff
+}
+
+const DUID_LL: u16 = 3;
+const HARDWARE_TYPE_ETHERNET: u16 = 1;
+
+/* Message format */
+const MSG_TYPE: usize = 0;
+const TRANSACTION_ID: Range<usize> = 1..4;
+const OPTIONS: RangeFrom<usize> = 4..;
+
+/// Size of the DHCPv6 header
+pub const HEADER_SIZE: u8 = OPTIONS.start as u8;
+
+// Option format
+const OPTION_CODE: Range<usize> = 0..2;
+const OPTION_LEN: Range<usize> = 2..4;
+const OPTION_HEADER_SIZE: usize = 4;
+
+/// DHCPv6 client / server message
+pub struct Message<BUFFER>
+where
+    BUFFER: AsSlice<Element = u8>,
+{
+    buffer: BUFFER,
+}
+
+impl<B> Message<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Parses bytes into a DHCPv6 message
+    ///
+    /// This rejects messages that are too short and messages whose options overflow the message
+    ///
+    /// NOTE relay agent messages (Relay-forward and Relay-reply) have a different format and are
+    /// also rejected
+    pub fn parse(bytes: B) -> Result<Self, B> {
+        let slice = bytes.as_slice();
+
+        if slice.len() < usize(HEADER_SIZE) {
+            return Err(bytes);
+        }
+
+        match MessageType::from(slice[MSG_TYPE]) {
+            MessageType::RelayForw | MessageType::RelayRepl => return Err(bytes),
+            _ => {}
+        }
+
+        if Options::are_valid(&slice[OPTIONS]) {
+            Ok(Message { buffer: bytes })
+        } else {
+            Err(bytes)
+        }
+    }
+
+    /* Getters */
+    /// Reads the 'msg-type' field
+    pub fn get_message_type(&self) -> MessageType {
+        unsafe { (*self.as_slice().gu(MSG_TYPE)).into() }
+    }
+
+    /// Reads the 'transaction-id' field
+    pub fn get_transaction_id(&self) -> u32 {
+        unsafe { NE::read_u24(self.as_slice().r(TRANSACTION_ID)) }
+    }
+
+    /// Returns an iterator over the options of this message
+    pub fn options(&self) -> Options<'_> {
+        unsafe { Options::new(self.as_slice().rf(OPTIONS)) }
+    }
+
+    /// Reads the 'Client Identifier' option
+    pub fn get_client_id(&self) -> Option<&[u8]> {
+        self.find(OptionCode::ClientId)
+    }
+
+    /// Reads the 'Server Identifier' option
+    pub fn get_server_id(&self) -> Option<&[u8]> {
+        self.find(OptionCode::ServerId)
+    }
+
+    /// Reads the status code of the 'Status Code' option
+    ///
+    /// NOTE the absence of this option implies success (`0`)
+    pub fn get_status_code(&self) -> Option<u16> {
+        self.find(OptionCode::StatusCode)
+            .and_then(|opt| opt.get(..2).map(NE::read_u16))
+    }
+
+    /// Reads the 'Information Refresh Time' option, in seconds
+    ///
+    /// NOTE if this option is absent `IRT_DEFAULT` should be used; values smaller than
+    /// `IRT_MINIMUM` should be treated as `IRT_MINIMUM`
+    pub fn get_information_refresh_time(&self) -> Option<u32> {
+        self.find(OptionCode::InformationRefreshTime)
+            .and_then(|opt| {
+                if opt.len() == 4 {
+                    Some(NE::read_u32(opt))
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Returns an iterator over the addresses in the 'DNS Recursive Name Server' option
+    pub fn dns_servers(&self) -> Addrs<'_> {
+        let addrs = self.find(OptionCode::DnsServers).unwrap_or(&[]);
+
+        Addrs {
+            chunks: addrs.chunks_exact(16),
+        }
+    }
+
+    /// Returns an iterator over the servers in the 'NTP Server' option
+    pub fn ntp_servers(&self) -> NtpServers<'_> {
+        NtpServers {
+            subopts: self.find(OptionCode::NtpServer).unwrap_or(&[]),
+        }
+    }
+
+    /// Returns the byte representation of this message
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    /// Returns the length (header + options) of this message
+    pub fn len(&self) -> u16 {
+        // NOTE(cast) messages are carried in UDP datagrams
+        self.as_slice().len() as u16
+    }
+
+    /// Frees the underlying buffer
+    pub fn free(self) -> B {
+        self.buffer
+    }
+
+    /* Private */
+    fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    // contents of the first option with the given code
+    fn find(&self, code: OptionCode) -> Option<&[u8]> {
+        self.options()
+            .filter_map(|opt| {
+                if opt.code == code {
+                    Some(opt.contents)
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+}
+
+impl<B> Message<B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /* Constructors */
+    /// Transforms the given buffer into an Information-Request message
+    ///
+    /// The message will contain the 'Client Identifier' option (if `client_id` is not empty),
+    /// the 'Elapsed Time' option and an 'Option Request' option that lists the `requested`
+    /// options. The buffer will be truncated to the size of the message
+    ///
+    /// `elapsed_time` is in hundredths of a second; it must be `0` in the first transmission of
+    /// the message. Only the lower 24 bits of `transaction_id` are used; see `transaction_id` for
+    /// generating one
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the given buffer is not large enough to contain the message
+    pub fn information_request(
+        buffer: B,
+        transaction_id: u32,
+        client_id: &[u8],
+        elapsed_time: u16,
+        requested: &[OptionCode],
+    ) -> Self {
+        let client_id_size = if client_id.is_empty() {
+            0
+        } else {
+            OPTION_HEADER_SIZE + client_id.len()
+        };
+        let size = usize(HEADER_SIZE)
+            + client_id_size
+            + OPTION_HEADER_SIZE
+            + 2
+            + OPTION_HEADER_SIZE
+            + 2 * requested.len();
+        assert!(buffer.as_slice().len() >= size);
+
+        let mut m = Message { buffer };
+        let mut cursor = usize(HEADER_SIZE);
+
+        unsafe {
+            *m.as_mut_slice().gum(MSG_TYPE) = MessageType::InformationRequest.into();
+            NE::write_u24(
+                m.as_mut_slice().rm(TRANSACTION_ID),
+                transaction_id & 0xff_ffff,
+            );
+        }
+
+        if !client_id.is_empty() {
+            m.write_option(&mut cursor, OptionCode::ClientId, client_id.len())
+                .copy_from_slice(client_id);
+        }
+
+        NE::write_u16(
+            m.write_option(&mut cursor, OptionCode::ElapsedTime, 2),
+            elapsed_time,
+        );
+
+        let oro = m.write_option(&mut cursor, OptionCode::OptionRequest, 2 * requested.len());
+        for (chunk, code) in oro.chunks_exact_mut(2).zip(requested) {
+            NE::write_u16(chunk, u16::from(*code));
+        }
+
+        debug_assert_eq!(cursor, size);
+        m.buffer.truncate(u16(cursor).unwrap());
+        m
+    }
+
+    /* Private */
+    // writes the header of an option and returns a view into its (uninitialized) contents
+    fn write_option(&mut self, cursor: &mut usize, code: OptionCode, len: usize) -> &mut [u8] {
+        let start = *cursor;
+        let end = start + OPTION_HEADER_SIZE + len;
+        *cursor = end;
+
+        let opt = &mut self.as_mut_slice()[start..end];
+        NE::write_u16(&mut opt[OPTION_CODE], code.into());
+        NE::write_u16(&mut opt[OPTION_LEN], u16(len).unwrap());
+        &mut opt[OPTION_HEADER_SIZE..]
+    }
+}
+
+impl<B> Message<B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8>,
+{
+    /* Private */
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.buffer.as_mut_slice()
+    }
+}
+
+impl<B> fmt::Debug for Message<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("dhcpv6::Message")
+            .field("message_type", &self.get_message_type())
+            .field("transaction_id", &self.get_transaction_id())
+            .finish()
+    }
+}
+
+/// Iterator over the options of a DHCPv6 message
+pub struct Options<'a> {
+    opts: &'a [u8],
+}
+
+impl<'a> Options<'a> {
+    // NOTE: Caller must ensure that `are_valid` returns `true` before using this as an iterator
+    unsafe fn new(opts: &'a [u8]) -> Self {
+        Options { opts }
+    }
+
+    fn are_valid(mut opts: &'a [u8]) -> bool {
+        while !opts.is_empty() {
+            if opts.len() < OPTION_HEADER_SIZE {
+                return false;
+            }
+
+            let end = OPTION_HEADER_SIZE + usize(NE::read_u16(&opts[OPTION_LEN]));
+            if opts.len() < end {
+                return false;
+            }
+
+            opts = &opts[end..];
+        }
+
+        true
+    }
+}
+
+impl<'a> Iterator for Options<'a> {
+    type Item = DhcpOption<'a>;
+
+    fn next(&mut self) -> Option<DhcpOption<'a>> {
+        if self.opts.is_empty() {
+            None
+        } else {
+            unsafe {
+                let code = OptionCode::from(NE::read_u16(self.opts.r(OPTION_CODE)));
+                let end = OPTION_HEADER_SIZE + usize(NE::read_u16(self.opts.r(OPTION_LEN)));
+                let contents = self.opts.r(OPTION_HEADER_SIZE..end);
+
+                self.opts = self.opts.rf(end..);
+
+                Some(DhcpOption { code, contents })
+            }
+        }
+    }
+}
+
+/// A DHCPv6 option
+pub struct DhcpOption<'a> {
+    code: OptionCode,
+    contents: &'a [u8],
+}
+
+impl<'a> DhcpOption<'a> {
+    /// Returns the option code
+    pub fn get_code(&self) -> OptionCode {
+        self.code
+    }
+
+    /// Returns the contents of the option
+    pub fn contents(&self) -> &'a [u8] {
+        self.contents
+    }
+}
+
+impl<'a> fmt::Debug for DhcpOption<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("dhcpv6::DhcpOption")
+            .field("code", &self.code)
+            .field("contents", &self.contents)
+            .finish()
+    }
+}
+
+/// Iterator over the IPv6 addresses of an option
+pub struct Addrs<'a> {
+    chunks: ChunksExact<'a, u8>,
+}
+
+impl<'a> Iterator for Addrs<'a> {
+    type Item = ipv6::Addr;
+
+    fn next(&mut self) -> Option<ipv6::Addr> {
+        self.chunks
+            .next()
+            .map(|chunk| unsafe { ipv6::Addr(*(chunk.as_ptr() as *const _)) })
+    }
+}
+
+/// Iterator over the suboptions of the 'NTP Server' option
+///
+/// Suboptions with an unknown code or an invalid length are skipped; iteration stops at a truncated
+/// suboption
+pub struct NtpServers<'a> {
+    subopts: &'a [u8],
+}
+
+impl<'a> Iterator for NtpServers<'a> {
+    type Item = NtpServer<'a>;
+
+    fn next(&mut self) -> Option<NtpServer<'a>> {
+        loop {
+            if self.subopts.len() < OPTION_HEADER_SIZE {
+                self.subopts = &[];
+                return None;
+            }
+
+            let code = unsafe { NE::read_u16(self.subopts.r(OPTION_CODE)) };
+            let end =
+                OPTION_HEADER_SIZE + usize(unsafe { NE::read_u16(self.subopts.r(OPTION_LEN)) });
+            if self.subopts.len() < end {
+                // truncated suboption
+                self.subopts = &[];
+                return None;
+            }
+
+            let contents = unsafe { self.subopts.r(OPTION_HEADER_SIZE..end) };
+            self.subopts = unsafe { self.subopts.rf(end..) };
+
+            match (code, contents.len()) {
+                (NTP_SUBOPTION_SRV_ADDR, 16) => {
+                    return Some(NtpServer::Addr(unsafe {
+                        ipv6::Addr(*(contents.as_ptr() as *const _))
+                    }));
+                }
+                (NTP_SUBOPTION_MC_ADDR, 16) => {
+                    return Some(NtpServer::MulticastAddr(unsafe {
+                        ipv6::Addr(*(contents.as_ptr() as *const _))
+                    }));
+                }
+                (NTP_SUBOPTION_SRV_FQDN, _) => return Some(NtpServer::Fqdn(contents)),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A suboption of the 'NTP Server' option (see Section 4 of RFC 5908)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NtpServer<'a> {
+    /// Unicast address of an NTP server
+    Addr(ipv6::Addr),
+    /// Multicast address to which NTP servers send their messages
+    MulticastAddr(ipv6::Addr),
+    /// Domain name of an NTP server, in (uncompressed) wire format
+    Fqdn(&'a [u8]),
+}
+
+const NTP_SUBOPTION_SRV_ADDR: u16 = 1;
+const NTP_SUBOPTION_MC_ADDR: u16 = 2;
+const NTP_SUBOPTION_SRV_FQDN: u16 = 3;
+
+full_range!(
+    u8,
+    /// Message type
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum MessageType {
+        /// Solicit
+        Solicit = 1,
+        /// Advertise
+        Advertise = 2,
+        /// Request
+        Request = 3,
+        /// Confirm
+        Confirm = 4,
+        /// Renew
+        Renew = 5,
+        /// Rebind
+        Rebind = 6,
+        /// Reply
+        Reply = 7,
+        /// Release
+        Release = 8,
+        /// Decline
+        Decline = 9,
+        /// Reconfigure
+        Reconfigure = 10,
+        /// Information-request
+        InformationRequest = 11,
+        /// Relay-forward
+        RelayForw = 12,
+        /// Relay-reply
+        RelayRepl = 13,
+    }
+);
+
+full_range!(
+    u16,
+    /// Option code
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum OptionCode {
+        /// Client Identifier
+        ClientId = 1,
+        /// Server Identifier
+        ServerId = 2,
+        /// Option Request
+        OptionRequest = 6,
+        /// Elapsed Time
+        ElapsedTime = 8,
+        /// Status Code
+        StatusCode = 13,
+        /// DNS Recursive Name Server (RFC 3646)
+        DnsServers = 23,
+        /// Domain Search List (RFC 3646)
+        DomainList = 24,
+        /// Information Refresh Time
+        InformationRefreshTime = 32,
+        /// NTP Server (RFC 5908)
+        NtpServer = 56,
+        /// SOL_MAX_RT
+        SolMaxRt = 82,
+        /// INF_MAX_RT
+        InfMaxRt = 83,
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{dhcpv6, ipv6, mac};
+
+    const MAC: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x00]);
+
+    #[test]
+    fn transaction_id() {
+        use crate::rng::tests::XorShift;
+
+        let mut rng = XorShift(0xdead_beef);
+        let first = dhcpv6::transaction_id(&mut rng);
+        let second = dhcpv6::transaction_id(&mut rng);
+
+        assert!(first <= 0xff_ffff && second <= 0xff_ffff);
+        assert!(first != second);
+    }
+
+    #[test]
+    fn information_request() {
+        let mut buf = [0xff; 64];
+        let client_id = dhcpv6::duid_ll(MAC);
+        let m = dhcpv6::Message::information_request(
+            &mut buf[..],
+            0x12_3456,
+            &client_id,
+            0,
+            &[
+                dhcpv6::OptionCode::DnsServers,
+                dhcpv6::OptionCode::InformationRefreshTime,
+            ],
+        );
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            11, // msg-type
+            0x12, 0x34, 0x56, // transaction-id
+            0, 1, 0, 10, // option: Client Identifier
+            0, 3, 0, 1, 0x20, 0x18, 0x03, 0x01, 0x00, 0x00,
+            0, 8, 0, 2, // option: Elapsed Time
+            0, 0,
+            0, 6, 0, 4, // option: Option Request
+            0, 23, 0, 32,
+        ];
+
+        assert_eq!(m.as_bytes(), expected);
+
+        let m = dhcpv6::Message::parse(m.as_bytes()).unwrap();
+        assert_eq!(
+            m.get_message_type(),
+            dhcpv6::MessageType::InformationRequest
+        );
+        assert_eq!(m.get_transaction_id(), 0x12_3456);
+        assert_eq!(m.get_client_id(), Some(&client_id[..]));
+    }
+
+    #[test]
+    fn reply() {
+        #[rustfmt::skip]
+        const BYTES: &[u8] = &[
+            7, // msg-type
+            0x12, 0x34, 0x56, // transaction-id
+            0, 2, 0, 4, // option: Server Identifier
+            0, 4, 0xab, 0xcd,
+            0, 23, 0, 32, // option: DNS Recursive Name Server
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x53,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x53,
+            0, 32, 0, 4, // option: Information Refresh Time
+            0, 0, 0x0e, 0x10,
+        ];
+
+        let m = dhcpv6::Message::parse(BYTES).unwrap();
+        assert_eq!(m.get_message_type(), dhcpv6::MessageType::Reply);
+        assert_eq!(m.get_transaction_id(), 0x12_3456);
+        assert_eq!(m.get_server_id(), Some(&[0, 4, 0xab, 0xcd][..]));
+        assert_eq!(m.get_status_code(), None);
+        assert_eq!(m.get_information_refresh_time(), Some(3600));
+
+        let mut servers = m.dns_servers();
+        assert_eq!(
+            servers.next(),
+            Some(ipv6::Addr([
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x53
+            ]))
+        );
+        assert!(servers.next().is_some());
+        assert!(servers.next().is_none());
+
+        // option overflows the message
+        assert!(dhcpv6::Message::parse(&BYTES[..BYTES.len() - 1]).is_err());
+
+        assert!(m.ntp_servers().next().is_none());
+    }
+
+    #[test]
+    fn ntp_servers() {
+        use crate::dhcpv6::NtpServer;
+
+        #[rustfmt::skip]
+        const BYTES: &[u8] = &[
+            7, // msg-type
+            0x12, 0x34, 0x56, // transaction-id
+            0, 56, 0, 65, // option: NTP Server
+            0, 1, 0, 16, // suboption: server address
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x7b,
+            0, 2, 0, 16, // suboption: multicast address
+            0xff, 0x05, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x01,
+            0, 9, 0, 1, // suboption: unknown
+            0,
+            0, 3, 0, 16, // suboption: server FQDN
+            3, b'n', b't', b'p', 7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0, 0, 0, 0,
+        ];
+
+        let m = dhcpv6::Message::parse(BYTES).unwrap();
+        let mut servers = m.ntp_servers();
+        assert_eq!(
+            servers.next(),
+            Some(NtpServer::Addr(ipv6::Addr([
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x7b
+            ])))
+        );
+        assert_eq!(
+            servers.next(),
+            Some(NtpServer::MulticastAddr(ipv6::Addr([
+                0xff, 0x05, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x01
+            ])))
+        );
+        assert_eq!(servers.next(), Some(NtpServer::Fqdn(&BYTES[57..73])));
+        assert_eq!(servers.next(), None);
+
+        // truncated suboption
+        let mut bytes = [0; 4 + 4 + 8];
+        bytes[0] = 7;
+        bytes[4..12].copy_from_slice(&[0, 56, 0, 8, 0, 1, 0, 16]);
+        let m = dhcpv6::Message::parse(&bytes[..]).unwrap();
+        assert_eq!(m.ntp_servers().next(), None);
+    }
+}
@@ -0,0 +1,462 @@
+//! DNS: Domain Name System
+//!
+//! NOTE there's no DNS message parser or builder (yet); this module only contains helpers to work
+//! with domain names, including `read_name` to extract e.g. the RDATA of a PTR record from a
+//! response.
+//!
+//! Names are plain ASCII; internationalized names must be converted to their A-label (`xn--`) form
+//! beforehand.
+//!
+//! # References
+//!
+//! - [RFC 1035: Domain Names - Implementation and Specification][rfc1035]
+//! - [RFC 1123: Requirements for Internet Hosts - Application and Support][rfc1123]
+//! - [RFC 3596: DNS Extensions to Support IP Version 6][rfc3596]
+//!
+//! [rfc1035]: https://tools.ietf.org/html/rfc1035
+//! [rfc1123]: https://tools.ietf.org/html/rfc1123
+//! [rfc3596]: https://tools.ietf.org/html/rfc3596
+
+use core::str;
+
+use crate::{ipv4, ipv6};
+
+/// Maximum size of a label
+pub const MAX_LABEL_SIZE: usize = 63;
+
+/// Maximum size of an encoded name, length octets and the terminating root label included
+pub const MAX_NAME_SIZE: usize = 255;
+
+/// Maximum size of the reverse lookup name of an IPv4 address (e.g.
+/// `255.255.255.255.in-addr.arpa`)
+pub const REVERSE_IPV4_MAX_SIZE: usize = 4 * 4 + IN_ADDR_ARPA.len();
+
+/// Size of the reverse lookup name of an IPv6 address (32 nibbles followed by `ip6.arpa`)
+pub const REVERSE_IPV6_SIZE: usize = 32 * 2 + IP6_ARPA.len();
+
+const IN_ADDR_ARPA: &[u8] = b"in-addr.arpa";
+const IP6_ARPA: &[u8] = b"ip6.arpa";
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// Writes the name used to look up the PTR record of `addr` (e.g. `1.1.168.192.in-addr.arpa`)
+/// into `buf`
+///
+/// Returns `Err` if `buf` is too small; `REVERSE_IPV4_MAX_SIZE` bytes are always enough
+pub fn reverse_ipv4(addr: ipv4::Addr, buf: &mut [u8]) -> Result<&str, ()> {
+    let mut len = 0;
+
+    for octet in addr.0.iter().rev() {
+        let mut digits = [0; 3];
+        let mut n = *octet;
+        let mut i = digits.len();
+        loop {
+            i -= 1;
+            digits[i] = b'0' + n % 10;
+            n /= 10;
+
+            if n == 0 {
+                break;
+            }
+        }
+
+        write(buf, &mut len, &digits[i..])?;
+        write(buf, &mut len, b".")?;
+    }
+
+    write(buf, &mut len, IN_ADDR_ARPA)?;
+
+    Ok(unsafe { str::from_utf8_unchecked(&buf[..len]) })
+}
+
+/// Writes the name used to look up the PTR record of `addr` (e.g. `1.0.0.0.[..].ip6.arpa`) into
+/// `buf`
+///
+/// Returns `Err` if `buf` is smaller than `REVERSE_IPV6_SIZE` bytes
+pub fn reverse_ipv6(addr: ipv6::Addr, buf: &mut [u8]) -> Result<&str, ()> {
+    let mut len = 0;
+
+    for byte in addr.0.iter().rev() {
+        write(buf, &mut len, &[HEX[usize::from(byte & 0xf)], b'.'])?;
+        write(buf, &mut len, &[HEX[usize::from(byte >> 4)], b'.'])?;
+    }
+
+    write(buf, &mut len, IP6_ARPA)?;
+
+    Ok(unsafe { str::from_utf8_unchecked(&buf[..len]) })
+}
+
+/// Error returned when a name is invalid
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NameError {
+    /// The name or one of its labels is empty
+    EmptyLabel,
+
+    /// A label is longer than `MAX_LABEL_SIZE`
+    LabelTooLong,
+
+    /// The encoded name would be longer than `MAX_NAME_SIZE`
+    NameTooLong,
+
+    /// A label contains a character other than a letter, a digit or a hyphen, or starts or ends
+    /// with a hyphen
+    InvalidCharacter,
+
+    /// The buffer is too small to hold the encoded name
+    BufferTooSmall,
+
+    /// The encoded name is truncated, uses an unsupported label type or contains a compression
+    /// pointer that doesn't point backwards
+    Malformed,
+}
+
+/// Checks that `label` is a valid host name label
+///
+/// Host name labels contain only ASCII letters, digits and hyphens and don't start or end with a
+/// hyphen (see Section 2.1 of RFC 1123). This is also what the Host Name option of DHCP (option
+/// 12) expects
+pub fn validate_label(label: &str) -> Result<(), NameError> {
+    let bytes = label.as_bytes();
+
+    if bytes.is_empty() {
+        Err(NameError::EmptyLabel)
+    } else if bytes.len() > MAX_LABEL_SIZE {
+        Err(NameError::LabelTooLong)
+    } else if bytes[0] == b'-'
+        || bytes[bytes.len() - 1] == b'-'
+        || !bytes
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+    {
+        Err(NameError::InvalidCharacter)
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that `name` is a valid host name (e.g. `sensor-1.local`)
+///
+/// Every label must pass `validate_label` and the encoded name must fit in `MAX_NAME_SIZE` bytes.
+/// A trailing dot (fully qualified name) is accepted
+pub fn validate_hostname(name: &str) -> Result<(), NameError> {
+    let name = strip_root(name);
+
+    encoded_size(name)?;
+    for label in name.split('.') {
+        validate_label(label)?;
+    }
+
+    Ok(())
+}
+
+/// Encodes `name` in the wire format (length prefixed labels followed by the zero length root
+/// label) into `buf`
+///
+/// Only the lengths are checked; labels may contain any character so service names like
+/// `_coap._udp.local` can be encoded. Use `validate_hostname` to also check the characters. A
+/// trailing dot is accepted; `.` on its own is the root name
+pub fn encode_name<'b>(name: &str, buf: &'b mut [u8]) -> Result<&'b [u8], NameError> {
+    let name = strip_root(name);
+    let size = if name.is_empty() {
+        1
+    } else {
+        encoded_size(name)?
+    };
+
+    if buf.len() < size {
+        return Err(NameError::BufferTooSmall);
+    }
+
+    let mut len = 0;
+    if !name.is_empty() {
+        for label in name.split('.') {
+            // NOTE(cast) `encoded_size` checked the label length
+            buf[len] = label.len() as u8;
+            buf[len + 1..len + 1 + label.len()].copy_from_slice(label.as_bytes());
+            len += 1 + label.len();
+        }
+    }
+    buf[len] = 0;
+
+    Ok(&buf[..size])
+}
+
+/// Reads the name that starts at `offset` of the DNS `message` into `buf`, in dotted form
+///
+/// Use this on the RDATA of a PTR record (or on the owner name of a record). `message` must be the
+/// whole DNS message because compression pointers are offsets from its start. Returns the name,
+/// without trailing dot (the root name is `.`), and the offset right after the name in `message`.
+///
+/// Compression pointers must point to an earlier part of the message than the one that's being
+/// read, which rules out loops. Labels must consist of printable ASCII characters other than `.`
+pub fn read_name<'b>(
+    message: &[u8],
+    mut offset: usize,
+    buf: &'b mut [u8],
+) -> Result<(&'b str, usize), NameError> {
+    let mut len = 0;
+    let mut size = 1; // root label
+    let mut end = None;
+    let mut limit = offset;
+
+    loop {
+        let byte = *message.get(offset).ok_or(NameError::Malformed)?;
+
+        match byte >> 6 {
+            0b00 if byte == 0 => {
+                if len == 0 {
+                    write(buf, &mut len, b".").map_err(|_| NameError::BufferTooSmall)?;
+                }
+
+                let end = end.unwrap_or(offset + 1);
+                return Ok((unsafe { str::from_utf8_unchecked(&buf[..len]) }, end));
+            }
+
+            0b00 => {
+                let start = offset + 1;
+                let label = message
+                    .get(start..start + usize::from(byte))
+                    .ok_or(NameError::Malformed)?;
+
+                size += 1 + label.len();
+                if size > MAX_NAME_SIZE {
+                    return Err(NameError::NameTooLong);
+                }
+
+                if !label.iter().all(|b| b.is_ascii_graphic() && *b != b'.') {
+                    return Err(NameError::InvalidCharacter);
+                }
+
+                if len != 0 {
+                    write(buf, &mut len, b".").map_err(|_| NameError::BufferTooSmall)?;
+                }
+                write(buf, &mut len, label).map_err(|_| NameError::BufferTooSmall)?;
+
+                offset = start + label.len();
+            }
+
+            0b11 => {
+                let low = *message.get(offset + 1).ok_or(NameError::Malformed)?;
+                let target = usize::from(byte & 0x3f) << 8 | usize::from(low);
+
+                if target >= limit {
+                    return Err(NameError::Malformed);
+                }
+
+                if end.is_none() {
+                    end = Some(offset + 2);
+                }
+                limit = target;
+                offset = target;
+            }
+
+            _ => return Err(NameError::Malformed),
+        }
+    }
+}
+
+// removes the trailing dot, if any
+fn strip_root(name: &str) -> &str {
+    name.strip_suffix('.').unwrap_or(name)
+}
+
+// size of the encoded form of a name that has no trailing dot
+fn encoded_size(name: &str) -> Result<usize, NameError> {
+    let mut size = 1; // root label
+
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err(NameError::EmptyLabel);
+        } else if label.len() > MAX_LABEL_SIZE {
+            return Err(NameError::LabelTooLong);
+        }
+
+        size += 1 + label.len();
+    }
+
+    if size > MAX_NAME_SIZE {
+        Err(NameError::NameTooLong)
+    } else {
+        Ok(size)
+    }
+}
+
+fn write(buf: &mut [u8], len: &mut usize, bytes: &[u8]) -> Result<(), ()> {
+    let end = *len + bytes.len();
+    buf.get_mut(*len..end).ok_or(())?.copy_from_slice(bytes);
+    *len = end;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dns::{self, NameError},
+        ipv4, ipv6,
+    };
+
+    #[test]
+    fn hostname() {
+        assert_eq!(dns::validate_hostname("sensor-1.local"), Ok(()));
+        assert_eq!(dns::validate_hostname("sensor-1.local."), Ok(()));
+        assert_eq!(dns::validate_hostname("1node"), Ok(()));
+
+        assert_eq!(dns::validate_hostname(""), Err(NameError::EmptyLabel));
+        assert_eq!(dns::validate_hostname("a..b"), Err(NameError::EmptyLabel));
+        assert_eq!(
+            dns::validate_hostname("-sensor.local"),
+            Err(NameError::InvalidCharacter)
+        );
+        assert_eq!(
+            dns::validate_hostname("sensor_1"),
+            Err(NameError::InvalidCharacter)
+        );
+        assert_eq!(
+            dns::validate_hostname("caf\u{e9}"),
+            Err(NameError::InvalidCharacter)
+        );
+
+        let long_label = [b'a'; dns::MAX_LABEL_SIZE + 1];
+        let long_label = core::str::from_utf8(&long_label).unwrap();
+        assert_eq!(
+            dns::validate_label(long_label),
+            Err(NameError::LabelTooLong)
+        );
+        assert_eq!(dns::validate_label(&long_label[1..]), Ok(()));
+
+        // 4 labels of 63 bytes: 4 * 64 + 1 = 257 bytes when encoded
+        let mut long_name = [b'a'; 4 * 64 - 1];
+        for i in 1..4 {
+            long_name[i * 64 - 1] = b'.';
+        }
+        let long_name = core::str::from_utf8(&long_name).unwrap();
+        assert_eq!(
+            dns::validate_hostname(long_name),
+            Err(NameError::NameTooLong)
+        );
+        assert_eq!(dns::validate_hostname(&long_name[2..]), Ok(()));
+    }
+
+    #[test]
+    fn encode_name() {
+        let mut buf = [0; dns::MAX_NAME_SIZE];
+
+        assert_eq!(
+            dns::encode_name("_coap._udp.local", &mut buf),
+            Ok(&b"\x05_coap\x04_udp\x05local\x00"[..])
+        );
+        assert_eq!(
+            dns::encode_name("example.com.", &mut buf),
+            Ok(&b"\x07example\x03com\x00"[..])
+        );
+        assert_eq!(dns::encode_name(".", &mut buf), Ok(&b"\x00"[..]));
+
+        assert_eq!(
+            dns::encode_name("local..", &mut buf),
+            Err(NameError::EmptyLabel)
+        );
+        assert_eq!(
+            dns::encode_name("example.com", &mut buf[..12]),
+            Err(NameError::BufferTooSmall)
+        );
+        assert!(dns::encode_name("example.com", &mut buf[..13]).is_ok());
+    }
+
+    #[test]
+    fn read_name() {
+        // response to `dig -x 8.8.8.8`
+        #[rustfmt::skip]
+        const RESPONSE: &[u8] = &[
+            0x8c, 0x5e, // ID
+            0x81, 0x80, // QR | RD | RA
+            0x00, 0x01, // QDCOUNT
+            0x00, 0x01, // ANCOUNT
+            0x00, 0x00, // NSCOUNT
+            0x00, 0x00, // ARCOUNT
+            // question: 8.8.8.8.in-addr.arpa PTR IN
+            1, b'8', 1, b'8', 1, b'8', 1, b'8',
+            7, b'i', b'n', b'-', b'a', b'd', b'd', b'r', 4, b'a', b'r', b'p', b'a', 0,
+            0x00, 0x0c, 0x00, 0x01,
+            // answer
+            0xc0, 0x0c, // name: pointer to the question
+            0x00, 0x0c, 0x00, 0x01, // PTR IN
+            0x00, 0x00, 0x2a, 0x30, // TTL
+            0x00, 0x0c, // RDLENGTH
+            3, b'd', b'n', b's', 6, b'g', b'o', b'o', b'g', b'l', b'e', 0, // RDATA
+        ];
+
+        let mut buf = [0; dns::MAX_NAME_SIZE];
+
+        assert_eq!(
+            dns::read_name(RESPONSE, 38, &mut buf),
+            Ok(("8.8.8.8.in-addr.arpa", 40))
+        );
+        assert_eq!(
+            dns::read_name(RESPONSE, 50, &mut buf),
+            Ok(("dns.google", RESPONSE.len()))
+        );
+        assert_eq!(
+            dns::read_name(RESPONSE, 50, &mut buf[..9]),
+            Err(NameError::BufferTooSmall)
+        );
+        assert_eq!(
+            dns::read_name(&RESPONSE[..RESPONSE.len() - 1], 50, &mut buf),
+            Err(NameError::Malformed)
+        );
+
+        // RDATA that points into the question
+        let mut message = [0; 64];
+        message[..50].copy_from_slice(&RESPONSE[..50]);
+        message[50..57].copy_from_slice(b"\x04host\xc0\x14");
+        assert_eq!(
+            dns::read_name(&message, 50, &mut buf),
+            Ok(("host.in-addr.arpa", 57))
+        );
+
+        // pointer loops
+        message[55..57].copy_from_slice(&[0xc0, 50]);
+        assert_eq!(
+            dns::read_name(&message, 50, &mut buf),
+            Err(NameError::Malformed)
+        );
+        message[50..52].copy_from_slice(&[0xc0, 50]);
+        assert_eq!(
+            dns::read_name(&message, 50, &mut buf),
+            Err(NameError::Malformed)
+        );
+
+        // root name
+        assert_eq!(dns::read_name(&[0], 0, &mut buf), Ok((".", 1)));
+    }
+
+    #[test]
+    fn reverse() {
+        let mut buf = [0; dns::REVERSE_IPV4_MAX_SIZE];
+
+        assert_eq!(
+            dns::reverse_ipv4(ipv4::Addr([192, 168, 1, 33]), &mut buf),
+            Ok("33.1.168.192.in-addr.arpa")
+        );
+        assert_eq!(
+            dns::reverse_ipv4(ipv4::Addr([10, 0, 0, 1]), &mut buf),
+            Ok("1.0.0.10.in-addr.arpa")
+        );
+        assert_eq!(
+            dns::reverse_ipv4(ipv4::Addr([255; 4]), &mut buf).map(|s| s.len()),
+            Ok(dns::REVERSE_IPV4_MAX_SIZE)
+        );
+        assert!(dns::reverse_ipv4(ipv4::Addr([255; 4]), &mut buf[..20]).is_err());
+
+        // example from Section 2.5 of RFC 3596
+        let addr = ipv6::Addr([
+            0x43, 0x21, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, //
+            0x00, 0x03, 0x00, 0x04, 0x05, 0x67, 0x89, 0xab,
+        ]);
+        let mut buf = [0; dns::REVERSE_IPV6_SIZE];
+        assert_eq!(
+            dns::reverse_ipv6(addr, &mut buf),
+            Ok("b.a.9.8.7.6.5.0.4.0.0.0.3.0.0.0.2.0.0.0.1.0.0.0.0.0.0.0.1.2.3.4.ip6.arpa")
+        );
+        assert!(dns::reverse_ipv6(addr, &mut buf[..71]).is_err());
+    }
+}
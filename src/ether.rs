@@ -1,4 +1,7 @@
 //! Ethernet II
+//!
+//! IEEE 802.3 frames (where the 'Type' field is a length) with an IEEE 802.2 LLC header, and
+//! optionally a SNAP header, are also recognized; see `Frame::get_llc` and `Frame::get_protocol`
 
 use core::{
     fmt,
@@ -21,6 +24,28 @@ const PAYLOAD: RangeFrom<usize> = 14..;
 /// Size of the MAC header
 pub const HEADER_SIZE: u8 = TYPE.end as u8;
 
+/// Maximum Transmission Unit: the maximum size of the payload of an Ethernet frame
+pub const MTU: u16 = 1500;
+
+/// Maximum size of an Ethernet frame (MAC header + payload), excluding the Frame Check Sequence
+pub const MAX_FRAME_SIZE: u16 = HEADER_SIZE as u16 + MTU;
+
+// Values of the 'Type' field equal or smaller than this are a length (IEEE 802.3)
+const MAX_LENGTH: u16 = MTU;
+// Values of the 'Type' field equal or greater than this are an EtherType
+const MIN_ETHER_TYPE: u16 = 0x0600;
+
+/* LLC (IEEE 802.2) */
+const DSAP: usize = 0;
+const SSAP: usize = 1;
+const CONTROL: usize = 2;
+
+/* SNAP (IEEE 802) */
+const SNAP_SAP: u8 = 0xAA;
+const SNAP_CONTROL: u16 = 0x03;
+const SNAP_OUI: Range<usize> = 3..6;
+const SNAP_PROTOCOL: Range<usize> = 6..8;
+
 /// Layer 2 Ethernet frame
 ///
 /// # Structure
@@ -83,6 +108,125 @@ where
         unsafe { &self.as_slice().rf(PAYLOAD) }
     }
 
+    /// Returns the 'Length' field of an IEEE 802.3 frame
+    ///
+    /// Returns `None` if the 'Type' field contains an EtherType (i.e. this is an Ethernet II
+    /// frame) or an invalid value
+    pub fn get_length(&self) -> Option<u16> {
+        let raw = NE::read_u16(&self.header_()[TYPE]);
+
+        if raw <= MAX_LENGTH {
+            Some(raw)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the LLC header of an IEEE 802.3 frame
+    ///
+    /// Returns `None` if this is an Ethernet II frame or if the payload is too short to contain an
+    /// LLC header
+    pub fn get_llc(&self) -> Option<Llc> {
+        self.get_length()?;
+
+        let payload = self.data();
+        if payload.len() <= CONTROL {
+            return None;
+        }
+
+        let control = if payload[CONTROL] & 0b11 == 0b11 {
+            // U-format: 1 byte
+            u16::from(payload[CONTROL])
+        } else {
+            // I-format and S-format: 2 bytes
+            if payload.len() <= CONTROL + 1 {
+                return None;
+            }
+
+            NE::read_u16(&payload[CONTROL..CONTROL + 2])
+        };
+
+        Some(Llc {
+            dsap: payload[DSAP],
+            ssap: payload[SSAP],
+            control,
+        })
+    }
+
+    /// Returns the SNAP header of an IEEE 802.3 frame
+    ///
+    /// Returns `None` if the frame doesn't have an LLC header, or if its LLC header doesn't
+    /// announce a SNAP header
+    pub fn get_snap(&self) -> Option<Snap> {
+        let llc = self.get_llc()?;
+
+        if llc.dsap != SNAP_SAP || llc.ssap != SNAP_SAP || llc.control != SNAP_CONTROL {
+            return None;
+        }
+
+        let payload = self.data();
+        if payload.len() < SNAP_PROTOCOL.end {
+            return None;
+        }
+
+        Some(Snap {
+            oui: [
+                payload[SNAP_OUI.start],
+                payload[SNAP_OUI.start + 1],
+                payload[SNAP_OUI.start + 2],
+            ],
+            protocol: NE::read_u16(&payload[SNAP_PROTOCOL]),
+        })
+    }
+
+    /// Returns the protocol carried in the payload
+    ///
+    /// For Ethernet II frames this is the 'Type' field. For IEEE 802.3 frames this is the EtherType
+    /// in the SNAP header (RFC 1042 encapsulation, OUI `00-00-00`).
+    ///
+    /// Returns `None` for IEEE 802.3 frames that don't carry an EtherType (e.g. STP BPDUs) and for
+    /// frames whose 'Type' field contains a reserved value (1501 - 1535)
+    pub fn get_protocol(&self) -> Option<Type> {
+        let raw = NE::read_u16(&self.header_()[TYPE]);
+
+        if raw >= MIN_ETHER_TYPE {
+            Some(raw.into())
+        } else {
+            let snap = self.get_snap()?;
+
+            if snap.oui == [0, 0, 0] {
+                Some(snap.protocol.into())
+            } else {
+                None
+            }
+        }
+    }
+
+    /// View into the payload that follows the LLC header (and the SNAP header, if present)
+    ///
+    /// For Ethernet II frames this is the same as `payload`. For IEEE 802.3 frames any padding
+    /// past the 'Length' field is excluded.
+    ///
+    /// Returns `None` for IEEE 802.3 frames that don't contain a (complete) LLC header, or whose
+    /// LLC header announces a SNAP header that's missing or truncated
+    pub fn protocol_payload(&self) -> Option<&[u8]> {
+        if self.get_length().is_none() {
+            return Some(self.payload());
+        }
+
+        let llc = self.get_llc()?;
+        let start = if llc.dsap == SNAP_SAP && llc.ssap == SNAP_SAP {
+            self.get_snap()?;
+            SNAP_PROTOCOL.end
+        } else if self.data()[CONTROL] & 0b11 == 0b11 {
+            CONTROL + 1
+        } else {
+            CONTROL + 2
+        };
+
+        Some(&self.data()[start..])
+    }
+
     /* Miscellaneous */
     /// Returns the byte representation of this frame
     pub fn as_bytes(&self) -> &[u8] {
@@ -104,6 +248,16 @@ where
         self.buffer.as_slice()
     }
 
+    // payload of an IEEE 802.3 frame, without padding
+    fn data(&self) -> &[u8] {
+        let payload = self.payload();
+
+        match self.get_length() {
+            Some(len) if usize(len) < payload.len() => &payload[..usize(len)],
+            _ => payload,
+        }
+    }
+
     fn header_(&self) -> &[u8; HEADER_SIZE as usize] {
         debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
 
@@ -252,6 +406,26 @@ full_range!(
     }
 );
 
+/// IEEE 802.2 Logical Link Control header
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Llc {
+    /// Destination Service Access Point
+    pub dsap: u8,
+    /// Source Service Access Point
+    pub ssap: u8,
+    /// Control field; 1 byte for U-format PDUs, 2 bytes otherwise
+    pub control: u16,
+}
+
+/// IEEE 802 Subnetwork Access Protocol header
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Snap {
+    /// Organizationally Unique Identifier
+    pub oui: [u8; 3],
+    /// Protocol identifier; an EtherType when `oui` is `00-00-00`
+    pub protocol: u16,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ether;
@@ -266,4 +440,70 @@ mod tests {
         let eth = ether::Frame::new(buf);
         assert_eq!(eth.len(), SZ);
     }
+
+    #[test]
+    fn llc() {
+        #[rustfmt::skip]
+        const STP: &[u8] = &[
+            0x01, 0x80, 0xc2, 0x00, 0x00, 0x00, // destination
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, // source
+            0x00, 0x07, // length
+            0x42, 0x42, 0x03, // LLC
+            0x00, 0x00, 0x00, 0x00, // BPDU
+            0x00, 0x00, 0x00, // padding
+        ];
+
+        let eth = ether::Frame::parse(STP).unwrap();
+        assert_eq!(eth.get_length(), Some(7));
+        assert_eq!(
+            eth.get_llc(),
+            Some(ether::Llc {
+                dsap: 0x42,
+                ssap: 0x42,
+                control: 0x03
+            })
+        );
+        assert_eq!(eth.get_snap(), None);
+        assert_eq!(eth.get_protocol(), None);
+        assert_eq!(eth.protocol_payload(), Some(&[0, 0, 0, 0][..]));
+
+        #[rustfmt::skip]
+        const SNAP: &[u8] = &[
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // destination
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, // source
+            0x00, 0x0a, // length
+            0xaa, 0xaa, 0x03, // LLC
+            0x00, 0x00, 0x00, 0x08, 0x06, // SNAP
+            0xde, 0xad, // payload
+        ];
+
+        let eth = ether::Frame::parse(SNAP).unwrap();
+        assert_eq!(
+            eth.get_snap(),
+            Some(ether::Snap {
+                oui: [0, 0, 0],
+                protocol: 0x0806
+            })
+        );
+        assert_eq!(eth.get_protocol(), Some(ether::Type::Arp));
+        assert_eq!(eth.protocol_payload(), Some(&[0xde, 0xad][..]));
+
+        // truncated SNAP header
+        let mut truncated = [0; SNAP.len()];
+        truncated.copy_from_slice(SNAP);
+        truncated[13] = 0x06;
+        let eth = ether::Frame::parse(&truncated[..]).unwrap();
+        assert!(eth.get_llc().is_some());
+        assert_eq!(eth.get_snap(), None);
+        assert_eq!(eth.get_protocol(), None);
+        assert_eq!(eth.protocol_payload(), None);
+
+        let mut chunk = [0; 64];
+        let mut eth = ether::Frame::new(&mut chunk[..]);
+        eth.set_type(ether::Type::Ipv6);
+        assert_eq!(eth.get_length(), None);
+        assert_eq!(eth.get_llc(), None);
+        assert_eq!(eth.get_protocol(), Some(ether::Type::Ipv6));
+        assert_eq!(eth.protocol_payload().map(|p| p.len()), Some(50));
+    }
 }
@@ -3,8 +3,12 @@
 //! # References
 //!
 //! - [RFC 792: Internet Control Message Protocol][rfc]
+//! - [RFC 950: Internet Standard Subnetting Procedure][rfc950]
+//! - [RFC 1256: ICMP Router Discovery Messages][rfc1256]
 //!
 //! [rfc]: https://tools.ietf.org/html/rfc792
+//! [rfc950]: https://tools.ietf.org/html/rfc950
+//! [rfc1256]: https://tools.ietf.org/html/rfc1256
 
 use core::fmt;
 use core::marker::PhantomData;
@@ -12,12 +16,13 @@ use core::ops::{Range, RangeFrom};
 
 use as_slice::{AsMutSlice, AsSlice};
 use byteorder::{ByteOrder, NetworkEndian as NE};
-use cast::usize;
+use cast::{u16, usize};
+use owning_slice::Truncate;
 
 use crate::{
     fmt::Hex,
     ipv4,
-    sealed::Echo,
+    sealed::{AddressMask, Echo, Identified},
     traits::{TryFrom, TryInto, UncheckedIndex},
     Invalid, Unknown, Valid,
 };
@@ -30,9 +35,23 @@ const IDENT: Range<usize> = 4..6;
 const SEQ_NO: Range<usize> = 6..8;
 const PAYLOAD: RangeFrom<usize> = 8..;
 
+// Router Advertisement
+const NUM_ADDRS: usize = 4;
+const ADDR_ENTRY_SIZE: usize = 5;
+const LIFETIME: Range<usize> = 6..8;
+
+// Address Mask Request / Reply
+const ADDRESS_MASK: Range<usize> = 8..12;
+
 /// Size of the ICMP header
 pub const HEADER_SIZE: u8 = PAYLOAD.start as u8;
 
+/// Returns the maximum payload size of an ICMP message given the maximum payload size of the IPv4
+/// packet that contains it
+pub const fn max_payload(ip_payload: u16) -> u16 {
+    ip_payload - HEADER_SIZE as u16
+}
+
 /// ICMP Message
 pub struct Message<BUFFER, TYPE, CHECKSUM>
 where
@@ -50,6 +69,18 @@ pub enum EchoReply {}
 /// [Type State] The Echo Request type
 pub enum EchoRequest {}
 
+/// [Type State] The Router Advertisement type
+pub enum RouterAdvertisement {}
+
+/// [Type State] The Router Solicitation type
+pub enum RouterSolicitation {}
+
+/// [Type State] The Address Mask Request type
+pub enum AddressMaskRequest {}
+
+/// [Type State] The Address Mask Reply type
+pub enum AddressMaskReply {}
+
 /* EchoRequest */
 impl<B> Message<B, EchoRequest, Invalid>
 where
@@ -69,11 +100,11 @@ where
     }
 }
 
-/* EchoReply OR EchoRequest */
+/* EchoReply, EchoRequest, AddressMaskReply OR AddressMaskRequest */
 impl<B, E, C> Message<B, E, C>
 where
     B: AsSlice<Element = u8>,
-    E: Echo,
+    E: Identified,
 {
     /* Getters */
     /// Returns the Identifier field of the header
@@ -90,7 +121,7 @@ where
 impl<B, E> Message<B, E, Invalid>
 where
     B: AsSlice<Element = u8> + AsMutSlice<Element = u8>,
-    E: Echo,
+    E: Identified,
 {
     /* Setters */
     /// Returns the Identifier field of the header
@@ -104,6 +135,250 @@ where
     }
 }
 
+impl<B, E, C> Message<B, E, C>
+where
+    B: AsSlice<Element = u8>,
+    E: Echo,
+{
+    /// Checks that the payload follows the given `pattern`
+    ///
+    /// Use this on an Echo Reply to detect corruption of the data echoed back by the remote host
+    pub fn matches_pattern(&self, pattern: Pattern<'_>) -> bool {
+        pattern.matches(self.payload())
+    }
+}
+
+impl<B, E> Message<B, E, Invalid>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+    E: Echo,
+{
+    /// Fills the payload with `len` bytes that follow the given `pattern` and truncates the message
+    /// to fit the payload
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the payload doesn't fit in the buffer or if `pattern` is an empty
+    /// `Pattern::Repeat`
+    pub fn set_payload_pattern(&mut self, pattern: Pattern<'_>, len: u16) {
+        pattern.fill(&mut self.payload_mut()[..usize(len)]);
+        self.buffer.truncate(u16(HEADER_SIZE) + len);
+    }
+}
+
+/// Echo payload pattern
+#[derive(Clone, Copy, Debug)]
+pub enum Pattern<'a> {
+    /// The n-th byte of the payload is `n` (mod 256); this is what `ping` sends by default (sans
+    /// timestamp)
+    Incrementing,
+    /// The given bytes, repeated over the whole payload; like `ping -p`
+    Repeat(&'a [u8]),
+}
+
+impl<'a> Pattern<'a> {
+    pub(crate) fn matches(&self, payload: &[u8]) -> bool {
+        match *self {
+            Pattern::Incrementing => payload.iter().enumerate().all(|(i, byte)| *byte == i as u8),
+            Pattern::Repeat(pattern) => {
+                if pattern.is_empty() {
+                    payload.is_empty()
+                } else {
+                    payload
+                        .chunks(pattern.len())
+                        .all(|chunk| chunk == &pattern[..chunk.len()])
+                }
+            }
+        }
+    }
+
+    fn fill(&self, payload: &mut [u8]) {
+        match *self {
+            Pattern::Incrementing => {
+                for (i, byte) in payload.iter_mut().enumerate() {
+                    *byte = i as u8;
+                }
+            }
+            Pattern::Repeat(pattern) => {
+                assert!(!pattern.is_empty());
+
+                for chunk in payload.chunks_mut(pattern.len()) {
+                    let len = chunk.len();
+                    chunk.copy_from_slice(&pattern[..len]);
+                }
+            }
+        }
+    }
+}
+
+/* RouterAdvertisement */
+impl<B, C> Message<B, RouterAdvertisement, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Returns the Num Addrs field of the header
+    pub fn get_num_addrs(&self) -> u8 {
+        self.header_()[NUM_ADDRS]
+    }
+
+    /// Returns the Addr Entry Size field of the header, in 32-bit words
+    pub fn get_addr_entry_size(&self) -> u8 {
+        self.header_()[ADDR_ENTRY_SIZE]
+    }
+
+    /// Returns the Lifetime field of the header, in seconds
+    pub fn get_lifetime(&self) -> u16 {
+        NE::read_u16(&self.header_()[LIFETIME])
+    }
+
+    /// Returns an iterator over the advertised router addresses and their preference levels
+    pub fn addresses(&self) -> RouterAddresses<'_> {
+        let entry_size = 4 * usize(self.get_addr_entry_size());
+        let len = usize(self.get_num_addrs()) * entry_size;
+
+        RouterAddresses {
+            // NOTE `TryFrom` checked the length of the message
+            entries: unsafe { self.payload().rt(..len) },
+            entry_size,
+        }
+    }
+
+    /// Returns the advertised router with the highest preference level
+    ///
+    /// Addresses with a preference level of `PREFERENCE_NOT_DEFAULT` are not considered. If
+    /// several addresses share the highest preference level the first one is returned
+    pub fn default_router(&self) -> Option<ipv4::Addr> {
+        let mut best: Option<(ipv4::Addr, i32)> = None;
+
+        for (addr, preference) in self.addresses() {
+            if preference != PREFERENCE_NOT_DEFAULT
+                && best
+                    .map(|(_, highest)| preference > highest)
+                    .unwrap_or(true)
+            {
+                best = Some((addr, preference));
+            }
+        }
+
+        best.map(|(addr, _)| addr)
+    }
+}
+
+/// Preference level of router addresses that must not be used as default router
+pub const PREFERENCE_NOT_DEFAULT: i32 = i32::min_value();
+
+/// Iterator over the router addresses of a Router Advertisement
+pub struct RouterAddresses<'a> {
+    entries: &'a [u8],
+    entry_size: usize,
+}
+
+impl<'a> Iterator for RouterAddresses<'a> {
+    /// Router address and preference level
+    type Item = (ipv4::Addr, i32);
+
+    fn next(&mut self) -> Option<(ipv4::Addr, i32)> {
+        if self.entries.is_empty() {
+            return None;
+        }
+
+        // NOTE(unsafe) the downcast checked that the entries fit in the message and that each one
+        // is at least 8 bytes long
+        unsafe {
+            let entry = self.entries.rt(..self.entry_size);
+            self.entries = self.entries.rf(self.entry_size..);
+
+            let mut addr = ipv4::Addr::UNSPECIFIED;
+            addr.0.copy_from_slice(entry.rt(..4));
+
+            Some((addr, NE::read_i32(entry.r(4..8))))
+        }
+    }
+}
+
+/* RouterSolicitation */
+impl<B> Message<B, RouterSolicitation, Invalid>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /* Constructors */
+    /// Transforms the input buffer into a Router Solicitation ICMP packet
+    ///
+    /// The buffer will be truncated to the size of the message
+    pub fn router_solicitation(mut buffer: B) -> Self {
+        assert!(buffer.as_slice().len() >= usize(HEADER_SIZE));
+
+        buffer.truncate(u16(HEADER_SIZE));
+        let mut packet: Message<B, Unknown, Invalid> = unsafe { Message::unchecked(buffer) };
+
+        packet.set_type(Type::RouterSolicitation);
+        packet.set_code(0);
+        // Reserved
+        for byte in &mut packet.header_mut_()[IDENT.start..] {
+            *byte = 0;
+        }
+
+        unsafe { Message::unchecked(packet.buffer) }
+    }
+}
+
+/* AddressMaskRequest */
+impl<B> Message<B, AddressMaskRequest, Invalid>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /* Constructors */
+    /// Transforms the input buffer into an Address Mask Request ICMP packet
+    ///
+    /// The Address Mask field is set to `0.0.0.0`. The buffer will be truncated to the size of the
+    /// message
+    pub fn address_mask_request(mut buffer: B) -> Self {
+        assert!(buffer.as_slice().len() >= ADDRESS_MASK.end);
+
+        buffer.truncate(ADDRESS_MASK.end as u16);
+        let mut packet: Message<B, Unknown, Invalid> = unsafe { Message::unchecked(buffer) };
+
+        packet.set_type(Type::AddressMaskRequest);
+        packet.set_code(0);
+        unsafe {
+            packet
+                .as_mut_slice()
+                .rm(ADDRESS_MASK)
+                .copy_from_slice(&[0; 4]);
+        }
+
+        unsafe { Message::unchecked(packet.buffer) }
+    }
+}
+
+/* AddressMaskReply OR AddressMaskRequest */
+impl<B, E, C> Message<B, E, C>
+where
+    B: AsSlice<Element = u8>,
+    E: AddressMask,
+{
+    /* Getters */
+    /// Returns the Address Mask field
+    ///
+    /// NOTE this is `0.0.0.0` in requests
+    pub fn get_address_mask(&self) -> ipv4::Addr {
+        unsafe { ipv4::Addr(*(self.as_slice().as_ptr().add(ADDRESS_MASK.start) as *const _)) }
+    }
+
+    /* Private */
+    fn fmt_address_mask(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmp::Message")
+            .field("type", &self.get_type())
+            .field("code", &self.get_code())
+            .field("checksum", &Hex(self.get_checksum()))
+            .field("id", &self.get_identifier())
+            .field("seq_no", &self.get_sequence_number())
+            .field("address_mask", &self.get_address_mask())
+            .finish()
+    }
+}
+
 /* Unknown */
 impl<B> Message<B, Unknown, Valid>
 where
@@ -217,6 +492,79 @@ where
     }
 }
 
+impl<B, C> TryFrom<Message<B, Unknown, C>> for Message<B, RouterAdvertisement, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown, C>;
+
+    fn try_from(p: Message<B, Unknown, C>) -> Result<Self, Message<B, Unknown, C>> {
+        if p.get_type() == Type::RouterAdvertisement && p.get_code() == 0 {
+            let num_addrs = usize(p.header_()[NUM_ADDRS]);
+            let entry_size = usize(p.header_()[ADDR_ENTRY_SIZE]);
+
+            // see section 5.2 of RFC 1256
+            if num_addrs >= 1 && entry_size >= 2 && p.payload().len() >= num_addrs * entry_size * 4
+            {
+                return Ok(unsafe { Message::unchecked(p.buffer) });
+            }
+        }
+
+        Err(p)
+    }
+}
+
+impl<B, C> TryFrom<Message<B, Unknown, C>> for Message<B, RouterSolicitation, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown, C>;
+
+    fn try_from(p: Message<B, Unknown, C>) -> Result<Self, Message<B, Unknown, C>> {
+        if p.get_type() == Type::RouterSolicitation && p.get_code() == 0 {
+            Ok(unsafe { Message::unchecked(p.buffer) })
+        } else {
+            Err(p)
+        }
+    }
+}
+
+impl<B, C> TryFrom<Message<B, Unknown, C>> for Message<B, AddressMaskRequest, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown, C>;
+
+    fn try_from(p: Message<B, Unknown, C>) -> Result<Self, Message<B, Unknown, C>> {
+        if p.get_type() == Type::AddressMaskRequest
+            && p.get_code() == 0
+            && p.as_slice().len() >= ADDRESS_MASK.end
+        {
+            Ok(unsafe { Message::unchecked(p.buffer) })
+        } else {
+            Err(p)
+        }
+    }
+}
+
+impl<B, C> TryFrom<Message<B, Unknown, C>> for Message<B, AddressMaskReply, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown, C>;
+
+    fn try_from(p: Message<B, Unknown, C>) -> Result<Self, Message<B, Unknown, C>> {
+        if p.get_type() == Type::AddressMaskReply
+            && p.get_code() == 0
+            && p.as_slice().len() >= ADDRESS_MASK.end
+        {
+            Ok(unsafe { Message::unchecked(p.buffer) })
+        } else {
+            Err(p)
+        }
+    }
+}
+
 /* TYPE */
 impl<B, T, C> Message<B, T, C>
 where
@@ -238,6 +586,14 @@ where
             Type::EchoReply
         } else if typeid!(T == EchoRequest) {
             Type::EchoRequest
+        } else if typeid!(T == RouterAdvertisement) {
+            Type::RouterAdvertisement
+        } else if typeid!(T == RouterSolicitation) {
+            Type::RouterSolicitation
+        } else if typeid!(T == AddressMaskRequest) {
+            Type::AddressMaskRequest
+        } else if typeid!(T == AddressMaskReply) {
+            Type::AddressMaskReply
         } else {
             self.header_()[TYPE].into()
         }
@@ -245,9 +601,13 @@ where
 
     /// Returns the Type field of the header
     pub fn get_code(&self) -> u8 {
-        if typeid!(T == EchoReply) {
-            0
-        } else if typeid!(T == EchoRequest) {
+        if typeid!(T == EchoReply)
+            || typeid!(T == EchoRequest)
+            || typeid!(T == RouterAdvertisement)
+            || typeid!(T == RouterSolicitation)
+            || typeid!(T == AddressMaskRequest)
+            || typeid!(T == AddressMaskReply)
+        {
             0
         } else {
             self.header_()[CODE]
@@ -359,6 +719,54 @@ where
     }
 }
 
+/// NOTE excludes the router addresses
+impl<B, C> fmt::Debug for Message<B, RouterAdvertisement, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmp::Message")
+            .field("type", &self.get_type())
+            .field("code", &self.get_code())
+            .field("checksum", &Hex(self.get_checksum()))
+            .field("num_addrs", &self.get_num_addrs())
+            .field("addr_entry_size", &self.get_addr_entry_size())
+            .field("lifetime", &self.get_lifetime())
+            .finish()
+    }
+}
+
+impl<B, C> fmt::Debug for Message<B, RouterSolicitation, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmp::Message")
+            .field("type", &self.get_type())
+            .field("code", &self.get_code())
+            .field("checksum", &Hex(self.get_checksum()))
+            .finish()
+    }
+}
+
+impl<B, C> fmt::Debug for Message<B, AddressMaskRequest, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_address_mask(f)
+    }
+}
+
+impl<B, C> fmt::Debug for Message<B, AddressMaskReply, C>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_address_mask(f)
+    }
+}
+
 impl<B, C> fmt::Debug for Message<B, Unknown, C>
 where
     B: AsSlice<Element = u8>,
@@ -384,6 +792,14 @@ full_range!(
         DestinationUnreachable = 3,
         /// Echo Request
         EchoRequest = 8,
+        /// Router Advertisement
+        RouterAdvertisement = 9,
+        /// Router Solicitation
+        RouterSolicitation = 10,
+        /// Address Mask Request
+        AddressMaskRequest = 17,
+        /// Address Mask Reply
+        AddressMaskReply = 18,
     }
 );
 
@@ -417,7 +833,7 @@ mod tests {
     ];
 
     const MAC_SRC: mac::Addr = mac::Addr([0x01; 6]);
-    const MAC_DST: mac::Addr = mac::Addr([0xff; 6]);
+    const MAC_DST: mac::Addr = mac::Addr::BROADCAST;
 
     const IP_SRC: ipv4::Addr = ipv4::Addr([192, 168, 0, 33]);
     const IP_DST: ipv4::Addr = ipv4::Addr([192, 168, 0, 1]);
@@ -464,4 +880,148 @@ mod tests {
         assert_eq!(icmp.get_identifier(), 4);
         assert_eq!(icmp.get_sequence_number(), 2);
     }
+
+    #[test]
+    fn router_discovery() {
+        const RA: [u8; 32] = [
+            9, // icmp: type
+            0, // icmp: code
+            42, 235, // icmp: checksum
+            3,   // icmp: num addrs
+            2,   // icmp: addr entry size
+            7, 8, // icmp: lifetime
+            192, 168, 0, 1, // icmp: router address [0]
+            0, 0, 0, 0, // icmp: preference level [0]
+            192, 168, 0, 2, // icmp: router address [1]
+            0, 0, 0, 10, // icmp: preference level [1]
+            192, 168, 0, 3, // icmp: router address [2]
+            128, 0, 0, 0, // icmp: preference level [2]
+        ];
+
+        let ra = icmp::Message::parse(&RA[..])
+            .unwrap()
+            .downcast::<icmp::RouterAdvertisement>()
+            .unwrap();
+        assert_eq!(ra.get_num_addrs(), 3);
+        assert_eq!(ra.get_addr_entry_size(), 2);
+        assert_eq!(ra.get_lifetime(), 1800);
+
+        let mut addrs = ra.addresses();
+        assert_eq!(addrs.next(), Some((ipv4::Addr([192, 168, 0, 1]), 0)));
+        assert_eq!(addrs.next(), Some((ipv4::Addr([192, 168, 0, 2]), 10)));
+        assert_eq!(
+            addrs.next(),
+            Some((ipv4::Addr([192, 168, 0, 3]), icmp::PREFERENCE_NOT_DEFAULT))
+        );
+        assert_eq!(addrs.next(), None);
+        assert_eq!(ra.default_router(), Some(ipv4::Addr([192, 168, 0, 2])));
+
+        // ties go to the first address
+        let mut array = RA;
+        array[15] = 10;
+        array[3] = 225; // fix up the checksum
+        let ra = icmp::Message::parse(&array[..])
+            .unwrap()
+            .downcast::<icmp::RouterAdvertisement>()
+            .unwrap();
+        assert_eq!(ra.default_router(), Some(ipv4::Addr([192, 168, 0, 1])));
+
+        // advertises more addresses than it carries
+        let mut array = RA;
+        array[4] = 4;
+        array[2] = 41; // fix up the checksum
+        assert!(icmp::Message::parse(&array[..])
+            .unwrap()
+            .downcast::<icmp::RouterAdvertisement>()
+            .is_err());
+
+        let mut array = [0xff; 16];
+        let rs = icmp::Message::router_solicitation(&mut array[..]);
+        assert_eq!(
+            rs.update_checksum().as_bytes(),
+            &[10, 0, 245, 255, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn address_mask() {
+        const REPLY: [u8; 12] = [
+            18, // icmp: type
+            0,  // icmp: code
+            238, 251, // icmp: checksum
+            0, 1, // icmp: identifier
+            0, 2, // icmp: sequence number
+            255, 255, 255, 0, // icmp: address mask
+        ];
+
+        let reply = icmp::Message::parse(&REPLY[..])
+            .unwrap()
+            .downcast::<icmp::AddressMaskReply>()
+            .unwrap();
+        assert_eq!(reply.get_identifier(), 1);
+        assert_eq!(reply.get_sequence_number(), 2);
+        assert_eq!(reply.get_address_mask(), ipv4::Addr([255, 255, 255, 0]));
+
+        // no Address Mask field
+        let mut array = [0; 8];
+        array.copy_from_slice(&REPLY[..8]);
+        // fix up the checksum
+        array[2] = 237;
+        array[3] = 252;
+        assert!(icmp::Message::parse(&array[..])
+            .unwrap()
+            .downcast::<icmp::AddressMaskReply>()
+            .is_err());
+
+        let mut array = [0xff; 16];
+        let mut request = icmp::Message::address_mask_request(&mut array[..]);
+        request.set_identifier(1);
+        request.set_sequence_number(2);
+        let request = request.update_checksum();
+        assert_eq!(request.len(), 12);
+
+        let request = icmp::Message::parse(request.as_bytes())
+            .unwrap()
+            .downcast::<icmp::AddressMaskRequest>()
+            .unwrap();
+        assert_eq!(request.get_identifier(), 1);
+        assert_eq!(request.get_address_mask(), ipv4::Addr::UNSPECIFIED);
+    }
+
+    #[test]
+    fn pattern() {
+        let mut array = [0; 128];
+
+        let mut eth = ether::Frame::new(&mut array[..]);
+        eth.ipv4(|ip| {
+            ip.set_destination(IP_DST);
+            ip.set_source(IP_SRC);
+
+            ip.echo_request(|icmp| {
+                icmp.set_payload_pattern(icmp::Pattern::Repeat(&[0xde, 0xad, 0xbe]), 8);
+            });
+        });
+
+        let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+        let icmp = icmp::Message::parse(ip.payload())
+            .unwrap()
+            .downcast::<icmp::EchoRequest>()
+            .unwrap();
+        assert_eq!(
+            icmp.payload(),
+            &[0xde, 0xad, 0xbe, 0xde, 0xad, 0xbe, 0xde, 0xad]
+        );
+        assert!(icmp.matches_pattern(icmp::Pattern::Repeat(&[0xde, 0xad, 0xbe])));
+        assert!(!icmp.matches_pattern(icmp::Pattern::Incrementing));
+
+        let mut array = [0; 64];
+        let mut icmp = icmp::Message::new(&mut array[..]);
+        icmp.set_payload_pattern(icmp::Pattern::Incrementing, 32);
+        assert_eq!(icmp.len(), 40);
+        assert!(icmp.matches_pattern(icmp::Pattern::Incrementing));
+
+        // corrupted payload
+        icmp.payload_mut()[7] ^= 1;
+        assert!(!icmp.matches_pattern(icmp::Pattern::Incrementing));
+    }
 }
@@ -10,19 +10,33 @@
 //! - [RFC 2461: Neighbor Discovery for IP Version 6 (IPv6)][1]
 //!
 //! [1]: https://tools.ietf.org/html/rfc2461
+//!
+//! - [RFC 6550: RPL: IPv6 Routing Protocol for Low-Power and Lossy Networks][2]
+//!
+//! [2]: https://tools.ietf.org/html/rfc6550
+//!
+//! - [RFC 8106: IPv6 Router Advertisement Options for DNS Configuration][3]
+//!
+//! [3]: https://tools.ietf.org/html/rfc8106
+//!
+//! - [RFC 3810: Multicast Listener Discovery Version 2 (MLDv2) for IPv6][4]
+//!
+//! [4]: https://tools.ietf.org/html/rfc3810
 
 use core::{
     fmt,
     marker::PhantomData,
     ops::{Range, RangeFrom},
+    slice::ChunksExact,
 };
 
 use as_slice::{AsMutSlice, AsSlice};
 use byteorder::{ByteOrder, NetworkEndian as NE};
 use owning_slice::Truncate;
 
-pub use crate::icmp::{EchoReply, EchoRequest};
+pub use crate::icmp::{EchoReply, EchoRequest, Pattern};
 use crate::{
+    checksum,
     fmt::Quoted,
     ieee802154, ipv6, mac,
     sealed::Echo,
@@ -39,6 +53,15 @@ const PAYLOAD: RangeFrom<usize> = 4..;
 /// Header size
 pub const HEADER_SIZE: u8 = CHECKSUM.end as u8;
 
+/// Returns the maximum payload size of an ICMPv6 message given the maximum payload size of the
+/// IPv6 packet that contains it
+///
+/// NOTE the payload includes the message body (e.g. the Identifier and Sequence Number fields of
+/// Echo messages)
+pub const fn max_payload(ip_payload: u16) -> u16 {
+    ip_payload - HEADER_SIZE as u16
+}
+
 // Neighbor{Advertisement,Solicitation}
 const RESERVED0: usize = 4;
 
@@ -117,42 +140,18 @@ where
 
     /* Miscellaneous */
     pub(crate) fn compute_checksum(&self, src: ipv6::Addr, dest: ipv6::Addr) -> u16 {
-        const NEXT_HEADER: u8 = 58;
-
-        let mut sum: u32 = 0;
-
-        // Pseudo-header
-        for chunk in src.0.chunks_exact(2).chain(dest.0.chunks_exact(2)) {
-            sum += u32::from(NE::read_u16(chunk));
-        }
+        let bytes = self.as_slice();
 
         // XXX should this be just `as u16`?
-        let len = self.as_slice().len() as u32;
-        sum += len >> 16;
-        sum += len & 0xffff;
-
-        sum += u32::from(NEXT_HEADER);
-
-        // ICMPv6 message
-        for (i, chunk) in self.as_slice().chunks(2).enumerate() {
-            if i == 1 {
-                // this is the checksum field, skip
-                continue;
-            }
-
-            if chunk.len() == 1 {
-                sum += u32::from(chunk[0]) << 8;
-            } else {
-                sum += u32::from(NE::read_u16(chunk));
-            }
-        }
+        let len = bytes.len() as u32;
+        let mut state =
+            checksum::State::ipv6_pseudo_header(src, dest, ipv6::NextHeader::Ipv6Icmp, len);
 
-        // fold carry-over
-        while sum >> 16 != 0 {
-            sum = (sum & 0xffff) + (sum >> 16);
-        }
+        // ICMPv6 message; skip the checksum field
+        state.push(&bytes[..CHECKSUM.start]);
+        state.push(&bytes[CHECKSUM.end..]);
 
-        !(sum as u16)
+        state.finish()
     }
 
     /// Verifies the 'Checksum' field
@@ -599,6 +598,13 @@ where
     pub fn payload(&self) -> &[u8] {
         unsafe { self.as_slice().rf(SEQUENCE.end..) }
     }
+
+    /// Checks that the payload follows the given `pattern`
+    ///
+    /// Use this on an Echo Reply to detect corruption of the data echoed back by the remote host
+    pub fn matches_pattern(&self, pattern: Pattern<'_>) -> bool {
+        pattern.matches(self.payload())
+    }
 }
 
 impl<B, E> fmt::Debug for Message<B, E>
@@ -690,140 +696,1779 @@ where
     }
 }
 
-// See Section 4.6 of RFC 2461
-struct Options<'a> {
-    opts: &'a [u8],
+/* Router Advertisement (see Section 4.2 of RFC 4861) */
+const RA_CUR_HOP_LIMIT: usize = 4;
+const RA_FLAGS: usize = 5;
+mod other {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 6;
+    pub const SIZE: usize = 1;
 }
-
-struct OptionRef<'a> {
-    pub ty: OptionType,
-    pub contents: &'a [u8],
+mod managed {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::other::OFFSET + super::other::SIZE;
+    pub const SIZE: usize = 1;
 }
+const RA_ROUTER_LIFETIME: Range<usize> = 6..8;
+const RA_REACHABLE_TIME: Range<usize> = 8..12;
+const RA_RETRANS_TIMER: Range<usize> = 12..16;
+const RA_OPTIONS: RangeFrom<usize> = 16..;
 
-struct OptionMut<'a> {
-    pub ty: OptionType,
-    pub contents: &'a mut [u8],
+/// [Type state] Router Advertisement
+pub enum RouterAdvertisement {}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, RouterAdvertisement>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        // RFC 4861 - Section 6.1.2.  Validation of Router Advertisement Messages
+        // "ICMP Code is 0"
+        // "ICMP length (derived from the IP length) is 16 or more octets"
+        // "All included options have a length that is greater than zero"
+        // NOTE the IP Source Address and Hop Limit checks are up to the caller
+        if m.get_type() == Type::RouterAdvertisement
+            && m.get_code() == 0
+            && m.as_slice().len() >= RA_OPTIONS.start
+            && Options::are_valid(&m.as_slice()[RA_OPTIONS])
+        {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
 }
 
-impl<'a> Options<'a> {
-    // NOTE: Caller must ensure that `are_valid` returns `true` before using this as an iterator
-    unsafe fn new(opts: &'a [u8]) -> Self {
-        Options { opts }
+impl<B> Message<B, RouterAdvertisement>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'Cur Hop Limit' field; `0` means unspecified
+    pub fn get_cur_hop_limit(&self) -> u8 {
+        unsafe { *self.as_slice().gu(RA_CUR_HOP_LIMIT) }
     }
 
-    fn are_valid(mut opts: &'a [u8]) -> bool {
-        if opts.is_empty() {
-            return true;
+    /// Reads the 'Managed address configuration' flag
+    pub fn get_managed(&self) -> bool {
+        unsafe { get!(*self.as_slice().gu(RA_FLAGS), managed) == 1 }
+    }
+
+    /// Reads the 'Other configuration' flag
+    pub fn get_other(&self) -> bool {
+        unsafe { get!(*self.as_slice().gu(RA_FLAGS), other) == 1 }
+    }
+
+    /// Reads the 'Router Lifetime' field, in seconds
+    pub fn get_router_lifetime(&self) -> u16 {
+        unsafe { NE::read_u16(self.as_slice().r(RA_ROUTER_LIFETIME)) }
+    }
+
+    /// Reads the 'Reachable Time' field, in milliseconds; `0` means unspecified
+    pub fn get_reachable_time(&self) -> u32 {
+        unsafe { NE::read_u32(self.as_slice().r(RA_REACHABLE_TIME)) }
+    }
+
+    /// Reads the 'Retrans Timer' field, in milliseconds; `0` means unspecified
+    pub fn get_retrans_timer(&self) -> u32 {
+        unsafe { NE::read_u32(self.as_slice().r(RA_RETRANS_TIMER)) }
+    }
+
+    /// Reads the 'Source Link-layer Address' option
+    // NOTE this contains padding
+    pub fn get_source_ll(&self) -> Option<&[u8]> {
+        self.options()
+            .filter_map(|opt| {
+                if opt.ty == OptionType::SourceLinkLayerAddress {
+                    Some(opt.contents)
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+
+    /// Reads the 'MTU' option
+    pub fn get_mtu(&self) -> Option<u32> {
+        self.options()
+            .filter_map(|opt| {
+                // Reserved (2 octets) + MTU (4 octets)
+                if opt.ty == OptionType::Mtu && opt.contents.len() == 6 {
+                    Some(NE::read_u32(&opt.contents[2..]))
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+
+    /// Returns an iterator over the 'Prefix Information' options
+    pub fn prefixes(&self) -> Prefixes<'_> {
+        Prefixes {
+            opts: self.options(),
         }
+    }
 
-        loop {
-            if opts.len() < 2 {
-                // not big enough to contain the Type and Length
-                return false;
-            }
+    /// Returns an iterator over the 'Recursive DNS Server' (RDNSS) options
+    pub fn rdnss(&self) -> RdnssOptions<'_> {
+        RdnssOptions {
+            opts: self.options(),
+        }
+    }
 
-            let length = usize::from(8 * opts[1]);
+    /// Returns an iterator over the 'DNS Search List' (DNSSL) options
+    pub fn dnssl(&self) -> DnsslOptions<'_> {
+        DnsslOptions {
+            opts: self.options(),
+        }
+    }
 
-            if length == 0 {
-                // zero sized option
-                return false;
-            }
+    /* Private */
+    fn options(&self) -> Options<'_> {
+        unsafe { Options::new(self.as_slice().rf(RA_OPTIONS)) }
+    }
+}
 
-            if opts.len() == length {
-                return true;
-            } else if opts.len() < length {
-                return false;
-            } else {
-                opts = &opts[length..];
+impl<B> fmt::Debug for Message<B, RouterAdvertisement>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<RouterAdvertisement>")
+            .field("checksum", &self.get_checksum())
+            .field("cur_hop_limit", &self.get_cur_hop_limit())
+            .field("managed", &self.get_managed())
+            .field("other", &self.get_other())
+            .field("router_lifetime", &self.get_router_lifetime())
+            .field("reachable_time", &self.get_reachable_time())
+            .field("retrans_timer", &self.get_retrans_timer())
+            .finish()
+    }
+}
+
+// Prefix Information option; offsets are relative to the option contents (see Section 4.6.2 of
+// RFC 4861)
+const PI_PREFIX_LENGTH: usize = 0;
+const PI_FLAGS: usize = 1;
+mod autonomous {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 6;
+    pub const SIZE: usize = 1;
+}
+mod on_link {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::autonomous::OFFSET + super::autonomous::SIZE;
+    pub const SIZE: usize = 1;
+}
+const PI_VALID_LIFETIME: Range<usize> = 2..6;
+const PI_PREFERRED_LIFETIME: Range<usize> = 6..10;
+const PI_PREFIX: Range<usize> = 14..30;
+
+/// Iterator over the 'Prefix Information' options of a Router Advertisement
+pub struct Prefixes<'a> {
+    opts: Options<'a>,
+}
+
+impl<'a> Iterator for Prefixes<'a> {
+    type Item = PrefixInformation<'a>;
+
+    fn next(&mut self) -> Option<PrefixInformation<'a>> {
+        while let Some(opt) = self.opts.next() {
+            // NOTE options with an invalid length are ignored
+            if opt.ty == OptionType::PrefixInformation && opt.contents.len() == PI_PREFIX.end {
+                return Some(PrefixInformation {
+                    contents: opt.contents,
+                });
             }
         }
+
+        None
     }
 }
 
-impl<'a> Iterator for Options<'a> {
-    type Item = OptionRef<'a>;
+/// 'Prefix Information' option
+pub struct PrefixInformation<'a> {
+    contents: &'a [u8],
+}
 
-    fn next(&mut self) -> Option<OptionRef<'a>> {
-        if self.opts.is_empty() {
-            None
-        } else {
-            unsafe {
-                let ty = OptionType::from(*self.opts.gu(0));
-                let len = 8 * usize::from(*self.opts.gu(1));
-                let contents = self.opts.r(2..len);
+impl<'a> PrefixInformation<'a> {
+    /// Reads the 'Prefix Length' field
+    pub fn get_prefix_length(&self) -> u8 {
+        unsafe { *self.contents.gu(PI_PREFIX_LENGTH) }
+    }
 
-                self.opts = self.opts.rf(len..);
+    /// Reads the 'on-link' flag
+    pub fn get_on_link(&self) -> bool {
+        unsafe { get!(*self.contents.gu(PI_FLAGS), on_link) == 1 }
+    }
 
-                Some(OptionRef { ty, contents })
+    /// Reads the 'autonomous address-configuration' flag
+    pub fn get_autonomous(&self) -> bool {
+        unsafe { get!(*self.contents.gu(PI_FLAGS), autonomous) == 1 }
+    }
+
+    /// Reads the 'Valid Lifetime' field, in seconds; `0xffff_ffff` means infinity
+    pub fn get_valid_lifetime(&self) -> u32 {
+        unsafe { NE::read_u32(self.contents.r(PI_VALID_LIFETIME)) }
+    }
+
+    /// Reads the 'Preferred Lifetime' field, in seconds; `0xffff_ffff` means infinity
+    pub fn get_preferred_lifetime(&self) -> u32 {
+        unsafe { NE::read_u32(self.contents.r(PI_PREFERRED_LIFETIME)) }
+    }
+
+    /// Reads the 'Prefix' field
+    pub fn get_prefix(&self) -> ipv6::Addr {
+        unsafe { ipv6::Addr(*(self.contents.as_ptr().add(PI_PREFIX.start) as *const _)) }
+    }
+}
+
+impl<'a> fmt::Debug for PrefixInformation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::PrefixInformation")
+            .field("prefix", &self.get_prefix())
+            .field("prefix_length", &self.get_prefix_length())
+            .field("on_link", &self.get_on_link())
+            .field("autonomous", &self.get_autonomous())
+            .field("valid_lifetime", &self.get_valid_lifetime())
+            .field("preferred_lifetime", &self.get_preferred_lifetime())
+            .finish()
+    }
+}
+
+// RDNSS and DNSSL options; offsets are relative to the option contents (see Section 5 of RFC
+// 8106)
+const DNS_LIFETIME: Range<usize> = 2..6;
+const RDNSS_ADDRS: RangeFrom<usize> = 6..;
+const DNSSL_DOMAINS: RangeFrom<usize> = 6..;
+
+/// Iterator over the 'Recursive DNS Server' options of a Router Advertisement
+pub struct RdnssOptions<'a> {
+    opts: Options<'a>,
+}
+
+impl<'a> Iterator for RdnssOptions<'a> {
+    type Item = Rdnss<'a>;
+
+    fn next(&mut self) -> Option<Rdnss<'a>> {
+        while let Some(opt) = self.opts.next() {
+            // "The minimum value is 3 if one IPv6 address is contained in the option. Every
+            // additional RDNSS address increases the length by 2"
+            // NOTE options with an invalid length are ignored
+            if opt.ty == OptionType::Rdnss
+                && opt.contents.len() >= RDNSS_ADDRS.start + 16
+                && (opt.contents.len() - RDNSS_ADDRS.start) % 16 == 0
+            {
+                return Some(Rdnss {
+                    contents: opt.contents,
+                });
             }
         }
+
+        None
     }
 }
 
-struct OptionsMut<'a> {
-    opts: &'a mut [u8],
+/// 'Recursive DNS Server' (RDNSS) option
+pub struct Rdnss<'a> {
+    contents: &'a [u8],
 }
 
-impl<'a> OptionsMut<'a> {
-    fn new(opts: &'a mut [u8]) -> Self {
-        OptionsMut { opts }
+impl<'a> Rdnss<'a> {
+    /// Reads the 'Lifetime' field, in seconds; `0xffff_ffff` means infinity and `0` means that
+    /// the servers must no longer be used
+    pub fn get_lifetime(&self) -> u32 {
+        unsafe { NE::read_u32(self.contents.r(DNS_LIFETIME)) }
+    }
+
+    /// Returns an iterator over the addresses of the recursive DNS servers
+    pub fn addrs(&self) -> RdnssAddrs<'a> {
+        RdnssAddrs {
+            chunks: unsafe { self.contents.rf(RDNSS_ADDRS) }.chunks_exact(16),
+        }
     }
 }
 
-impl<'a> Iterator for OptionsMut<'a> {
-    type Item = OptionMut<'a>;
+impl<'a> fmt::Debug for Rdnss<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Rdnss")
+            .field("lifetime", &self.get_lifetime())
+            .finish()
+    }
+}
 
-    fn next(&mut self) -> Option<OptionMut<'a>> {
-        if self.opts.is_empty() {
-            None
-        } else {
-            unsafe {
-                let ty = OptionType::from(*self.opts.gu(0));
-                let len = 8 * usize::from(*self.opts.gu(1));
-                let contents = &mut *(self.opts.rm(2..len) as *mut [u8]);
+/// Iterator over the addresses of a 'Recursive DNS Server' option
+pub struct RdnssAddrs<'a> {
+    chunks: ChunksExact<'a, u8>,
+}
 
-                self.opts = &mut *(self.opts.rfm(len..) as *mut [u8]);
+impl<'a> Iterator for RdnssAddrs<'a> {
+    type Item = ipv6::Addr;
 
-                Some(OptionMut { ty, contents })
+    fn next(&mut self) -> Option<ipv6::Addr> {
+        self.chunks
+            .next()
+            .map(|chunk| unsafe { ipv6::Addr(*(chunk.as_ptr() as *const _)) })
+    }
+}
+
+/// Iterator over the 'DNS Search List' options of a Router Advertisement
+pub struct DnsslOptions<'a> {
+    opts: Options<'a>,
+}
+
+impl<'a> Iterator for DnsslOptions<'a> {
+    type Item = Dnssl<'a>;
+
+    fn next(&mut self) -> Option<Dnssl<'a>> {
+        while let Some(opt) = self.opts.next() {
+            // "The minimum value is 2 if at least one domain name is contained in the option"
+            // NOTE options with an invalid length are ignored
+            if opt.ty == OptionType::Dnssl && opt.contents.len() > DNSSL_DOMAINS.start {
+                return Some(Dnssl {
+                    contents: opt.contents,
+                });
             }
         }
+
+        None
     }
 }
 
-full_range!(
-    u8,
-    /// ICMPv6 types
-    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-    pub enum Type {
-        /// Echo request
-        EchoRequest = 128,
-        /// Echo reply
-        EchoReply = 129,
-        /// Router solicitation
-        RouterSolicitation = 133,
-        /// Router advertisement
-        RouterAdvertisement = 134,
-        /// Neighbor solicitation
-        NeighborSolicitation = 135,
-        /// Neighbor advertisement
-        NeighborAdvertisement = 136,
+/// 'DNS Search List' (DNSSL) option
+pub struct Dnssl<'a> {
+    contents: &'a [u8],
+}
+
+impl<'a> Dnssl<'a> {
+    /// Reads the 'Lifetime' field, in seconds; `0xffff_ffff` means infinity and `0` means that
+    /// the domain names must no longer be used
+    pub fn get_lifetime(&self) -> u32 {
+        unsafe { NE::read_u32(self.contents.r(DNS_LIFETIME)) }
     }
-);
 
-full_range!(
-    u8,
-    /// Option type
-    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-    enum OptionType {
-        // Source link-layer address
-        SourceLinkLayerAddress = 1,
-        // Target link-layer address
-        TargetLinkLayerAddress = 2,
-        // Prefix information
-        PrefixInformation = 3,
-        // Redirected header
-        RedirectedHeader = 4,
-        // MTU
-        Mtu = 5,
+    /// Returns an iterator over the domain names of the search list
+    pub fn domains(&self) -> Domains<'a> {
+        Domains {
+            bytes: unsafe { self.contents.rf(DNSSL_DOMAINS) },
+        }
     }
-);
+}
+
+impl<'a> fmt::Debug for Dnssl<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Dnssl")
+            .field("lifetime", &self.get_lifetime())
+            .finish()
+    }
+}
+
+/// Iterator over the domain names of a 'DNS Search List' option
+pub struct Domains<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Domains<'a> {
+    type Item = Domain<'a>;
+
+    fn next(&mut self) -> Option<Domain<'a>> {
+        // domain names are encoded as a sequence of labels terminated by an empty label; the
+        // option is padded with zeros
+        let mut end = 0;
+        loop {
+            let len = usize::from(*self.bytes.get(end)?);
+            if len == 0 {
+                break;
+            }
+            end += 1 + len;
+        }
+
+        if end == 0 {
+            // padding
+            self.bytes = &[];
+            return None;
+        }
+
+        let labels = unsafe { self.bytes.rt(..end) };
+        self.bytes = unsafe { self.bytes.rf(end + 1..) };
+        Some(Domain { labels })
+    }
+}
+
+/// A domain name
+///
+/// The `Display` implementation formats the name in dotted notation (e.g. `example.com`)
+pub struct Domain<'a> {
+    // labels without the terminating empty label
+    labels: &'a [u8],
+}
+
+impl<'a> Domain<'a> {
+    /// Returns an iterator over the labels of the domain name
+    pub fn labels(&self) -> Labels<'a> {
+        Labels { bytes: self.labels }
+    }
+}
+
+impl<'a> fmt::Debug for Domain<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self)
+    }
+}
+
+impl<'a> fmt::Display for Domain<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        for label in self.labels() {
+            if !first {
+                f.write_str(".")?;
+            }
+            first = false;
+
+            for byte in label {
+                fmt::Write::write_char(f, char::from(*byte))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Iterator over the labels of a domain name
+pub struct Labels<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let (len, rest) = self.bytes.split_first()?;
+        let len = usize::from(*len);
+
+        // NOTE `Domains` has already checked that all the labels are in bounds
+        let label = rest.get(..len)?;
+        self.bytes = rest.get(len..)?;
+        Some(label)
+    }
+}
+
+/* Packet Too Big (see Section 3.2 of RFC 4443) */
+const PTB_MTU: Range<usize> = 4..8;
+const PTB_INVOKING_PACKET: RangeFrom<usize> = 8..;
+// 'Destination Address' field of the invoking IPv6 packet
+const PTB_DESTINATION: Range<usize> = 32..48;
+
+/// [Type state] Packet Too Big
+pub enum PacketTooBig {}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, PacketTooBig>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        // NOTE the 'Code' field is ignored by the receiver
+        if m.get_type() == Type::PacketTooBig && m.as_slice().len() >= PTB_INVOKING_PACKET.start {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
+}
+
+impl<B> Message<B, PacketTooBig>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'MTU' field: the MTU of the next-hop link
+    pub fn get_mtu(&self) -> u32 {
+        unsafe { NE::read_u32(self.as_slice().r(PTB_MTU)) }
+    }
+
+    /// Returns the destination address of the packet that triggered this message
+    ///
+    /// This is the destination whose Path MTU must be updated. Returns `None` if the message
+    /// doesn't include enough of the invoking packet
+    pub fn get_invoking_destination(&self) -> Option<ipv6::Addr> {
+        if self.as_slice().len() >= PTB_DESTINATION.end {
+            Some(unsafe {
+                ipv6::Addr(*(self.as_slice().as_ptr().add(PTB_DESTINATION.start) as *const _))
+            })
+        } else {
+            None
+        }
+    }
+
+    /// View into the packet that triggered this message
+    ///
+    /// NOTE this is usually a truncated IPv6 packet
+    pub fn invoking_packet(&self) -> &[u8] {
+        unsafe { self.as_slice().rf(PTB_INVOKING_PACKET) }
+    }
+}
+
+impl<B> fmt::Debug for Message<B, PacketTooBig>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<PacketTooBig>")
+            .field("checksum", &self.get_checksum())
+            .field("mtu", &self.get_mtu())
+            .field("invoking_destination", &self.get_invoking_destination())
+            .finish()
+    }
+}
+
+/* Version 2 Multicast Listener Report (see Section 5.2 of RFC 3810) */
+const MLR_RESERVED: Range<usize> = 4..6;
+const MLR_NRECORDS: Range<usize> = 6..8;
+const MLR_RECORDS: RangeFrom<usize> = 8..;
+
+// Multicast Address Record
+const MAR_TYPE: usize = 0;
+const MAR_AUX_DATA_LEN: usize = 1;
+const MAR_NSOURCES: Range<usize> = 2..4;
+const MAR_ADDRESS: Range<usize> = 4..20;
+const MAR_SOURCES: RangeFrom<usize> = 20..;
+
+/// [Type state] Version 2 Multicast Listener Report
+pub enum MulticastListenerReport {}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, MulticastListenerReport>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        if m.get_type() == Type::MulticastListenerReport
+            && m.as_slice().len() >= MLR_RECORDS.start
+            && AddressRecords::are_valid(
+                unsafe { NE::read_u16(m.as_slice().r(MLR_NRECORDS)) },
+                unsafe { m.as_slice().rf(MLR_RECORDS) },
+            )
+        {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
+}
+
+impl<B> Message<B, MulticastListenerReport>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'Nr of Mcast Address Records' field
+    pub fn get_number_of_records(&self) -> u16 {
+        unsafe { NE::read_u16(self.as_slice().r(MLR_NRECORDS)) }
+    }
+
+    /// Returns an iterator over the Multicast Address Records
+    pub fn records(&self) -> AddressRecords<'_> {
+        AddressRecords {
+            records: unsafe { self.as_slice().rf(MLR_RECORDS) },
+            remaining: self.get_number_of_records(),
+        }
+    }
+}
+
+impl<B> Message<B, MulticastListenerReport>
+where
+    B: AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /* Constructors */
+    /// Transforms the input buffer into a Version 2 Multicast Listener Report that contains one
+    /// Multicast Address Record, with no sources and no auxiliary data, per entry in `records`
+    ///
+    /// To start listening to a group report it as `ChangeToExcludeMode`; to stop listening report
+    /// it as `ChangeToIncludeMode`. The checksum is *not* computed.
+    ///
+    /// NOTE RFC 3810 requires the report to be sent to `ipv6::Addr::ALL_MLDV2_ROUTERS` with a
+    /// Hop Limit of 1, a link-local source address and a Router Alert option in a Hop-by-Hop
+    /// Options header. This crate doesn't build extension headers so the caller must prepend the
+    /// latter
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the buffer is not large enough to contain the records or if the
+    /// message would be larger than 65535 bytes
+    pub fn multicast_listener_report(mut buffer: B, records: &[(RecordType, ipv6::Addr)]) -> Self {
+        let size = MLR_RECORDS.start + records.len() * MAR_SOURCES.start;
+        assert!(size <= usize::from(u16::MAX) && buffer.as_slice().len() >= size);
+
+        buffer.truncate(size as u16);
+
+        let bytes = buffer.as_mut_slice();
+        unsafe {
+            bytes.rm(MLR_RESERVED).copy_from_slice(&[0; 2]);
+            NE::write_u16(bytes.rm(MLR_NRECORDS), records.len() as u16);
+        }
+
+        for (record, (ty, addr)) in unsafe { bytes.rfm(MLR_RECORDS) }
+            .chunks_exact_mut(MAR_SOURCES.start)
+            .zip(records)
+        {
+            unsafe {
+                *record.gum(MAR_TYPE) = (*ty).into();
+                *record.gum(MAR_AUX_DATA_LEN) = 0;
+                NE::write_u16(record.rm(MAR_NSOURCES), 0);
+                record.rm(MAR_ADDRESS).copy_from_slice(&addr.0);
+            }
+        }
+
+        let mut m: Message<B, Unknown> = unsafe { Message::unchecked(buffer) };
+        m.set_type(Type::MulticastListenerReport);
+        m.set_code(0);
+        unsafe { Message::unchecked(m.buffer) }
+    }
+}
+
+impl<B> fmt::Debug for Message<B, MulticastListenerReport>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<MulticastListenerReport>")
+            .field("checksum", &self.get_checksum())
+            .field("number_of_records", &self.get_number_of_records())
+            .finish()
+    }
+}
+
+/// Iterator over the Multicast Address Records of a Multicast Listener Report
+pub struct AddressRecords<'a> {
+    records: &'a [u8],
+    remaining: u16,
+}
+
+impl<'a> AddressRecords<'a> {
+    fn are_valid(nrecords: u16, mut records: &[u8]) -> bool {
+        for _ in 0..nrecords {
+            if records.len() < MAR_SOURCES.start {
+                return false;
+            }
+
+            let size = unsafe { AddressRecord::size(records) };
+            if records.len() < size {
+                return false;
+            }
+
+            records = unsafe { records.rf(size..) };
+        }
+
+        true
+    }
+}
+
+impl<'a> Iterator for AddressRecords<'a> {
+    type Item = AddressRecord<'a>;
+
+    fn next(&mut self) -> Option<AddressRecord<'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        // NOTE(unsafe) `are_valid` checked that all the records fit in the message
+        unsafe {
+            let size = AddressRecord::size(self.records);
+            let bytes = self.records.rt(..size);
+            self.records = self.records.rf(size..);
+
+            Some(AddressRecord { bytes })
+        }
+    }
+}
+
+/// Multicast Address Record
+pub struct AddressRecord<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> AddressRecord<'a> {
+    /// Reads the 'Record Type' field
+    pub fn get_type(&self) -> RecordType {
+        RecordType::from(unsafe { *self.bytes.gu(MAR_TYPE) })
+    }
+
+    /// Reads the 'Multicast Address' field
+    pub fn get_multicast_address(&self) -> ipv6::Addr {
+        unsafe { ipv6::Addr(*(self.bytes.as_ptr().add(MAR_ADDRESS.start) as *const _)) }
+    }
+
+    /// Returns an iterator over the 'Source Address' fields
+    pub fn sources(&self) -> SourceAddrs<'a> {
+        let n = usize::from(self.get_number_of_sources());
+
+        SourceAddrs {
+            chunks: unsafe { self.bytes.r(MAR_SOURCES.start..MAR_SOURCES.start + 16 * n) }
+                .chunks_exact(16),
+        }
+    }
+
+    /// View into the 'Auxiliary Data' field
+    pub fn aux_data(&self) -> &'a [u8] {
+        let n = usize::from(self.get_number_of_sources());
+
+        unsafe { self.bytes.rf(MAR_SOURCES.start + 16 * n..) }
+    }
+
+    fn get_number_of_sources(&self) -> u16 {
+        unsafe { NE::read_u16(self.bytes.r(MAR_NSOURCES)) }
+    }
+
+    // NOTE(unsafe) caller must ensure that `record` contains at least the fixed part of a record
+    unsafe fn size(record: &[u8]) -> usize {
+        let nsources = usize::from(NE::read_u16(record.r(MAR_NSOURCES)));
+        let aux_data_len = usize::from(*record.gu(MAR_AUX_DATA_LEN));
+
+        MAR_SOURCES.start + 16 * nsources + 4 * aux_data_len
+    }
+}
+
+impl<'a> fmt::Debug for AddressRecord<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::AddressRecord")
+            .field("type", &self.get_type())
+            .field("multicast_address", &Quoted(self.get_multicast_address()))
+            .field("number_of_sources", &self.get_number_of_sources())
+            .finish()
+    }
+}
+
+/// Iterator over the source addresses of a Multicast Address Record
+pub struct SourceAddrs<'a> {
+    chunks: ChunksExact<'a, u8>,
+}
+
+impl<'a> Iterator for SourceAddrs<'a> {
+    type Item = ipv6::Addr;
+
+    fn next(&mut self) -> Option<ipv6::Addr> {
+        self.chunks
+            .next()
+            .map(|chunk| unsafe { ipv6::Addr(*(chunk.as_ptr() as *const _)) })
+    }
+}
+
+/* RPL control messages (see Section 6 of RFC 6550) */
+// Code field
+const RPL_DIS: u8 = 0x00;
+const RPL_DIO: u8 = 0x01;
+const RPL_DAO: u8 = 0x02;
+const RPL_DAO_ACK: u8 = 0x03;
+
+// DODAG Information Solicitation
+const DIS_OPTIONS: RangeFrom<usize> = 6..;
+
+// DODAG Information Object
+const DIO_INSTANCE_ID: usize = 4;
+const DIO_VERSION: usize = 5;
+const DIO_RANK: Range<usize> = 6..8;
+const DIO_G_MOP_PRF: usize = 8;
+mod prf {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 0;
+    pub const SIZE: usize = 3;
+}
+mod mop {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::prf::OFFSET + super::prf::SIZE;
+    pub const SIZE: usize = 3;
+}
+// NOTE 1 bit that must be zero sits between `mop` and `grounded`
+mod grounded {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::mop::OFFSET + super::mop::SIZE + 1;
+    pub const SIZE: usize = 1;
+}
+const DIO_DTSN: usize = 9;
+const DIO_DODAGID: Range<usize> = 12..28;
+const DIO_OPTIONS: RangeFrom<usize> = 28..;
+
+// Destination Advertisement Object
+const DAO_INSTANCE_ID: usize = 4;
+const DAO_FLAGS: usize = 5;
+mod dodagid_present {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 6;
+    pub const SIZE: usize = 1;
+}
+mod ack_request {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::dodagid_present::OFFSET + super::dodagid_present::SIZE;
+    pub const SIZE: usize = 1;
+}
+const DAO_SEQUENCE: usize = 7;
+const DAO_DODAGID: Range<usize> = 8..24;
+
+// Destination Advertisement Object Acknowledgment
+const DAO_ACK_INSTANCE_ID: usize = 4;
+const DAO_ACK_FLAGS: usize = 5;
+mod ack_dodagid_present {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 7;
+    pub const SIZE: usize = 1;
+}
+const DAO_ACK_SEQUENCE: usize = 6;
+const DAO_ACK_STATUS: usize = 7;
+const DAO_ACK_DODAGID: Range<usize> = 8..24;
+
+/// [Type state] DODAG Information Solicitation
+///
+/// NOTE secure RPL control messages are not supported
+pub enum Dis {}
+
+/// [Type state] DODAG Information Object
+///
+/// NOTE secure RPL control messages are not supported
+pub enum Dio {}
+
+/// [Type state] Destination Advertisement Object
+///
+/// NOTE secure RPL control messages are not supported
+pub enum Dao {}
+
+/// [Type state] Destination Advertisement Object Acknowledgment
+///
+/// NOTE secure RPL control messages are not supported
+pub enum DaoAck {}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, Dis>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        if m.get_type() == Type::RplControl
+            && m.get_code() == RPL_DIS
+            && m.as_slice().len() >= DIS_OPTIONS.start
+            && RplOptions::are_valid(&m.as_slice()[DIS_OPTIONS])
+        {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
+}
+
+impl<B> Message<B, Dis>
+where
+    B: AsSlice<Element = u8>,
+{
+    /// Returns an iterator over the RPL options of this message
+    pub fn options(&self) -> RplOptions<'_> {
+        unsafe { RplOptions::new(self.as_slice().rf(DIS_OPTIONS)) }
+    }
+}
+
+impl<B> fmt::Debug for Message<B, Dis>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<Dis>")
+            .field("checksum", &self.get_checksum())
+            .finish()
+    }
+}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, Dio>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        if m.get_type() == Type::RplControl
+            && m.get_code() == RPL_DIO
+            && m.as_slice().len() >= DIO_OPTIONS.start
+            && RplOptions::are_valid(&m.as_slice()[DIO_OPTIONS])
+        {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
+}
+
+impl<B> Message<B, Dio>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'RPLInstanceID' field
+    pub fn get_instance_id(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DIO_INSTANCE_ID) }
+    }
+
+    /// Reads the 'Version Number' field
+    pub fn get_version(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DIO_VERSION) }
+    }
+
+    /// Reads the 'Rank' field
+    pub fn get_rank(&self) -> u16 {
+        unsafe { NE::read_u16(self.as_slice().r(DIO_RANK)) }
+    }
+
+    /// Reads the 'Grounded' flag
+    pub fn get_grounded(&self) -> bool {
+        unsafe { get!(*self.as_slice().gu(DIO_G_MOP_PRF), grounded) == 1 }
+    }
+
+    /// Reads the 'Mode of Operation' field
+    pub fn get_mode_of_operation(&self) -> ModeOfOperation {
+        unsafe { ModeOfOperation::from(get!(*self.as_slice().gu(DIO_G_MOP_PRF), mop)) }
+    }
+
+    /// Reads the 'DODAGPreference' field
+    pub fn get_preference(&self) -> u8 {
+        unsafe { get!(*self.as_slice().gu(DIO_G_MOP_PRF), prf) }
+    }
+
+    /// Reads the 'Destination Advertisement Trigger Sequence Number' field
+    pub fn get_dtsn(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DIO_DTSN) }
+    }
+
+    /// Reads the 'DODAGID' field
+    pub fn get_dodag_id(&self) -> ipv6::Addr {
+        unsafe { ipv6::Addr(*(self.as_slice().as_ptr().add(DIO_DODAGID.start) as *const _)) }
+    }
+
+    /// Returns an iterator over the RPL options of this message
+    pub fn options(&self) -> RplOptions<'_> {
+        unsafe { RplOptions::new(self.as_slice().rf(DIO_OPTIONS)) }
+    }
+}
+
+impl<B> fmt::Debug for Message<B, Dio>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<Dio>")
+            .field("checksum", &self.get_checksum())
+            .field("instance_id", &self.get_instance_id())
+            .field("version", &self.get_version())
+            .field("rank", &self.get_rank())
+            .field("grounded", &self.get_grounded())
+            .field("mode_of_operation", &self.get_mode_of_operation())
+            .field("preference", &self.get_preference())
+            .field("dtsn", &self.get_dtsn())
+            .field("dodag_id", &Quoted(self.get_dodag_id()))
+            .finish()
+    }
+}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, Dao>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        if m.get_type() != Type::RplControl
+            || m.get_code() != RPL_DAO
+            || m.as_slice().len() < DAO_DODAGID.start
+        {
+            return Err(m);
+        }
+
+        let start = if get!(m.as_slice()[DAO_FLAGS], dodagid_present) == 1 {
+            DAO_DODAGID.end
+        } else {
+            DAO_DODAGID.start
+        };
+
+        if m.as_slice().len() >= start && RplOptions::are_valid(&m.as_slice()[start..]) {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
+}
+
+impl<B> Message<B, Dao>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'RPLInstanceID' field
+    pub fn get_instance_id(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DAO_INSTANCE_ID) }
+    }
+
+    /// Reads the 'K' flag; the sender expects a DAO-ACK in response
+    pub fn get_ack_request(&self) -> bool {
+        unsafe { get!(*self.as_slice().gu(DAO_FLAGS), ack_request) == 1 }
+    }
+
+    /// Reads the 'DAOSequence' field
+    pub fn get_sequence(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DAO_SEQUENCE) }
+    }
+
+    /// Reads the 'DODAGID' field, if present ('D' flag set)
+    pub fn get_dodag_id(&self) -> Option<ipv6::Addr> {
+        if self.has_dodag_id() {
+            Some(unsafe {
+                ipv6::Addr(*(self.as_slice().as_ptr().add(DAO_DODAGID.start) as *const _))
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the RPL options of this message
+    ///
+    /// In storing mode these are the 'RPL Target' and 'Transit Information' options
+    pub fn options(&self) -> RplOptions<'_> {
+        let start = if self.has_dodag_id() {
+            DAO_DODAGID.end
+        } else {
+            DAO_DODAGID.start
+        };
+
+        unsafe { RplOptions::new(self.as_slice().rf(start..)) }
+    }
+
+    /* Private */
+    fn has_dodag_id(&self) -> bool {
+        unsafe { get!(*self.as_slice().gu(DAO_FLAGS), dodagid_present) == 1 }
+    }
+}
+
+impl<B> fmt::Debug for Message<B, Dao>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<Dao>")
+            .field("checksum", &self.get_checksum())
+            .field("instance_id", &self.get_instance_id())
+            .field("ack_request", &self.get_ack_request())
+            .field("sequence", &self.get_sequence())
+            .field("dodag_id", &self.get_dodag_id().map(Quoted))
+            .finish()
+    }
+}
+
+impl<B> TryFrom<Message<B, Unknown>> for Message<B, DaoAck>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Message<B, Unknown>;
+
+    fn try_from(m: Message<B, Unknown>) -> Result<Self, Message<B, Unknown>> {
+        if m.get_type() != Type::RplControl
+            || m.get_code() != RPL_DAO_ACK
+            || m.as_slice().len() < DAO_ACK_DODAGID.start
+        {
+            return Err(m);
+        }
+
+        let start = if get!(m.as_slice()[DAO_ACK_FLAGS], ack_dodagid_present) == 1 {
+            DAO_ACK_DODAGID.end
+        } else {
+            DAO_ACK_DODAGID.start
+        };
+
+        if m.as_slice().len() >= start && RplOptions::are_valid(&m.as_slice()[start..]) {
+            Ok(unsafe { Message::unchecked(m.buffer) })
+        } else {
+            Err(m)
+        }
+    }
+}
+
+impl<B> Message<B, DaoAck>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'RPLInstanceID' field
+    pub fn get_instance_id(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DAO_ACK_INSTANCE_ID) }
+    }
+
+    /// Reads the 'DAOSequence' field
+    pub fn get_sequence(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DAO_ACK_SEQUENCE) }
+    }
+
+    /// Reads the 'Status' field
+    ///
+    /// `0` means unqualified acceptance; values of `128` or greater indicate rejection
+    pub fn get_status(&self) -> u8 {
+        unsafe { *self.as_slice().gu(DAO_ACK_STATUS) }
+    }
+
+    /// Reads the 'DODAGID' field, if present ('D' flag set)
+    pub fn get_dodag_id(&self) -> Option<ipv6::Addr> {
+        if unsafe { get!(*self.as_slice().gu(DAO_ACK_FLAGS), ack_dodagid_present) == 1 } {
+            Some(unsafe {
+                ipv6::Addr(*(self.as_slice().as_ptr().add(DAO_ACK_DODAGID.start) as *const _))
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<B> fmt::Debug for Message<B, DaoAck>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::Message<DaoAck>")
+            .field("checksum", &self.get_checksum())
+            .field("instance_id", &self.get_instance_id())
+            .field("sequence", &self.get_sequence())
+            .field("status", &self.get_status())
+            .field("dodag_id", &self.get_dodag_id().map(Quoted))
+            .finish()
+    }
+}
+
+full_range!(
+    u8,
+    /// RPL Mode of Operation
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum ModeOfOperation {
+        /// No downward routes maintained by RPL
+        NoDownwardRoutes = 0,
+        /// Non-Storing Mode of Operation
+        NonStoring = 1,
+        /// Storing Mode of Operation with no multicast support
+        Storing = 2,
+        /// Storing Mode of Operation with multicast support
+        StoringMulticast = 3,
+    }
+);
+
+full_range!(
+    u8,
+    /// RPL control message option type
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum RplOptionType {
+        /// Pad1
+        Pad1 = 0x00,
+        /// PadN
+        PadN = 0x01,
+        /// DAG Metric Container
+        DagMetricContainer = 0x02,
+        /// Route Information
+        RouteInformation = 0x03,
+        /// DODAG Configuration
+        DodagConfiguration = 0x04,
+        /// RPL Target
+        RplTarget = 0x05,
+        /// Transit Information
+        TransitInformation = 0x06,
+        /// Solicited Information
+        SolicitedInformation = 0x07,
+        /// Prefix Information
+        PrefixInformation = 0x08,
+        /// RPL Target Descriptor
+        RplTargetDescriptor = 0x09,
+    }
+);
+
+/// Iterator over the options of a RPL control message
+///
+/// Padding options (`Pad1` and `PadN`) are skipped
+pub struct RplOptions<'a> {
+    opts: &'a [u8],
+}
+
+/// A RPL control message option
+#[derive(Clone, Copy)]
+pub struct RplOption<'a> {
+    ty: RplOptionType,
+    contents: &'a [u8],
+}
+
+impl<'a> RplOption<'a> {
+    /// Returns the 'Option Type' of this option
+    pub fn get_type(&self) -> RplOptionType {
+        self.ty
+    }
+
+    /// Returns the contents of this option (the bytes after the 'Option Length' field)
+    pub fn contents(&self) -> &'a [u8] {
+        self.contents
+    }
+}
+
+impl<'a> fmt::Debug for RplOption<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("icmpv6::RplOption")
+            .field("type", &self.ty)
+            .field("contents", &self.contents)
+            .finish()
+    }
+}
+
+impl<'a> RplOptions<'a> {
+    // NOTE: Caller must ensure that `are_valid` returns `true` before using this as an iterator
+    unsafe fn new(opts: &'a [u8]) -> Self {
+        RplOptions { opts }
+    }
+
+    // See Section 6.7.1 of RFC 6550
+    fn are_valid(mut opts: &[u8]) -> bool {
+        while !opts.is_empty() {
+            if opts[0] == RplOptionType::Pad1.into() {
+                opts = &opts[1..];
+                continue;
+            }
+
+            if opts.len() < 2 {
+                // not big enough to contain the Type and Length
+                return false;
+            }
+
+            let length = 2 + usize::from(opts[1]);
+
+            if opts.len() < length {
+                return false;
+            }
+
+            opts = &opts[length..];
+        }
+
+        true
+    }
+}
+
+impl<'a> Iterator for RplOptions<'a> {
+    type Item = RplOption<'a>;
+
+    fn next(&mut self) -> Option<RplOption<'a>> {
+        while !self.opts.is_empty() {
+            unsafe {
+                let ty = RplOptionType::from(*self.opts.gu(0));
+
+                if ty == RplOptionType::Pad1 {
+                    self.opts = self.opts.rf(1..);
+                    continue;
+                }
+
+                let len = 2 + usize::from(*self.opts.gu(1));
+                let contents = self.opts.r(2..len);
+
+                self.opts = self.opts.rf(len..);
+
+                if ty != RplOptionType::PadN {
+                    return Some(RplOption { ty, contents });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// See Section 4.6 of RFC 2461
+struct Options<'a> {
+    opts: &'a [u8],
+}
+
+struct OptionRef<'a> {
+    pub ty: OptionType,
+    pub contents: &'a [u8],
+}
+
+struct OptionMut<'a> {
+    pub ty: OptionType,
+    pub contents: &'a mut [u8],
+}
+
+impl<'a> Options<'a> {
+    // NOTE: Caller must ensure that `are_valid` returns `true` before using this as an iterator
+    unsafe fn new(opts: &'a [u8]) -> Self {
+        Options { opts }
+    }
+
+    fn are_valid(mut opts: &'a [u8]) -> bool {
+        if opts.is_empty() {
+            return true;
+        }
+
+        loop {
+            if opts.len() < 2 {
+                // not big enough to contain the Type and Length
+                return false;
+            }
+
+            let length = 8 * usize::from(opts[1]);
+
+            if length == 0 {
+                // zero sized option
+                return false;
+            }
+
+            if opts.len() == length {
+                return true;
+            } else if opts.len() < length {
+                return false;
+            } else {
+                opts = &opts[length..];
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Options<'a> {
+    type Item = OptionRef<'a>;
+
+    fn next(&mut self) -> Option<OptionRef<'a>> {
+        if self.opts.is_empty() {
+            None
+        } else {
+            unsafe {
+                let ty = OptionType::from(*self.opts.gu(0));
+                let len = 8 * usize::from(*self.opts.gu(1));
+                let contents = self.opts.r(2..len);
+
+                self.opts = self.opts.rf(len..);
+
+                Some(OptionRef { ty, contents })
+            }
+        }
+    }
+}
+
+struct OptionsMut<'a> {
+    opts: &'a mut [u8],
+}
+
+impl<'a> OptionsMut<'a> {
+    fn new(opts: &'a mut [u8]) -> Self {
+        OptionsMut { opts }
+    }
+}
+
+impl<'a> Iterator for OptionsMut<'a> {
+    type Item = OptionMut<'a>;
+
+    fn next(&mut self) -> Option<OptionMut<'a>> {
+        if self.opts.is_empty() {
+            None
+        } else {
+            unsafe {
+                let ty = OptionType::from(*self.opts.gu(0));
+                let len = 8 * usize::from(*self.opts.gu(1));
+                let contents = &mut *(self.opts.rm(2..len) as *mut [u8]);
+
+                self.opts = &mut *(self.opts.rfm(len..) as *mut [u8]);
+
+                Some(OptionMut { ty, contents })
+            }
+        }
+    }
+}
+
+full_range!(
+    u8,
+    /// ICMPv6 types
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Type {
+        /// Packet too big
+        PacketTooBig = 2,
+        /// Echo request
+        EchoRequest = 128,
+        /// Echo reply
+        EchoReply = 129,
+        /// Router solicitation
+        RouterSolicitation = 133,
+        /// Router advertisement
+        RouterAdvertisement = 134,
+        /// Neighbor solicitation
+        NeighborSolicitation = 135,
+        /// Neighbor advertisement
+        NeighborAdvertisement = 136,
+        /// Version 2 Multicast Listener Report (RFC 3810)
+        MulticastListenerReport = 143,
+        /// RPL control message
+        RplControl = 155,
+    }
+);
+
+full_range!(
+    u8,
+    /// Multicast Address Record types (see Section 5.2.12 of RFC 3810)
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum RecordType {
+        /// The listener is in INCLUDE mode for the listed sources
+        ModeIsInclude = 1,
+        /// The listener is in EXCLUDE mode for the listed sources
+        ModeIsExclude = 2,
+        /// The listener changed to INCLUDE mode; with no sources this means leaving the group
+        ChangeToIncludeMode = 3,
+        /// The listener changed to EXCLUDE mode; with no sources this means joining the group
+        ChangeToExcludeMode = 4,
+        /// The listener wants to hear from additional sources
+        AllowNewSources = 5,
+        /// The listener no longer wants to hear from some sources
+        BlockOldSources = 6,
+    }
+);
+
+full_range!(
+    u8,
+    /// Option type
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    enum OptionType {
+        // Source link-layer address
+        SourceLinkLayerAddress = 1,
+        // Target link-layer address
+        TargetLinkLayerAddress = 2,
+        // Prefix information
+        PrefixInformation = 3,
+        // Redirected header
+        RedirectedHeader = 4,
+        // MTU
+        Mtu = 5,
+        // Recursive DNS Server (RFC 8106)
+        Rdnss = 25,
+        // DNS Search List (RFC 8106)
+        Dnssl = 31,
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{icmpv6, ipv6};
+
+    #[test]
+    fn router_advertisement() {
+        #[rustfmt::skip]
+        const BYTES: &[u8] = &[
+            134, // icmpv6: type
+            0, // icmpv6: code
+            0, 0, // icmpv6: checksum
+            64, // ra: Cur Hop Limit
+            0b0100_0000, // ra: M | O | Reserved
+            0x07, 0x08, // ra: Router Lifetime
+            0, 0, 0, 0, // ra: Reachable Time
+            0, 0, 0, 0, // ra: Retrans Timer
+            1, 1, // option: Source Link-layer Address
+            0x20, 0x18, 0x03, 0x01, 0x00, 0x00,
+            5, 1, // option: MTU
+            0, 0, 0, 0, 0x05, 0xdc,
+            3, 4, // option: Prefix Information
+            64, 0b1100_0000, 0xff, 0xff, 0xff, 0xff, 0, 0, 0x0e, 0x10, 0, 0, 0, 0,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            25, 5, // option: RDNSS
+            0, 0, 0, 0, 0x0e, 0x10,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x53,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x53,
+            31, 4, // option: DNSSL
+            0, 0, 0, 0, 0x0e, 0x10,
+            7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'c', b'o', b'm', 0,
+            3, b'l', b'a', b'n', 0,
+            0, 0, 0, 0, 0, 0, // padding
+        ];
+
+        const PREFIX: ipv6::Addr =
+            ipv6::Addr([0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+        let m = icmpv6::Message::parse(BYTES)
+            .unwrap()
+            .downcast::<icmpv6::RouterAdvertisement>()
+            .unwrap();
+
+        assert_eq!(m.get_cur_hop_limit(), 64);
+        assert!(!m.get_managed());
+        assert!(m.get_other());
+        assert_eq!(m.get_router_lifetime(), 1800);
+        assert_eq!(m.get_reachable_time(), 0);
+        assert_eq!(m.get_retrans_timer(), 0);
+        assert_eq!(
+            m.get_source_ll(),
+            Some(&[0x20, 0x18, 0x03, 0x01, 0x00, 0x00][..])
+        );
+        assert_eq!(m.get_mtu(), Some(1500));
+
+        let mut prefixes = m.prefixes();
+        let pi = prefixes.next().unwrap();
+        assert_eq!(pi.get_prefix(), PREFIX);
+        assert_eq!(pi.get_prefix_length(), 64);
+        assert!(pi.get_on_link());
+        assert!(pi.get_autonomous());
+        assert_eq!(pi.get_valid_lifetime(), 0xffff_ffff);
+        assert_eq!(pi.get_preferred_lifetime(), 3600);
+        assert!(prefixes.next().is_none());
+
+        let mut rdnss = m.rdnss();
+        let opt = rdnss.next().unwrap();
+        assert_eq!(opt.get_lifetime(), 3600);
+        let mut addrs = opt.addrs();
+        assert_eq!(addrs.next().unwrap().0[15], 0x53);
+        assert_eq!(addrs.next().unwrap().0[14], 0x01);
+        assert!(addrs.next().is_none());
+        assert!(rdnss.next().is_none());
+
+        let mut dnssl = m.dnssl();
+        let opt = dnssl.next().unwrap();
+        assert_eq!(opt.get_lifetime(), 3600);
+        let mut domains = opt.domains();
+        let domain = domains.next().unwrap();
+        let mut labels = domain.labels();
+        assert_eq!(labels.next(), Some(&b"example"[..]));
+        assert_eq!(labels.next(), Some(&b"com"[..]));
+        assert_eq!(labels.next(), None);
+        let domain = domains.next().unwrap();
+        assert_eq!(domain.labels().next(), Some(&b"lan"[..]));
+        assert!(domains.next().is_none());
+        assert!(dnssl.next().is_none());
+    }
+
+    #[test]
+    fn oversized_option() {
+        let mut bytes = [0; 16 + 8];
+        bytes[0] = 134; // icmpv6: type
+
+        // 33 * 8 = 264 bytes but only 8 are present
+        bytes[16] = 5; // option: MTU
+        bytes[17] = 33;
+        assert!(icmpv6::Message::parse(&bytes[..])
+            .unwrap()
+            .downcast::<icmpv6::RouterAdvertisement>()
+            .is_err());
+
+        // 32 * 8 = 256 bytes
+        let mut bytes = [0; 16 + 256];
+        bytes[0] = 134; // icmpv6: type
+        bytes[16] = 5; // option: MTU
+        bytes[17] = 32;
+        assert!(icmpv6::Message::parse(&bytes[..])
+            .unwrap()
+            .downcast::<icmpv6::RouterAdvertisement>()
+            .is_ok());
+        assert!(icmpv6::Message::parse(&bytes[..255 + 16])
+            .unwrap()
+            .downcast::<icmpv6::RouterAdvertisement>()
+            .is_err());
+    }
+
+    #[test]
+    fn packet_too_big() {
+        const DEST: ipv6::Addr =
+            ipv6::Addr([0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+
+        let mut bytes = [0; 56];
+        bytes[0] = 2; // icmpv6: type
+        bytes[4..8].copy_from_slice(&[0, 0, 0x05, 0x00]); // ptb: MTU
+        bytes[8] = 0x60; // ipv6: version
+        bytes[32..48].copy_from_slice(&DEST.0); // ipv6: destination
+
+        let m = icmpv6::Message::parse(&bytes[..])
+            .unwrap()
+            .downcast::<icmpv6::PacketTooBig>()
+            .unwrap();
+
+        assert_eq!(m.get_mtu(), 1280);
+        assert_eq!(m.get_invoking_destination(), Some(DEST));
+        assert_eq!(m.invoking_packet().len(), 48);
+
+        // too short to contain the destination address of the invoking packet
+        let m = icmpv6::Message::parse(&bytes[..40])
+            .unwrap()
+            .downcast::<icmpv6::PacketTooBig>()
+            .unwrap();
+        assert_eq!(m.get_invoking_destination(), None);
+    }
+
+    #[test]
+    fn multicast_listener_report() {
+        const GROUP: ipv6::Addr = ipv6::Addr([
+            0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0xff, 0x12, 0x34, 0x56,
+        ]);
+        const SOURCE: ipv6::Addr =
+            ipv6::Addr([0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+        #[rustfmt::skip]
+        const BYTES: &[u8] = &[
+            143, // icmpv6: type
+            0, // icmpv6: code
+            0, 0, // icmpv6: checksum
+            0, 0, // mlr: Reserved
+            0, 2, // mlr: Nr of Mcast Address Records
+            4, // record: Record Type
+            0, // record: Aux Data Len
+            0, 0, // record: Number of Sources
+            0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0xff, 0x12, 0x34, 0x56,
+            1, // record: Record Type
+            1, // record: Aux Data Len
+            0, 1, // record: Number of Sources
+            0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0xff, 0x12, 0x34, 0x56,
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            1, 2, 3, 4, // record: Auxiliary Data
+        ];
+
+        let m = icmpv6::Message::parse(BYTES)
+            .unwrap()
+            .downcast::<icmpv6::MulticastListenerReport>()
+            .unwrap();
+
+        assert_eq!(m.get_number_of_records(), 2);
+
+        let mut records = m.records();
+        let record = records.next().unwrap();
+        assert_eq!(record.get_type(), icmpv6::RecordType::ChangeToExcludeMode);
+        assert_eq!(record.get_multicast_address(), GROUP);
+        assert!(record.sources().next().is_none());
+        assert!(record.aux_data().is_empty());
+
+        let record = records.next().unwrap();
+        assert_eq!(record.get_type(), icmpv6::RecordType::ModeIsInclude);
+        assert_eq!(record.get_multicast_address(), GROUP);
+        let mut sources = record.sources();
+        assert_eq!(sources.next(), Some(SOURCE));
+        assert!(sources.next().is_none());
+        assert_eq!(record.aux_data(), &[1, 2, 3, 4]);
+        assert!(records.next().is_none());
+
+        // the second record is truncated
+        assert!(icmpv6::Message::parse(&BYTES[..BYTES.len() - 1])
+            .unwrap()
+            .downcast::<icmpv6::MulticastListenerReport>()
+            .is_err());
+
+        // builder
+        const SRC: ipv6::Addr = ipv6::Addr([
+            0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x12, 0x34, 0x56, 0x78,
+        ]);
+
+        let mut buffer = [0xff; 64];
+        let mut m = icmpv6::Message::multicast_listener_report(
+            &mut buffer[..],
+            &[
+                (icmpv6::RecordType::ChangeToExcludeMode, GROUP),
+                (icmpv6::RecordType::ChangeToIncludeMode, ipv6::Addr::MDNS),
+            ],
+        );
+        m.update_checksum(SRC, ipv6::Addr::ALL_MLDV2_ROUTERS);
+
+        assert_eq!(&m.as_bytes()[4..28], &BYTES[4..28]);
+        assert_eq!(m.as_bytes().len(), 48);
+
+        let m = icmpv6::Message::parse(m.as_bytes())
+            .unwrap()
+            .downcast::<icmpv6::MulticastListenerReport>()
+            .unwrap();
+        assert!(m.verify_checksum(SRC, ipv6::Addr::ALL_MLDV2_ROUTERS));
+
+        let mut records = m.records();
+        records.next().unwrap();
+        let record = records.next().unwrap();
+        assert_eq!(record.get_type(), icmpv6::RecordType::ChangeToIncludeMode);
+        assert_eq!(record.get_multicast_address(), ipv6::Addr::MDNS);
+        assert!(record.sources().next().is_none());
+        assert!(records.next().is_none());
+    }
+
+    #[test]
+    fn dio() {
+        #[rustfmt::skip]
+        const BYTES: &[u8] = &[
+            155, // icmpv6: type
+            1, // icmpv6: code
+            0, 0, // icmpv6: checksum
+            30, // dio: RPLInstanceID
+            240, // dio: Version Number
+            1, 0, // dio: Rank
+            0b1001_0000, // dio: G | 0 | MOP | Prf
+            240, // dio: DTSN
+            0, // dio: Flags
+            0, // dio: Reserved
+            0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, // dio: DODAGID
+            0, // option: Pad1
+            1, 1, 0, // option: PadN
+            4, 14, // option: DODAG Configuration
+            0, 20, 2, 10, 0, 1, 1, 0, 0, 1, 0, 0xff, 0xff, 0xff,
+        ];
+
+        let m = icmpv6::Message::parse(BYTES)
+            .unwrap()
+            .downcast::<icmpv6::Dio>()
+            .unwrap();
+
+        assert_eq!(m.get_instance_id(), 30);
+        assert_eq!(m.get_version(), 240);
+        assert_eq!(m.get_rank(), 256);
+        assert!(m.get_grounded());
+        assert_eq!(m.get_mode_of_operation(), icmpv6::ModeOfOperation::Storing);
+        assert_eq!(m.get_preference(), 0);
+        assert_eq!(m.get_dtsn(), 240);
+        assert_eq!(
+            m.get_dodag_id(),
+            ipv6::Addr([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1])
+        );
+
+        let mut opts = m.options();
+        let opt = opts.next().unwrap();
+        assert_eq!(opt.get_type(), icmpv6::RplOptionType::DodagConfiguration);
+        assert_eq!(opt.contents().len(), 14);
+        assert!(opts.next().is_none());
+    }
+
+    #[test]
+    fn dao() {
+        #[rustfmt::skip]
+        const BYTES: &[u8] = &[
+            155, // icmpv6: type
+            2, // icmpv6: code
+            0, 0, // icmpv6: checksum
+            30, // dao: RPLInstanceID
+            0b1000_0000, // dao: K | D | Flags
+            0, // dao: Reserved
+            7, // dao: DAOSequence
+            5, 18, // option: RPL Target
+            0, 128, 0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2,
+            6, 4, // option: Transit Information
+            0, 0, 0, 30,
+        ];
+
+        let m = icmpv6::Message::parse(BYTES)
+            .unwrap()
+            .downcast::<icmpv6::Dao>()
+            .unwrap();
+
+        assert_eq!(m.get_instance_id(), 30);
+        assert!(m.get_ack_request());
+        assert_eq!(m.get_sequence(), 7);
+        assert_eq!(m.get_dodag_id(), None);
+
+        let mut opts = m.options();
+        assert_eq!(
+            opts.next().unwrap().get_type(),
+            icmpv6::RplOptionType::RplTarget
+        );
+        assert_eq!(
+            opts.next().unwrap().get_type(),
+            icmpv6::RplOptionType::TransitInformation
+        );
+        assert!(opts.next().is_none());
+
+        // truncated option
+        assert!(icmpv6::Message::parse(&BYTES[..BYTES.len() - 1])
+            .unwrap()
+            .downcast::<icmpv6::Dao>()
+            .is_err());
+    }
+}
@@ -5,6 +5,8 @@
 //! - [IEEE 802.15.4-2003 standard][standard], Section 7.2.1 General MAC frame format
 //!
 //! [standard]: https://www.iith.ac.in/~tbr/teaching/docs/802.15.4-2003.pdf
+//!
+//! - IEEE 802.15.4-2006 standard, Section 7.6.2 Auxiliary security header
 
 // NOTE(dev) unlike other networking protocol 802.15.4 uses the LITTLE endian byte order
 
@@ -61,6 +63,12 @@ mod dest_addr_mode {
     pub const SIZE: u8 = 2;
 }
 
+mod frame_version {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: u8 = 4;
+    pub const SIZE: u8 = 2;
+}
+
 mod src_addr_mode {
     pub const MASK: u8 = (1 << SIZE) - 1;
     pub const OFFSET: u8 = 6;
@@ -72,6 +80,26 @@ const SEQUENCE: usize = 2;
 
 const HEADER_SIZE: u8 = SEQUENCE as u8 + 1;
 
+/* Auxiliary security header (Section 7.6.2 of 802.15.4-2006) */
+// Security control
+mod security_level {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: u8 = 0;
+    pub const SIZE: u8 = 3;
+}
+
+mod key_id_mode {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: u8 = super::security_level::OFFSET + super::security_level::SIZE;
+    pub const SIZE: u8 = 2;
+}
+
+// Security control + Frame counter
+const AUX_HEADER_SIZE: u8 = 5;
+
+// 802.15.4 frames use a version number of 1 when the auxiliary security header is present
+const FRAME_VERSION_2006: u8 = 0b01;
+
 /// IEEE 802.15.4 MAC frame
 #[derive(Clone, Copy)]
 pub struct Frame<BUFFER>
@@ -163,6 +191,16 @@ where
                 len += 2;
             }
 
+            // 7.6.2 Auxiliary security header
+            //
+            // "The Auxiliary Security Header field [..] shall be present only if the Security
+            // Enabled subfield [..] is set to one."
+            if get!(slice[CONTROLL], security_enabled) == 1 {
+                let security_control = *slice.get(usize::from(len)).ok_or(())?;
+
+                len += AUX_HEADER_SIZE + KeyIdentifier::size(get!(security_control, key_id_mode));
+            }
+
             if slice.len() < usize::from(len) {
                 // too small
                 Err(())
@@ -303,7 +341,51 @@ where
         })
     }
 
+    /// Reads the 'Auxiliary security header' field
+    ///
+    /// Returns `None` if the 'Security enabled' field is not set
+    pub fn get_aux_security_header(&self) -> Option<AuxSecurityHeader> {
+        if !self.get_security_enabled() {
+            return None;
+        }
+
+        // NOTE(unsafe) `parse` checked that the auxiliary security header, including the key
+        // identifier its 'Key identifier mode' calls for, fits in the header
+        let start = self.aux_start();
+        let bytes = unsafe { self.as_slice().r(start..usize::from(self.payload)) };
+
+        let sc = unsafe { *bytes.gu(0) };
+        let frame_counter = LE::read_u32(unsafe { bytes.r(1..5) });
+        let key = unsafe { bytes.rf(5..) };
+        let key_identifier = match get!(sc, key_id_mode) {
+            0 => KeyIdentifier::Implicit,
+            1 => KeyIdentifier::Index(unsafe { *key.gu(0) }),
+            2 => unsafe {
+                KeyIdentifier::Source4 {
+                    source: [*key.gu(0), *key.gu(1), *key.gu(2), *key.gu(3)],
+                    index: *key.gu(4),
+                }
+            },
+            _ => {
+                let mut source = [0; 8];
+                source.copy_from_slice(unsafe { key.rt(..8) });
+                KeyIdentifier::Source8 {
+                    source,
+                    index: unsafe { *key.gu(8) },
+                }
+            }
+        };
+
+        Some(AuxSecurityHeader {
+            security_level: SecurityLevel::from(get!(sc, security_level)),
+            frame_counter,
+            key_identifier,
+        })
+    }
+
     /// Returns an immutable view into the header
+    ///
+    /// NOTE this includes the auxiliary security header, if present
     pub fn header(&self) -> &[u8] {
         unsafe { self.as_slice().rt(..usize::from(self.payload)) }
     }
@@ -329,6 +411,33 @@ where
         self.buffer.as_slice()
     }
 
+    // start of the auxiliary security header; i.e. end of the addressing fields
+    fn aux_start(&self) -> usize {
+        let mut start = 3;
+
+        if self.get_dest_pan_id().is_some() {
+            start += 2;
+        }
+
+        start += match self.get_dest_addr_mode() {
+            AddrMode::None => 0,
+            AddrMode::Short => 2,
+            AddrMode::Extended => 8,
+        };
+
+        if self.get_src_pan_id().is_some() {
+            start += 2;
+        }
+
+        start += match self.get_src_addr_mode() {
+            AddrMode::None => 0,
+            AddrMode::Short => 2,
+            AddrMode::Extended => 8,
+        };
+
+        start
+    }
+
     fn header_(&self) -> &[u8; HEADER_SIZE as usize] {
         debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
 
@@ -379,6 +488,10 @@ where
             _ => {}
         }
 
+        if let Some(aux) = self.get_aux_security_header() {
+            s.field("aux_security_header", &aux);
+        }
+
         // s.field("payload", &self.payload());
         s.finish()
     }
@@ -459,6 +572,50 @@ where
         self.header_mut_()[SEQUENCE] = seq;
     }
 
+    /// Appends the given auxiliary security header to the MAC header and sets the 'Security
+    /// enabled' field
+    ///
+    /// This must be called *before* the payload is filled in as it shifts the start of the
+    /// payload. The 'Frame version' field is set to `1` (802.15.4-2006)
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the auxiliary security header has already been set or if it doesn't
+    /// fit in the buffer
+    pub fn set_aux_security_header(&mut self, aux: AuxSecurityHeader) {
+        assert!(!self.get_security_enabled());
+
+        let start = usize::from(self.payload);
+        let size = aux.size();
+        let slice = &mut self.as_mut_slice()[start..start + usize::from(size)];
+
+        let mut sc = 0;
+        set!(sc, security_level, u8::from(aux.security_level));
+        set!(sc, key_id_mode, aux.key_identifier.mode());
+        slice[0] = sc;
+        LE::write_u32(&mut slice[1..5], aux.frame_counter);
+        match aux.key_identifier {
+            KeyIdentifier::Implicit => {}
+            KeyIdentifier::Index(index) => slice[5] = index,
+            KeyIdentifier::Source4 { source, index } => {
+                slice[5..9].copy_from_slice(&source);
+                slice[9] = index;
+            }
+            KeyIdentifier::Source8 { source, index } => {
+                slice[5..13].copy_from_slice(&source);
+                slice[13] = index;
+            }
+        }
+
+        set!(self.header_mut_()[CONTROLL], security_enabled, 1);
+        set!(
+            self.header_mut_()[CONTROLH],
+            frame_version,
+            FRAME_VERSION_2006
+        );
+        self.payload += size;
+    }
+
     /// Returns a mutable view into the payload
     pub fn payload_mut(&mut self) -> &mut [u8] {
         let start = usize::from(self.payload);
@@ -507,6 +664,89 @@ where
         self.buffer.truncate(self.payload + plen as u8);
     }
 
+    /// Fills the payload with the given data and secures it using the CCM* `cipher`
+    ///
+    /// The security level and frame counter are taken from the auxiliary security header, which
+    /// must have been set with `set_aux_security_header`. `src` must be the extended address of
+    /// the originator of this frame; it's part of the CCM* nonce.
+    ///
+    /// The Message Integrity Code (MIC) is appended to the payload and the frame is truncated to
+    /// fit it
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the auxiliary security header has not been set or if the payload plus
+    /// the MIC doesn't fit in the buffer
+    pub fn set_secured_payload<C>(&mut self, payload: &[u8], cipher: &mut C, src: ExtendedAddr)
+    where
+        C: CcmStar,
+    {
+        let aux = self
+            .get_aux_security_header()
+            .expect("security not enabled");
+        let mic_len = aux.security_level.mic_len();
+        let plen = payload.len();
+
+        assert!(self.payload().len() >= plen + usize::from(mic_len));
+
+        self.payload_mut()[..plen].copy_from_slice(payload);
+
+        let nonce = aux.nonce(src);
+        let start = usize::from(self.payload);
+        let end = start + plen;
+        let slice = &mut self.as_mut_slice()[..end + usize::from(mic_len)];
+
+        // 7.6.3.4 CCM* transformation data representation
+        //
+        // "If the security level has encryption, `a` is the header and `m` the payload; otherwise
+        // `a` is the header plus the payload and `m` is empty"
+        let (a, m_mic) = slice.split_at_mut(if aux.security_level.is_encrypted() {
+            start
+        } else {
+            end
+        });
+        let (m, mic) = m_mic.split_at_mut(m_mic.len() - usize::from(mic_len));
+
+        cipher.seal(&nonce, a, m, mic);
+
+        self.buffer.truncate(end as u8 + mic_len);
+    }
+
+    /// Verifies and decrypts, in place, the payload of this secured frame using the CCM* `cipher`
+    ///
+    /// `src` must be the extended address of the originator of this frame. On success, the MIC is
+    /// removed from the payload and `payload` returns the plaintext.
+    ///
+    /// Returns `Err` if security is not enabled, the payload is too short to contain the MIC or the
+    /// MIC doesn't match. If the MIC doesn't match the contents of the payload are unspecified.
+    ///
+    /// NOTE this does *not* check the frame counter for replays; that's up to the caller
+    pub fn unsecure<C>(&mut self, cipher: &mut C, src: ExtendedAddr) -> Result<(), ()>
+    where
+        C: CcmStar,
+    {
+        let aux = self.get_aux_security_header().ok_or(())?;
+        let mic_len = usize::from(aux.security_level.mic_len());
+        let plen = self.payload().len().checked_sub(mic_len).ok_or(())?;
+
+        let nonce = aux.nonce(src);
+        let start = usize::from(self.payload);
+        let end = start + plen;
+        let slice = self.as_mut_slice();
+
+        let (a, m_mic) = slice.split_at_mut(if aux.security_level.is_encrypted() {
+            start
+        } else {
+            end
+        });
+        let (m, mic) = m_mic.split_at_mut(m_mic.len() - mic_len);
+
+        cipher.open(&nonce, a, m, mic)?;
+
+        self.buffer.truncate(end as u8);
+        Ok(())
+    }
+
     /// Fills the buffer with an 'Echo Reply' ICMPv6 message
     pub fn echo_reply<F>(&mut self, src: ipv6::Addr, dest: ipv6::Addr, f: F)
     where
@@ -694,6 +934,155 @@ full_range!(
     }
 );
 
+/// Auxiliary security header (see Section 7.6.2 of 802.15.4-2006)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuxSecurityHeader {
+    /// Security level
+    pub security_level: SecurityLevel,
+    /// Frame counter
+    pub frame_counter: u32,
+    /// Key identifier
+    pub key_identifier: KeyIdentifier,
+}
+
+impl AuxSecurityHeader {
+    /// Returns the CCM* nonce used to secure a frame sent by `src` with this header
+    ///
+    /// See Section 7.6.3.2 of 802.15.4-2006
+    pub fn nonce(&self, src: ExtendedAddr) -> [u8; 13] {
+        let mut nonce = [0; 13];
+        NE::write_u64(&mut nonce[..8], src.0);
+        NE::write_u32(&mut nonce[8..12], self.frame_counter);
+        nonce[12] = self.security_level.into();
+        nonce
+    }
+
+    fn size(&self) -> u8 {
+        AUX_HEADER_SIZE + KeyIdentifier::size(self.key_identifier.mode())
+    }
+}
+
+/// Security level (see Table 95 of 802.15.4-2006)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SecurityLevel {
+    /// No security
+    None = 0b000,
+    /// Authentication only, 32-bit MIC
+    Mic32 = 0b001,
+    /// Authentication only, 64-bit MIC
+    Mic64 = 0b010,
+    /// Authentication only, 128-bit MIC
+    Mic128 = 0b011,
+    /// Encryption only
+    Enc = 0b100,
+    /// Encryption and authentication, 32-bit MIC
+    EncMic32 = 0b101,
+    /// Encryption and authentication, 64-bit MIC
+    EncMic64 = 0b110,
+    /// Encryption and authentication, 128-bit MIC
+    EncMic128 = 0b111,
+}
+
+impl SecurityLevel {
+    /// Returns the size of the Message Integrity Code, in bytes
+    pub fn mic_len(&self) -> u8 {
+        match *self as u8 & 0b11 {
+            0b00 => 0,
+            0b01 => 4,
+            0b10 => 8,
+            _ => 16,
+        }
+    }
+
+    /// Whether the payload is encrypted at this security level
+    pub fn is_encrypted(&self) -> bool {
+        *self as u8 & 0b100 != 0
+    }
+}
+
+impl From<u8> for SecurityLevel {
+    fn from(bits: u8) -> Self {
+        match bits & 0b111 {
+            0b000 => SecurityLevel::None,
+            0b001 => SecurityLevel::Mic32,
+            0b010 => SecurityLevel::Mic64,
+            0b011 => SecurityLevel::Mic128,
+            0b100 => SecurityLevel::Enc,
+            0b101 => SecurityLevel::EncMic32,
+            0b110 => SecurityLevel::EncMic64,
+            _ => SecurityLevel::EncMic128,
+        }
+    }
+}
+
+impl From<SecurityLevel> for u8 {
+    fn from(sl: SecurityLevel) -> u8 {
+        sl as u8
+    }
+}
+
+/// Key identifier (see Section 7.6.2.4 of 802.15.4-2006)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyIdentifier {
+    /// Key is determined implicitly from the originator and recipient(s) of the frame
+    Implicit,
+    /// Key is determined from the key index and the `macDefaultKeySource`
+    Index(u8),
+    /// Key is determined from a 4-byte key source and a key index
+    Source4 {
+        /// Key source
+        source: [u8; 4],
+        /// Key index
+        index: u8,
+    },
+    /// Key is determined from an 8-byte key source and a key index
+    Source8 {
+        /// Key source
+        source: [u8; 8],
+        /// Key index
+        index: u8,
+    },
+}
+
+impl KeyIdentifier {
+    fn mode(&self) -> u8 {
+        match *self {
+            KeyIdentifier::Implicit => 0,
+            KeyIdentifier::Index(..) => 1,
+            KeyIdentifier::Source4 { .. } => 2,
+            KeyIdentifier::Source8 { .. } => 3,
+        }
+    }
+
+    // size of the 'Key Identifier' field given the 'Key Identifier Mode'
+    fn size(mode: u8) -> u8 {
+        match mode & 0b11 {
+            0 => 0,
+            1 => 1,
+            2 => 5,
+            _ => 9,
+        }
+    }
+}
+
+/// CCM* block cipher mode of operation, as used by 802.15.4 (see Annex B of 802.15.4-2006)
+///
+/// Implement this on top of an AES-128 implementation (software or hardware); key selection is up
+/// to the implementer
+pub trait CcmStar {
+    /// Authenticates `a` and `m`, encrypts `m` in place and writes the Message Integrity Code into
+    /// `mic`
+    ///
+    /// `mic.len()` is the MIC length (`M`); it can be `0`. `m` can be empty
+    fn seal(&mut self, nonce: &[u8; 13], a: &[u8], m: &mut [u8], mic: &mut [u8]);
+
+    /// Decrypts `m` in place and verifies the Message Integrity Code `mic` against `a` and the
+    /// decrypted `m`
+    ///
+    /// Returns `Err` if the MIC doesn't match. In that case the contents of `m` are unspecified
+    fn open(&mut self, nonce: &[u8; 13], a: &[u8], m: &mut [u8], mic: &[u8]) -> Result<(), ()>;
+}
+
 /// Address mode
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum AddrMode {
@@ -868,7 +1257,10 @@ impl From<ExtendedAddr> for Addr {
 mod tests {
     use rand::{self, RngCore};
 
-    use super::{Addr, ExtendedAddr, Frame, PanId, ShortAddr, SrcDest, Type};
+    use super::{
+        Addr, AuxSecurityHeader, CcmStar, ExtendedAddr, Frame, KeyIdentifier, PanId, SecurityLevel,
+        ShortAddr, SrcDest, Type,
+    };
 
     #[test]
     fn data() {
@@ -916,4 +1308,86 @@ mod tests {
             ExtendedAddr(0x09_0A_0B_0C_0D_0E_0F_10)
         );
     }
+
+    // NOT a real cipher: XORs `m` with the nonce and uses the sum of all the bytes as the MIC
+    struct Mock;
+
+    impl Mock {
+        fn mic(nonce: &[u8; 13], a: &[u8], m: &[u8], mic: &mut [u8]) {
+            let sum = nonce
+                .iter()
+                .chain(a)
+                .chain(m)
+                .fold(0u8, |sum, b| sum.wrapping_add(*b));
+            for byte in mic {
+                *byte = sum;
+            }
+        }
+    }
+
+    impl CcmStar for Mock {
+        fn seal(&mut self, nonce: &[u8; 13], a: &[u8], m: &mut [u8], mic: &mut [u8]) {
+            Mock::mic(nonce, a, m, mic);
+            for (i, byte) in m.iter_mut().enumerate() {
+                *byte ^= nonce[i % 13];
+            }
+        }
+
+        fn open(&mut self, nonce: &[u8; 13], a: &[u8], m: &mut [u8], mic: &[u8]) -> Result<(), ()> {
+            for (i, byte) in m.iter_mut().enumerate() {
+                *byte ^= nonce[i % 13];
+            }
+            let mut expected = [0; 16];
+            Mock::mic(nonce, a, m, &mut expected[..mic.len()]);
+            if expected[..mic.len()] == *mic {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+    }
+
+    #[test]
+    fn secured() {
+        const PAYLOAD: &[u8] = b"Hello, world!";
+        const SRC: ExtendedAddr = ExtendedAddr(0x01_02_03_04_05_06_07_08);
+
+        let aux = AuxSecurityHeader {
+            security_level: SecurityLevel::EncMic64,
+            frame_counter: 0xdead_beef,
+            key_identifier: KeyIdentifier::Source4 {
+                source: [1, 2, 3, 4],
+                index: 5,
+            },
+        };
+
+        let mut buf = [0; 128];
+        let mut frame = Frame::data(
+            &mut buf[..],
+            SrcDest::IntraPan {
+                pan_id: PanId(0xbeef),
+                dest_addr: ShortAddr(0x09_0a).into(),
+                src_addr: SRC.into(),
+            },
+        );
+        frame.set_aux_security_header(aux);
+        frame.set_secured_payload(PAYLOAD, &mut Mock, SRC);
+        assert_eq!(frame.payload().len(), PAYLOAD.len() + 8);
+        assert!(&frame.payload()[..PAYLOAD.len()] != PAYLOAD);
+
+        let len = frame.as_bytes().len();
+        let mut frame = Frame::parse(&mut buf[..len]).unwrap();
+        assert!(frame.get_security_enabled());
+        assert_eq!(frame.get_aux_security_header(), Some(aux));
+
+        // tampered frame
+        let mut copy = [0; 128];
+        copy[..len].copy_from_slice(frame.as_bytes());
+        copy[len - 9] ^= 1;
+        let mut tampered = Frame::parse(&mut copy[..len]).unwrap();
+        assert!(tampered.unsecure(&mut Mock, SRC).is_err());
+
+        frame.unsecure(&mut Mock, SRC).unwrap();
+        assert_eq!(frame.payload(), PAYLOAD);
+    }
 }
@@ -12,15 +12,16 @@ use core::{fmt, u16};
 
 use as_slice::{AsMutSlice, AsSlice};
 use byteorder::{ByteOrder, NetworkEndian as NE};
-use cast::{u16, u32, usize};
+use cast::{u16, usize};
 use hash32_derive::Hash32;
 use owning_slice::{IntoSliceFrom, Truncate};
 
 use crate::{
+    checksum,
     fmt::Hex,
     icmp,
     traits::{UncheckedIndex, UxxExt},
-    udp, Invalid, Valid,
+    udp, udplite, vrrp, Invalid, Valid,
 };
 
 /* Packet structure */
@@ -84,6 +85,12 @@ const DESTINATION: Range<usize> = 16..20;
 /// Minimum size of the IPv4 header
 pub const MIN_HEADER_SIZE: u8 = DESTINATION.end as u8;
 
+/// Returns the maximum payload size of an IPv4 packet (without options) given the MTU of the
+/// link
+pub const fn max_payload(mtu: u16) -> u16 {
+    mtu - MIN_HEADER_SIZE as u16
+}
+
 /// IPv4 packet
 pub struct Packet<BUFFER, CHECKSUM>
 where
@@ -116,6 +123,9 @@ where
         if header_len < u16(MIN_HEADER_SIZE) {
             // IHL < 5
             Err(packet.buffer)
+        } else if usize(header_len) > packet.as_slice().len() {
+            // input doesn't contain the options
+            Err(packet.buffer)
         } else if total_len < header_len {
             Err(packet.buffer)
         } else if packet.get_version() != 4 {
@@ -388,7 +398,27 @@ where
         self.truncate(len);
     }
 
+    /// Fills the payload with a VRRP advertisement that carries `count` IP addresses
+    ///
+    /// NOTE the TTL must be set to `vrrp::TTL`
+    pub fn vrrp<F>(&mut self, count: u8, f: F)
+    where
+        F: FnOnce(&mut vrrp::Packet<&mut [u8]>),
+    {
+        self.set_protocol(Protocol::Vrrp);
+        let len = {
+            let mut vrrp = vrrp::Packet::new(self.payload_mut(), count);
+            f(&mut vrrp);
+            vrrp.update_checksum();
+            vrrp.len()
+        };
+        self.truncate(len);
+    }
+
     /// Fills the payload with an UDP packet
+    ///
+    /// NOTE the UDP checksum is left zeroed ("no checksum"); use `udp::Packet::update_ipv4_checksum`
+    /// in the closure to compute it
     pub fn udp<F>(&mut self, f: F)
     where
         F: FnOnce(&mut udp::Packet<&mut [u8]>),
@@ -402,6 +432,27 @@ where
         self.truncate(len);
     }
 
+    /// Fills the payload with an UDP-Lite packet
+    ///
+    /// This method computes the UDP-Lite checksum so the Source and Destination fields of this
+    /// packet must be set *before* calling this method
+    pub fn udplite<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut udplite::Packet<&mut [u8]>),
+    {
+        let src = self.get_source();
+        let dest = self.get_destination();
+
+        self.set_protocol(Protocol::UdpLite);
+        let len = {
+            let mut udp = udplite::Packet::new(self.payload_mut());
+            f(&mut udp);
+            udp.update_ipv4_checksum(src, dest);
+            udp.len()
+        };
+        self.truncate(len);
+    }
+
     /// Truncates the *payload* to the specified length
     pub fn truncate(&mut self, len: u16) {
         if self.payload_len() > len {
@@ -641,6 +692,41 @@ impl Addr {
 
     /// Unspecified address
     pub const UNSPECIFIED: Self = Addr([0; 4]);
+
+    /// Limited broadcast address
+    pub const BROADCAST: Self = Addr([255; 4]);
+
+    /// All systems on this subnet multicast address (see RFC 1112)
+    pub const ALL_SYSTEMS: Self = Addr([224, 0, 0, 1]);
+
+    /// All routers on this subnet multicast address
+    pub const ALL_ROUTERS: Self = Addr([224, 0, 0, 2]);
+
+    /// IGMPv3 multicast address; membership reports are sent here (see RFC 3376)
+    pub const IGMP: Self = Addr([224, 0, 0, 22]);
+
+    /// mDNS multicast address (see RFC 6762)
+    pub const MDNS: Self = Addr([224, 0, 0, 251]);
+
+    /// Is this the limited broadcast address?
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    /// Is this a loopback (`127.0.0.0/8`) address?
+    pub fn is_loopback(&self) -> bool {
+        self.0[0] == 127
+    }
+
+    /// Is this a multicast (`224.0.0.0/4`) address?
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] >> 4 == 0b1110
+    }
+
+    /// Is this the unspecified address?
+    pub fn is_unspecified(&self) -> bool {
+        *self == Self::UNSPECIFIED
+    }
 }
 
 impl fmt::Debug for Addr {
@@ -1129,47 +1215,46 @@ impl Protocol {
 }
 
 /// Computes the IPv4 checksum of the header
+///
+/// NOTE this is also used for ICMP messages, which can have an odd length
 pub(crate) fn compute_checksum(header: &[u8], cksum_pos: usize) -> u16 {
-    let mut sum = 0u32;
-    let skip = cksum_pos / 2;
-
-    // Header length is always even
-    debug_assert_eq!(header.len() % 2, 0);
-    for (i, chunk) in header.chunks_exact(2).enumerate() {
-        if i == skip {
-            // skip checksum field
-            continue;
-        }
-        sum = sum.wrapping_add(u32(NE::read_u16(chunk)));
-    }
-
-    loop {
-        let carry = sum.high();
-        if carry == 0 {
-            break;
-        }
-        sum = u32(sum.low()) + u32(carry);
-    }
-
-    !sum.low()
+    let mut state = checksum::State::new();
+    // skip checksum field
+    state.push(&header[..cksum_pos]);
+    state.push(&header[cksum_pos + 2..]);
+    state.finish()
 }
 
 /// Verifies the IPv4 checksum of the header
+///
+/// NOTE this is also used for ICMP messages, which can have an odd length
 pub(crate) fn verify_checksum(header: &[u8]) -> bool {
-    debug_assert!(header.len() % 2 == 0);
-
-    let mut sum = 0u32;
-    for chunk in header.chunks_exact(2) {
-        sum = sum.wrapping_add(u32(NE::read_u16(chunk)));
-    }
-
-    sum.low() + sum.high() == 0xffff
+    let mut state = checksum::State::new();
+    state.push(header);
+    state.finish() == 0
 }
 
 #[cfg(test)]
 mod tests {
     use crate::ipv4;
 
+    #[test]
+    fn addr() {
+        assert!(ipv4::Addr::BROADCAST.is_broadcast());
+        assert!(!ipv4::Addr::BROADCAST.is_multicast());
+
+        assert!(ipv4::Addr::LOOPBACK.is_loopback());
+        assert!(ipv4::Addr([127, 1, 2, 3]).is_loopback());
+
+        assert!(ipv4::Addr::ALL_SYSTEMS.is_multicast());
+        assert!(ipv4::Addr::MDNS.is_multicast());
+        assert!(ipv4::Addr([239, 255, 255, 255]).is_multicast());
+        assert!(!ipv4::Addr([240, 0, 0, 0]).is_multicast());
+
+        assert!(ipv4::Addr::UNSPECIFIED.is_unspecified());
+        assert!(!ipv4::Addr([192, 168, 1, 1]).is_unspecified());
+    }
+
     #[test]
     fn checksum() {
         let header = [
@@ -1204,4 +1289,15 @@ mod tests {
 
         assert!(super::verify_checksum(&header))
     }
+
+    #[test]
+    fn parse_truncated_options() {
+        // IHL = 6 but the input ends right after the fixed part of the header
+        let header = [
+            0x46, 0x00, 0x00, 0x18, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 0xc0, 0xa8,
+            0x00, 0x01, 0xc0, 0xa8, 0x00, 0xc7,
+        ];
+
+        assert!(ipv4::Packet::parse(&header[..]).is_err());
+    }
 }
@@ -5,6 +5,14 @@
 //! - [RFC 4291 IP Version 6 Addressing Architecture][rfc]
 //!
 //! [rfc]: https://tools.ietf.org/html/rfc4291
+//!
+//! - [RFC 8200 Internet Protocol, Version 6 (IPv6) Specification][rfc8200]
+//!
+//! [rfc8200]: https://tools.ietf.org/html/rfc8200
+//!
+//! - [RFC 6724 Default Address Selection for Internet Protocol Version 6 (IPv6)][rfc6724]
+//!
+//! [rfc6724]: https://tools.ietf.org/html/rfc6724
 
 use core::{
     fmt,
@@ -19,7 +27,7 @@ use hash32_derive::Hash32;
 use owning_slice::Truncate;
 
 pub use crate::ipv4::Protocol as NextHeader;
-use crate::{fmt::Quoted, icmpv6, mac, traits::UncheckedIndex, udp};
+use crate::{fmt::Quoted, icmpv6, mac, rng::Rng, traits::UncheckedIndex, udp, udplite};
 
 /* Packet structure */
 const V: usize = 0;
@@ -49,6 +57,14 @@ const PAYLOAD: RangeFrom<usize> = 40..;
 /// Fixed header size, in bytes
 pub const HEADER_SIZE: u8 = DESTINATION.end as u8;
 
+/// Minimum MTU that every link must support (see Section 5 of RFC 8200)
+pub const MIN_MTU: u16 = 1280;
+
+/// Returns the maximum payload size of an IPv6 packet given the MTU of the link
+pub const fn max_payload(mtu: u16) -> u16 {
+    mtu - HEADER_SIZE as u16
+}
+
 /// IPv6 packet
 pub struct Packet<BUFFER>
 where
@@ -76,7 +92,8 @@ where
             return Err(());
         }
 
-        if p.get_next_header().is_ipv6_extension_header() {
+        let nh = p.get_next_header();
+        if nh.is_ipv6_extension_header() && nh != NextHeader::Ipv6Frag {
             // currently unsupported
             return Err(());
         }
@@ -132,8 +149,11 @@ where
     }
 
     /// Immutable view into the payload
+    ///
+    /// NOTE if the 'Next Header' field is `Ipv6Frag` the payload starts with a Fragment header;
+    /// use `Fragment::parse` on it
     pub fn payload(&self) -> &[u8] {
-        // NOTE we reject packets that contain extension headers in `parse`
+        // NOTE we reject packets that contain extension headers, other than Fragment, in `parse`
         unsafe { self.as_slice().rf(PAYLOAD) }
     }
 
@@ -332,6 +352,37 @@ where
         self.truncate(len);
     }
 
+    /// Fills the payload with a UDP-Lite packet
+    pub fn udplite(&mut self, f: impl FnOnce(&mut udplite::Packet<&mut [u8]>)) {
+        let src = self.get_source();
+        let dest = self.get_destination();
+
+        self.set_next_header(NextHeader::UdpLite);
+
+        let mut packet = udplite::Packet::new(self.payload_mut());
+
+        f(&mut packet);
+
+        packet.update_ipv6_checksum(src, dest);
+
+        let len = packet.len();
+        self.truncate(len);
+    }
+
+    /// Fills the payload with a Fragment header and the given fragment data
+    ///
+    /// The 'Next Header' field of this packet will be set to `Ipv6Frag`
+    pub fn fragment(&mut self, f: impl FnOnce(&mut Fragment<&mut [u8]>)) {
+        self.header_mut()[NEXT_HEADER] = NextHeader::Ipv6Frag.into();
+
+        let mut fragment = Fragment::new(self.payload_mut());
+
+        f(&mut fragment);
+
+        let len = fragment.as_bytes().len() as u16;
+        self.truncate(len);
+    }
+
     /// Truncates the *payload* to the specified length
     pub fn truncate(&mut self, len: u16) {
         if self.get_length() > len {
@@ -360,6 +411,229 @@ where
     }
 }
 
+/* Fragment header structure */
+const FRAG_NEXT_HEADER: usize = 0;
+const FRAG_RESERVED: usize = 1;
+
+const FRAG_OFFSET_M: Range<usize> = 2..4;
+mod m {
+    pub const MASK: u16 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 0;
+    pub const SIZE: usize = 1;
+}
+// NOTE 2 reserved bits sit between `m` and `fragment_offset`
+mod fragment_offset {
+    pub const MASK: u16 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::m::OFFSET + super::m::SIZE + 2;
+    pub const SIZE: usize = 13;
+}
+
+const FRAG_IDENTIFICATION: Range<usize> = 4..8;
+const FRAG_PAYLOAD: RangeFrom<usize> = 8..;
+
+/// Size of the Fragment header, in bytes
+pub const FRAGMENT_HEADER_SIZE: u8 = FRAG_IDENTIFICATION.end as u8;
+
+/// IPv6 Fragment extension header (see Section 4.5 of RFC 8200) plus the fragment data
+pub struct Fragment<BUFFER>
+where
+    BUFFER: AsSlice<Element = u8>,
+{
+    buffer: BUFFER,
+}
+
+impl<B> Fragment<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Parses bytes into a Fragment header
+    ///
+    /// `bytes` is expected to be the payload of an IPv6 packet whose 'Next Header' field is
+    /// `Ipv6Frag`
+    pub fn parse(bytes: B) -> Result<Self, B> {
+        if bytes.as_slice().len() < usize(FRAGMENT_HEADER_SIZE) {
+            Err(bytes)
+        } else {
+            Ok(Fragment { buffer: bytes })
+        }
+    }
+
+    /* Accessors */
+    /// Reads the 'Next Header' field
+    pub fn get_next_header(&self) -> NextHeader {
+        self.header()[FRAG_NEXT_HEADER].into()
+    }
+
+    /// Reads the 'Fragment Offset' field
+    ///
+    /// NOTE the offset is expressed in units of 8 octets
+    pub fn get_fragment_offset(&self) -> u16 {
+        get!(NE::read_u16(&self.header()[FRAG_OFFSET_M]), fragment_offset)
+    }
+
+    /// Reads the 'M' (More fragments) flag
+    pub fn get_mf(&self) -> bool {
+        get!(NE::read_u16(&self.header()[FRAG_OFFSET_M]), m) == 1
+    }
+
+    /// Reads the 'Identification' field
+    pub fn get_identification(&self) -> u32 {
+        NE::read_u32(&self.header()[FRAG_IDENTIFICATION])
+    }
+
+    /// Is this an atomic fragment? (see RFC 6946)
+    ///
+    /// Atomic fragments have a 'Fragment Offset' of `0` and the 'M' flag unset; they contain the
+    /// whole original packet.
+    pub fn is_atomic(&self) -> bool {
+        self.get_fragment_offset() == 0 && !self.get_mf()
+    }
+
+    /// Immutable view into the fragment data
+    pub fn payload(&self) -> &[u8] {
+        unsafe { self.as_slice().rf(FRAG_PAYLOAD) }
+    }
+
+    /// Returns the byte representation of this header plus the fragment data
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    /// Frees the underlying buffer
+    pub fn free(self) -> B {
+        self.buffer
+    }
+
+    /* Private */
+    fn header(&self) -> &[u8; FRAGMENT_HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= usize(FRAGMENT_HEADER_SIZE));
+
+        unsafe { &*(self.as_slice().as_ptr() as *const _) }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+}
+
+impl<B> Fragment<B>
+where
+    B: AsMutSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Transforms the given buffer into a Fragment header
+    ///
+    /// The header is initialized with 'Fragment Offset' = 0, 'M' = false and 'Identification' = 0.
+    /// The 'Next Header' field is left unpopulated.
+    ///
+    /// NOTE the fragment data will span the rest of the buffer
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the given `buffer` is smaller than `FRAGMENT_HEADER_SIZE`
+    pub fn new(buffer: B) -> Self {
+        assert!(buffer.as_slice().len() >= usize(FRAGMENT_HEADER_SIZE));
+
+        let mut f = Fragment { buffer };
+
+        // f.set_next_header(..);
+        f.header_mut()[FRAG_RESERVED] = 0;
+        NE::write_u16(&mut f.header_mut()[FRAG_OFFSET_M], 0);
+        f.set_identification(0);
+
+        f
+    }
+
+    /* Setters */
+    /// Sets the 'Next Header' field
+    pub fn set_next_header(&mut self, nh: NextHeader) {
+        self.header_mut()[FRAG_NEXT_HEADER] = nh.into();
+    }
+
+    /// Sets the 'Fragment Offset' field
+    ///
+    /// NOTE the offset is expressed in units of 8 octets
+    pub fn set_fragment_offset(&mut self, fo: u16) {
+        let mut halfword = NE::read_u16(&self.header()[FRAG_OFFSET_M]);
+        set!(halfword, fragment_offset, fo);
+        NE::write_u16(&mut self.header_mut()[FRAG_OFFSET_M], halfword);
+    }
+
+    /// Sets the 'M' (More fragments) flag
+    pub fn set_mf(&mut self, mf: bool) {
+        let mut halfword = NE::read_u16(&self.header()[FRAG_OFFSET_M]);
+        set!(halfword, m, if mf { 1 } else { 0 });
+        NE::write_u16(&mut self.header_mut()[FRAG_OFFSET_M], halfword);
+    }
+
+    /// Sets the 'Identification' field
+    pub fn set_identification(&mut self, id: u32) {
+        NE::write_u32(&mut self.header_mut()[FRAG_IDENTIFICATION], id);
+    }
+
+    /// Sets the 'Identification' field to a random value and returns it
+    ///
+    /// Identifications must be hard to predict (see RFC 7739); pass the returned value to
+    /// `set_identification` when building the other fragments of the same packet
+    pub fn set_random_identification<R>(&mut self, rng: &mut R) -> u32
+    where
+        R: Rng,
+    {
+        let id = rng.next_u32();
+        self.set_identification(id);
+        id
+    }
+
+    /// Mutable view into the fragment data
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        unsafe { self.as_mut_slice().rfm(FRAG_PAYLOAD) }
+    }
+
+    /* Private */
+    fn header_mut(&mut self) -> &mut [u8; FRAGMENT_HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= usize(FRAGMENT_HEADER_SIZE));
+
+        unsafe { &mut *(self.as_mut_slice().as_mut_ptr() as *mut _) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.buffer.as_mut_slice()
+    }
+}
+
+impl<B> Fragment<B>
+where
+    B: AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /// Fills the fragment data with `data` and truncates the buffer to fit it
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `data` doesn't fit in the buffer
+    pub fn set_payload(&mut self, data: &[u8]) {
+        let len = data.len();
+        self.payload_mut()[..len].copy_from_slice(data);
+        self.buffer
+            .truncate(u16(len + usize(FRAGMENT_HEADER_SIZE)).unwrap());
+    }
+}
+
+impl<B> fmt::Debug for Fragment<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ipv6::Fragment")
+            .field("next_header", &self.get_next_header())
+            .field("fragment_offset", &self.get_fragment_offset())
+            .field("mf", &self.get_mf())
+            .field("identification", &self.get_identification())
+            // .field("payload", &self.payload())
+            .finish()
+    }
+}
+
 /// IPv6 address
 #[derive(Clone, Copy, Debug, Eq, Hash32, PartialEq)]
 pub struct Addr(pub [u8; 16]);
@@ -379,6 +653,14 @@ impl Addr {
     /// All link-local routers multicast address
     pub const ALL_ROUTERS: Self = Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
 
+    /// All MLDv2-capable routers multicast address; membership reports are sent here (see RFC
+    /// 3810)
+    pub const ALL_MLDV2_ROUTERS: Self =
+        Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x16]);
+
+    /// Link-local mDNS multicast address (see RFC 6762)
+    pub const MDNS: Self = Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xfb]);
+
     // Section 2.5.6
     /// Is this a link local address?
     pub fn is_link_local(&self) -> bool {
@@ -406,6 +688,53 @@ impl Addr {
         *self == Self::UNSPECIFIED
     }
 
+    // Section 2.7 of RFC 4291 and Section 3.1 of RFC 6724
+    /// Returns the scope of this address
+    ///
+    /// The scope uses the encoding of the multicast scope field: `1` (interface-local), `2`
+    /// (link-local), `5` (site-local), `0xe` (global), etc. so larger values mean wider scopes.
+    /// The loopback and link-local unicast addresses have link-local scope; all other unicast
+    /// addresses have global scope
+    pub fn scope(&self) -> u8 {
+        if self.is_multicast() {
+            self.0[1] & 0x0f
+        } else if self.is_link_local() || self.is_loopback() {
+            0x2
+        } else {
+            0xe
+        }
+    }
+
+    /// Returns the length, in bits, of the longest prefix shared by `self` and `other`
+    pub fn common_prefix_len(&self, other: &Self) -> u8 {
+        let mut len = 0;
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            let diff = a ^ b;
+            // NOTE(cast) `leading_zeros` of a `u8` is at most 8
+            len += diff.leading_zeros() as u8;
+
+            if diff != 0 {
+                break;
+            }
+        }
+        len
+    }
+
+    // Section 7 of RFC 2464
+    /// Maps this multicast address into the Ethernet multicast MAC address that frames sent to
+    /// it must be addressed to
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `self` is not a multicast address
+    pub fn into_multicast_mac(self) -> mac::Addr {
+        assert!(self.is_multicast());
+
+        let mut bytes = [0x33; 6];
+        bytes[2..].copy_from_slice(&self.0[12..]);
+        mac::Addr(bytes)
+    }
+
     /// Turns this unicast or anycast address into a solicited node multicast address
     ///
     /// # Panics
@@ -419,6 +748,64 @@ impl Addr {
     }
 }
 
+/// Picks, among the `candidates` addresses, the source address to use to reach `dest`
+///
+/// This is a simplified version of the source address selection algorithm in Section 5 of RFC
+/// 6724; the rules applied, in order, are:
+///
+/// - Prefer `dest` itself, if it's one of the candidates
+/// - Prefer the candidate whose scope is the smallest one that's not smaller than the scope of
+///   `dest`; if there's no such candidate prefer the one with the widest scope
+/// - Prefer the candidate that shares the longest prefix with `dest`, up to the 64-bit interface
+///   identifier
+///
+/// Ties are broken in favor of the candidate that comes first. The unspecified address and
+/// multicast addresses are never selected. Returns `None` if there's no valid candidate.
+pub fn select_source(candidates: &[Addr], dest: Addr) -> Option<Addr> {
+    let dest_scope = dest.scope();
+
+    let mut best: Option<Addr> = None;
+    for candidate in candidates {
+        if candidate.is_unspecified() || candidate.is_multicast() {
+            continue;
+        }
+
+        best = Some(match best {
+            None => *candidate,
+            Some(best) => {
+                if prefer(*candidate, best, dest, dest_scope) {
+                    *candidate
+                } else {
+                    best
+                }
+            }
+        });
+    }
+
+    best
+}
+
+// is `a` a strictly better source address than `b`?
+fn prefer(a: Addr, b: Addr, dest: Addr, dest_scope: u8) -> bool {
+    // Rule 1: prefer same address
+    if a == dest || b == dest {
+        return a == dest && b != dest;
+    }
+
+    // Rule 2: prefer appropriate scope
+    let (a_scope, b_scope) = (a.scope(), b.scope());
+    if a_scope < b_scope {
+        return a_scope >= dest_scope;
+    } else if b_scope < a_scope {
+        return b_scope < dest_scope;
+    }
+
+    // Rule 8: use longest matching prefix
+    let a_len = a.common_prefix_len(&dest).min(64);
+    let b_len = b.common_prefix_len(&dest).min(64);
+    a_len > b_len
+}
+
 impl fmt::Display for Addr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut is_first = true;
@@ -439,7 +826,7 @@ impl fmt::Display for Addr {
 
 #[cfg(test)]
 mod tests {
-    use crate::ipv6;
+    use crate::{ipv6, mac};
 
     use super::HEADER_SIZE;
 
@@ -453,6 +840,103 @@ mod tests {
             unicast.into_solicited_node(),
             ipv6::Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0xff, 0xb9, 0xf3, 0x93])
         );
+
+        let mac = unicast.into_solicited_node().into_multicast_mac();
+        assert!(mac.is_ipv6_multicast());
+        assert_eq!(mac, mac::Addr([0x33, 0x33, 0xff, 0xb9, 0xf3, 0x93]));
+    }
+
+    #[test]
+    fn select_source() {
+        let link_local = ipv6::Addr([
+            0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0xec, 0x0b, 0xfb, 0x0f, 0x76, 0xb9, 0xf3, 0x93,
+        ]);
+        let global = ipv6::Addr([
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 1, 0xec, 0x0b, 0xfb, 0x0f, 0x76, 0xb9, 0xf3, 0x93,
+        ]);
+        let other_global = ipv6::Addr([
+            0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 2, 0xec, 0x0b, 0xfb, 0x0f, 0x76, 0xb9, 0xf3, 0x93,
+        ]);
+        let candidates = [ipv6::Addr::UNSPECIFIED, link_local, global, other_global];
+
+        assert_eq!(link_local.scope(), 0x2);
+        assert_eq!(global.scope(), 0xe);
+        assert_eq!(ipv6::Addr::ALL_NODES.scope(), 0x2);
+        assert_eq!(global.common_prefix_len(&other_global), 62);
+        assert_eq!(global.common_prefix_len(&global), 128);
+
+        // link-local destinations
+        assert_eq!(
+            ipv6::select_source(&candidates, ipv6::Addr::ALL_NODES),
+            Some(link_local)
+        );
+        let mut neighbor = link_local;
+        neighbor.0[15] = 1;
+        assert_eq!(ipv6::select_source(&candidates, neighbor), Some(link_local));
+
+        // global destinations
+        let mut dest = other_global;
+        dest.0[15] = 1;
+        assert_eq!(ipv6::select_source(&candidates, dest), Some(other_global));
+        assert_eq!(ipv6::select_source(&candidates, global), Some(global));
+        let mut off_link = global;
+        off_link.0[0] = 0x2a;
+        assert_eq!(ipv6::select_source(&candidates, off_link), Some(global));
+
+        // a link-local source is better than nothing
+        assert_eq!(
+            ipv6::select_source(&[link_local], off_link),
+            Some(link_local)
+        );
+        assert_eq!(
+            ipv6::select_source(&[ipv6::Addr::UNSPECIFIED], off_link),
+            None
+        );
+    }
+
+    #[test]
+    fn fragment() {
+        const DATA: &[u8] = &[0xaa; 16];
+
+        let mut chunk = [0; 128];
+
+        let mut ip = ipv6::Packet::new(&mut chunk[..]);
+        ip.set_destination(ipv6::Addr::ALL_NODES);
+        ip.set_source(ipv6::Addr::UNSPECIFIED);
+        ip.fragment(|frag| {
+            frag.set_next_header(ipv6::NextHeader::Udp);
+            frag.set_fragment_offset(185);
+            frag.set_mf(true);
+            frag.set_identification(0xdead_beef);
+            frag.set_payload(DATA);
+        });
+
+        let ip = ipv6::Packet::parse(ip.as_bytes()).unwrap();
+        assert_eq!(ip.get_next_header(), ipv6::NextHeader::Ipv6Frag);
+        assert_eq!(
+            usize::from(ip.get_length()),
+            usize::from(ipv6::FRAGMENT_HEADER_SIZE) + DATA.len()
+        );
+
+        let frag = ipv6::Fragment::parse(ip.payload()).unwrap();
+        assert_eq!(frag.get_next_header(), ipv6::NextHeader::Udp);
+        assert_eq!(frag.get_fragment_offset(), 185);
+        assert_eq!(frag.get_mf(), true);
+        assert_eq!(frag.get_identification(), 0xdead_beef);
+        assert!(!frag.is_atomic());
+        assert_eq!(frag.payload(), DATA);
+
+        let mut rng = crate::rng::tests::XorShift(1);
+        let mut ip = ipv6::Packet::new(&mut chunk[..]);
+        let mut id = 0;
+        ip.fragment(|frag| {
+            id = frag.set_random_identification(&mut rng);
+            frag.set_payload(DATA);
+        });
+        assert_eq!(id, 270_369);
+
+        let frag = ipv6::Fragment::parse(ip.payload()).unwrap();
+        assert_eq!(frag.get_identification(), id);
     }
 
     #[test]
@@ -103,6 +103,7 @@ extern crate pretty_assertions;
 #[macro_use]
 mod macros;
 
+mod cbor;
 mod fmt;
 mod sealed;
 mod traits;
@@ -113,20 +114,37 @@ pub mod ieee802154;
 pub mod mac;
 
 pub mod arp;
+pub mod stp;
 
 // Network layer
 pub mod ipv4;
 pub mod ipv6;
+pub mod siit;
 pub mod sixlowpan;
 
 pub mod icmp;
 pub mod icmpv6;
 
+pub mod vrrp;
+
 // Transport layer
+pub mod tcp;
 pub mod udp;
+pub mod udplite;
 
 // Application layer
 pub mod coap;
+pub mod dhcpv6;
+pub mod dns;
+pub mod multipart;
+pub mod senml;
+
+// Utilities
+pub mod checksum;
+pub mod crc;
+pub mod ota;
+pub mod rng;
+pub mod stats;
 
 /// [Type State] Unknown
 pub enum Unknown {}
@@ -1,6 +1,6 @@
 //! MAC: Medium Access Control
 
-use core::fmt;
+use core::{fmt, hash::Hasher};
 
 use hash32_derive::Hash32;
 
@@ -14,6 +14,32 @@ impl Addr {
     /// Broadcast address
     pub const BROADCAST: Self = Addr([0xff; 6]);
 
+    /// Derives a locally administered unicast address from a device unique ID (e.g. the 96-bit
+    /// UID of STM32 microcontrollers)
+    ///
+    /// The 48 least significant bits of the `hasher` output become the address; the Individual /
+    /// Group bit is then cleared and the Universal / Local bit is set. The same `uid` and the same
+    /// `hasher` always produce the same address.
+    pub fn from_unique_id<H>(uid: &[u8], mut hasher: H) -> Self
+    where
+        H: Hasher,
+    {
+        hasher.write(uid);
+        let hash = hasher.finish().to_be_bytes();
+
+        let mut bytes = [0; 6];
+        bytes.copy_from_slice(&hash[2..]);
+        // unicast, locally administered
+        bytes[0] = bytes[0] & !1 | 1 << 1;
+
+        Addr(bytes)
+    }
+
+    /// Is this a locally administered address?
+    pub fn is_local(&self) -> bool {
+        self.0[0] & (1 << 1) != 0
+    }
+
     /// Is this a unicast address?
     pub fn is_unicast(&self) -> bool {
         !self.is_broadcast() && !self.is_multicast()
@@ -118,8 +144,28 @@ impl fmt::Display for Addr {
 
 #[cfg(test)]
 mod tests {
+    use core::hash::Hasher;
+
     use super::Addr;
 
+    // 64-bit FNV-1a
+    struct Fnv(u64);
+
+    impl Hasher for Fnv {
+        fn write(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 ^= u64::from(*byte);
+                self.0 = self.0.wrapping_mul(0x100_0000_01b3);
+            }
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
     #[test]
     fn eui_64() {
         assert_eq!(
@@ -127,4 +173,23 @@ mod tests {
             [0x36, 0x56, 0x78, 0xFF, 0xFE, 0x9A, 0xBC, 0xDE]
         );
     }
+
+    #[test]
+    fn from_unique_id() {
+        let uid = [
+            0x32, 0x00, 0x44, 0x00, 0x0b, 0x51, 0x36, 0x33, 0x31, 0x38, 0x30, 0x33,
+        ];
+
+        let addr = Addr::from_unique_id(&uid, Fnv(FNV_OFFSET_BASIS));
+        assert!(addr.is_unicast());
+        assert!(addr.is_local());
+
+        // stable
+        assert_eq!(addr, Addr::from_unique_id(&uid, Fnv(FNV_OFFSET_BASIS)));
+
+        // different devices get different addresses
+        let mut other = uid;
+        other[11] += 1;
+        assert!(addr != Addr::from_unique_id(&other, Fnv(FNV_OFFSET_BASIS)));
+    }
 }
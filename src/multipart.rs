@@ -0,0 +1,455 @@
+//! Streaming `multipart/form-data` parser
+//!
+//! The body of the message can be fed in chunks of any size (e.g. as TCP segments arrive); the
+//! boundary is detected even when it straddles two chunks. Part headers are reported one at a time
+//! and part bodies are handed over as they come in, so a file upload can be written to Flash
+//! without ever holding the whole file in memory.
+//!
+//! # References
+//!
+//! - [RFC 2046: Multipurpose Internet Mail Extensions (MIME) Part Two: Media Types][rfc2046]
+//! - [RFC 7578: Returning Values from Forms: multipart/form-data][rfc7578]
+//!
+//! [rfc2046]: https://tools.ietf.org/html/rfc2046
+//! [rfc7578]: https://tools.ietf.org/html/rfc7578
+//!
+//! # Example
+//!
+//! ```
+//! use jnet::multipart::{self, Event, Parser};
+//!
+//! let content_type = b"multipart/form-data; boundary=XyZ";
+//! let boundary = multipart::parameter(content_type, "boundary").unwrap();
+//!
+//! let mut scratch = [0; 128];
+//! let mut parser = Parser::new(boundary, &mut scratch).unwrap();
+//!
+//! let mut image = [0; 16];
+//! let mut len = 0;
+//! let mut is_firmware = false;
+//! for chunk in [
+//!     &b"--XyZ\r\nContent-Disposition: form-data; name=\"fw\"; filename=\"a.bin\"\r\n"[..],
+//!     &b"\r\n\x01\x02\x03\r"[..],
+//!     &b"\n--X\x04\r\n--XyZ--\r\n"[..],
+//! ]
+//! .iter()
+//! {
+//!     parser
+//!         .feed(chunk, |event| match event {
+//!             Event::Header { name, value } => {
+//!                 if name.eq_ignore_ascii_case(b"content-disposition") {
+//!                     is_firmware = multipart::parameter(value, "name") == Some(&b"fw"[..]);
+//!                 }
+//!             }
+//!             Event::Data(data) => {
+//!                 if is_firmware {
+//!                     image[len..len + data.len()].copy_from_slice(data);
+//!                     len += data.len();
+//!                 }
+//!             }
+//!             Event::PartEnd => {}
+//!         })
+//!         .unwrap();
+//! }
+//!
+//! assert!(parser.is_done());
+//! assert_eq!(&image[..len], b"\x01\x02\x03\r\n--X\x04");
+//! ```
+
+use core::str;
+
+/// Maximum length of a boundary (see Section 5.1.1 of RFC 2046)
+pub const MAX_BOUNDARY_SIZE: usize = 70;
+
+// the boundary delimiter is CRLF + "--" + boundary
+const DELIMITER_PREFIX: &[u8; 4] = b"\r\n--";
+
+/// Parser event
+#[derive(Debug, PartialEq)]
+pub enum Event<'a> {
+    /// A header of the current part
+    Header {
+        /// Header name
+        name: &'a [u8],
+        /// Header value, with surrounding whitespace removed
+        value: &'a [u8],
+    },
+
+    /// A piece of the body of the current part
+    ///
+    /// The body of a part may be split in several `Data` events
+    Data(&'a [u8]),
+
+    /// The end of the current part
+    PartEnd,
+}
+
+/// Streaming `multipart/form-data` parser
+pub struct Parser<'a> {
+    boundary: &'a [u8],
+    // buffer for the current header line
+    scratch: &'a mut [u8],
+    len: usize,
+    // number of bytes of the delimiter matched so far
+    matched: usize,
+    state: State,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    // before the first boundary; the data is discarded
+    Preamble,
+    // right after a boundary delimiter
+    Delimiter,
+    // the first dash of the close delimiter has been seen
+    Dash,
+    // transport padding after a boundary delimiter
+    Padding,
+    // `\r` after the boundary delimiter
+    DelimiterCr,
+    Headers,
+    // `\r` at the end of a header line
+    HeaderCr,
+    Body,
+    // after the close delimiter; the data is discarded
+    Epilogue,
+}
+
+impl<'a> Parser<'a> {
+    /// Creates a new parser
+    ///
+    /// `scratch` is used to buffer header lines so it must be at least as large as the longest
+    /// expected header line (name, colon and value). Returns `Err` if `boundary` is empty, longer
+    /// than `MAX_BOUNDARY_SIZE` or contains a CR or LF character
+    pub fn new(boundary: &'a [u8], scratch: &'a mut [u8]) -> Result<Self, ()> {
+        if boundary.is_empty()
+            || boundary.len() > MAX_BOUNDARY_SIZE
+            || boundary.iter().any(|b| *b == b'\r' || *b == b'\n')
+        {
+            return Err(());
+        }
+
+        Ok(Parser {
+            boundary,
+            scratch,
+            len: 0,
+            // the first boundary delimiter may appear at the very start of the body; in that case
+            // it's not preceded by CRLF
+            matched: 2,
+            state: State::Preamble,
+        })
+    }
+
+    /// Has the close delimiter been seen?
+    pub fn is_done(&self) -> bool {
+        self.state == State::Epilogue
+    }
+
+    /// Feeds the next chunk of the message body to the parser
+    ///
+    /// `f` is called with the events found in `chunk`. Returns `Err` if the body is malformed or
+    /// if a header line doesn't fit in the scratch buffer; the parser must not be used after an
+    /// error
+    pub fn feed<F>(&mut self, chunk: &[u8], mut f: F) -> Result<(), ()>
+    where
+        F: FnMut(Event<'_>),
+    {
+        // start of the body data in `chunk` that has not been reported yet
+        let mut start = 0;
+
+        for (i, byte) in chunk.iter().cloned().enumerate() {
+            match self.state {
+                State::Preamble | State::Body => {
+                    let discard = self.state == State::Preamble;
+
+                    if byte != self.delimiter(self.matched) && self.matched != 0 {
+                        // false alarm: the partially matched delimiter was data
+                        if !discard {
+                            let matched = self.matched;
+                            self.emit_delimiter(matched, &mut f);
+                        }
+                        self.matched = 0;
+                        start = i;
+                    }
+
+                    if byte == self.delimiter(self.matched) {
+                        if self.matched == 0 && !discard && start < i {
+                            f(Event::Data(&chunk[start..i]));
+                        }
+
+                        self.matched += 1;
+                        start = i + 1;
+
+                        if self.matched == self.delimiter_len() {
+                            if !discard {
+                                f(Event::PartEnd);
+                            }
+
+                            self.matched = 0;
+                            self.state = State::Delimiter;
+                        }
+                    }
+                }
+
+                State::Delimiter => {
+                    self.state = match byte {
+                        b'-' => State::Dash,
+                        b'\r' => State::DelimiterCr,
+                        b' ' | b'\t' => State::Padding,
+                        _ => return Err(()),
+                    }
+                }
+
+                State::Dash => {
+                    if byte == b'-' {
+                        self.state = State::Epilogue;
+                    } else {
+                        return Err(());
+                    }
+                }
+
+                State::Padding => {
+                    self.state = match byte {
+                        b'\r' => State::DelimiterCr,
+                        b' ' | b'\t' => State::Padding,
+                        _ => return Err(()),
+                    }
+                }
+
+                State::DelimiterCr => {
+                    if byte == b'\n' {
+                        self.len = 0;
+                        self.state = State::Headers;
+                    } else {
+                        return Err(());
+                    }
+                }
+
+                State::Headers => {
+                    if byte == b'\r' {
+                        self.state = State::HeaderCr;
+                    } else {
+                        *self.scratch.get_mut(self.len).ok_or(())? = byte;
+                        self.len += 1;
+                    }
+                }
+
+                State::HeaderCr => {
+                    if byte != b'\n' {
+                        return Err(());
+                    }
+
+                    if self.len == 0 {
+                        // empty line: the body follows
+                        self.state = State::Body;
+                        start = i + 1;
+                    } else {
+                        self.emit_header(&mut f)?;
+                        self.len = 0;
+                        self.state = State::Headers;
+                    }
+                }
+
+                State::Epilogue => {}
+            }
+        }
+
+        if self.state == State::Body && self.matched == 0 && start < chunk.len() {
+            f(Event::Data(&chunk[start..]));
+        }
+
+        Ok(())
+    }
+
+    /* Private */
+    fn delimiter(&self, i: usize) -> u8 {
+        if i < DELIMITER_PREFIX.len() {
+            DELIMITER_PREFIX[i]
+        } else {
+            self.boundary[i - DELIMITER_PREFIX.len()]
+        }
+    }
+
+    fn delimiter_len(&self) -> usize {
+        DELIMITER_PREFIX.len() + self.boundary.len()
+    }
+
+    // reports the first `n` bytes of the delimiter as data
+    fn emit_delimiter<F>(&self, n: usize, f: &mut F)
+    where
+        F: FnMut(Event<'_>),
+    {
+        let prefix = n.min(DELIMITER_PREFIX.len());
+        f(Event::Data(&DELIMITER_PREFIX[..prefix]));
+
+        if n > prefix {
+            f(Event::Data(&self.boundary[..n - prefix]));
+        }
+    }
+
+    fn emit_header<F>(&self, f: &mut F) -> Result<(), ()>
+    where
+        F: FnMut(Event<'_>),
+    {
+        let line = &self.scratch[..self.len];
+        let colon = line.iter().position(|b| *b == b':').ok_or(())?;
+
+        f(Event::Header {
+            name: trim(&line[..colon]),
+            value: trim(&line[colon + 1..]),
+        });
+
+        Ok(())
+    }
+}
+
+/// Returns the value of the parameter `name` of a header value like `Content-Type` or
+/// `Content-Disposition`
+///
+/// The name comparison is case insensitive. Surrounding quotes are removed from the value but
+/// escape sequences (backslashes) are left as they are
+pub fn parameter<'a>(value: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    let mut rest = value;
+
+    // skip the media type / disposition type
+    loop {
+        let semicolon = rest.iter().position(|b| *b == b';')?;
+        rest = &rest[semicolon + 1..];
+
+        let eq = rest.iter().position(|b| *b == b'=')?;
+        let key = trim(&rest[..eq]);
+        let after = trim_start(&rest[eq + 1..]);
+
+        let (val, next) = if after.first() == Some(&b'"') {
+            let end = after[1..].iter().position(|b| *b == b'"')? + 1;
+            (&after[1..end], &after[end + 1..])
+        } else {
+            let end = after.iter().position(|b| *b == b';').unwrap_or(after.len());
+            (trim(&after[..end]), &after[end..])
+        };
+
+        if str::from_utf8(key)
+            .map(|key| key.eq_ignore_ascii_case(name))
+            .unwrap_or(false)
+        {
+            return Some(val);
+        }
+
+        rest = next;
+    }
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| *b != b' ' && *b != b'\t')
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn trim(bytes: &[u8]) -> &[u8] {
+    let bytes = trim_start(bytes);
+    let end = bytes
+        .iter()
+        .rposition(|b| *b != b' ' && *b != b'\t')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    &bytes[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, Parser};
+    use crate::multipart;
+
+    const BODY: &[u8] = b"preamble\r\n\
+        --AaB03x\r\n\
+        Content-Disposition: form-data; name=\"field1\"\r\n\
+        \r\n\
+        Joe Blow\r\n\
+        --AaB03x  \r\n\
+        Content-Disposition: form-data; name=\"pics\"; filename=\"file1.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        ...contents of\r\n--AaB0 file1.txt...\r\n\
+        --AaB03x--\r\n\
+        epilogue";
+
+    // Parses `BODY` split in chunks of `size` bytes; returns the concatenation of all the part
+    // bodies (separated by `|`) and the number of headers
+    fn parse(size: usize, out: &mut [u8]) -> (usize, usize) {
+        let mut scratch = [0; 128];
+        let mut parser = Parser::new(b"AaB03x", &mut scratch).unwrap();
+
+        let mut len = 0;
+        let mut headers = 0;
+        for chunk in BODY.chunks(size) {
+            parser
+                .feed(chunk, |event| match event {
+                    Event::Header { name, value } => {
+                        headers += 1;
+                        if name == b"Content-Type" {
+                            assert_eq!(value, b"text/plain");
+                        }
+                    }
+                    Event::Data(data) => {
+                        out[len..len + data.len()].copy_from_slice(data);
+                        len += data.len();
+                    }
+                    Event::PartEnd => {
+                        out[len] = b'|';
+                        len += 1;
+                    }
+                })
+                .unwrap();
+        }
+
+        assert!(parser.is_done());
+        (len, headers)
+    }
+
+    #[test]
+    fn chunks() {
+        const EXPECTED: &[u8] = b"Joe Blow|...contents of\r\n--AaB0 file1.txt...|";
+
+        for size in 1..=BODY.len() {
+            let mut out = [0; 128];
+            let (len, headers) = parse(size, &mut out);
+            assert_eq!(&out[..len], EXPECTED, "chunk size = {}", size);
+            assert_eq!(headers, 3);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut scratch = [0; 16];
+        assert!(Parser::new(b"", &mut scratch).is_err());
+        assert!(Parser::new(b"a\r\nb", &mut scratch).is_err());
+
+        // header line doesn't fit in the scratch buffer
+        let mut parser = Parser::new(b"b", &mut scratch).unwrap();
+        let body = b"--b\r\nContent-Disposition: form-data\r\n\r\n";
+        assert!(parser.feed(body, |_| {}).is_err());
+
+        // header without a colon
+        let mut scratch = [0; 16];
+        let mut parser = Parser::new(b"b", &mut scratch).unwrap();
+        assert!(parser.feed(b"--b\r\nfoo\r\n\r\n", |_| {}).is_err());
+
+        // garbage after the boundary
+        let mut scratch = [0; 16];
+        let mut parser = Parser::new(b"b", &mut scratch).unwrap();
+        assert!(parser.feed(b"--bx\r\n", |_| {}).is_err());
+    }
+
+    #[test]
+    fn parameter() {
+        let cd = b"form-data; name=\"pics\"; filename=\"a;b.txt\"";
+        assert_eq!(multipart::parameter(cd, "name"), Some(&b"pics"[..]));
+        assert_eq!(multipart::parameter(cd, "FILENAME"), Some(&b"a;b.txt"[..]));
+        assert_eq!(multipart::parameter(cd, "size"), None);
+
+        let ct = b"multipart/form-data ; boundary=AaB03x";
+        assert_eq!(multipart::parameter(ct, "boundary"), Some(&b"AaB03x"[..]));
+    }
+}
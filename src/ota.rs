@@ -0,0 +1,327 @@
+//! Transport agnostic bookkeeping for over-the-air (OTA) firmware downloads
+//!
+//! `Downloader` doesn't send or receive anything. It tells the application which part of the image
+//! to request next, as a byte range (HTTP `Range`) or as a block number (TFTP, CoAP Block2). It
+//! passes the received data to a Flash writer and checks the image against a caller-provided
+//! digest. The download offset can be persisted so an interrupted download can be resumed after a
+//! reset.
+//!
+//! # Example
+//!
+//! ```
+//! use jnet::ota::{Digest, Downloader};
+//!
+//! // NOTE use a cryptographic hash function in real applications
+//! struct Sum {
+//!     sum: u8,
+//!     expected: u8,
+//! }
+//!
+//! impl Digest for Sum {
+//!     fn update(&mut self, bytes: &[u8]) {
+//!         for byte in bytes {
+//!             self.sum = self.sum.wrapping_add(*byte);
+//!         }
+//!     }
+//!
+//!     fn verify(self) -> bool {
+//!         self.sum == self.expected
+//!     }
+//! }
+//!
+//! let image = [1, 2, 3, 4, 5, 6, 7];
+//! let mut flash = [0; 7];
+//!
+//! let mut ota = Downloader::new(image.len() as u32, 4, Sum { sum: 0, expected: 28 });
+//! while let Some(range) = ota.next_range() {
+//!     // request `range` from the server
+//!     let data = &image[range.start as usize..range.end as usize];
+//!
+//!     ota.write(range.start, data, |offset, data| {
+//!         let offset = offset as usize;
+//!         flash[offset..offset + data.len()].copy_from_slice(data);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! }
+//!
+//! assert!(ota.finish().is_ok());
+//! assert_eq!(flash, image);
+//! ```
+
+use core::ops::Range;
+
+use cast::{u32, usize};
+
+/// A digest (e.g. SHA-256) of the firmware image
+///
+/// Implementers hold the expected digest, which usually comes from a signed manifest
+pub trait Digest {
+    /// Feeds the next bytes of the image to the hash function
+    fn update(&mut self, bytes: &[u8]);
+
+    /// Returns `true` if the digest of all the bytes fed so far matches the expected one
+    fn verify(self) -> bool;
+}
+
+/// OTA download state
+pub struct Downloader<D>
+where
+    D: Digest,
+{
+    block_size: u16,
+    digest: D,
+    offset: u32,
+    size: u32,
+}
+
+impl<D> Downloader<D>
+where
+    D: Digest,
+{
+    /// Starts the download of a `size` bytes long image in blocks of `block_size` bytes
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if `block_size` is `0`
+    pub fn new(size: u32, block_size: u16, digest: D) -> Self {
+        assert!(block_size != 0);
+
+        Downloader {
+            block_size,
+            digest,
+            offset: 0,
+            size,
+        }
+    }
+
+    /// Resumes a download that was interrupted after writing `offset` bytes to Flash
+    ///
+    /// `digest` must have already been fed the first `offset` bytes of the image, read back from
+    /// Flash. Returns `Err` if `offset` is greater than `size`
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if `block_size` is `0`
+    pub fn resume(size: u32, block_size: u16, offset: u32, digest: D) -> Result<Self, ()> {
+        assert!(block_size != 0);
+
+        if offset > size {
+            return Err(());
+        }
+
+        Ok(Downloader {
+            block_size,
+            digest,
+            offset,
+            size,
+        })
+    }
+
+    /// Returns the number of bytes written so far
+    ///
+    /// Persist this value (after the Flash write has completed) to be able to `resume` the
+    /// download
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns the size of the image
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Have all the bytes of the image been written?
+    pub fn is_complete(&self) -> bool {
+        self.offset == self.size
+    }
+
+    /// Returns the byte range to request next, or `None` if the download is complete
+    pub fn next_range(&self) -> Option<Range<u32>> {
+        if self.is_complete() {
+            None
+        } else {
+            let end = self
+                .offset
+                .saturating_add(u32(self.block_size))
+                .min(self.size);
+            Some(self.offset..end)
+        }
+    }
+
+    /// Returns the number of the block to request next, or `None` if the download is complete
+    ///
+    /// Blocks are numbered from `0`; TFTP numbers them from `1`. If `offset` is not a multiple of
+    /// the block size the returned block partially overlaps data that was already written;
+    /// `write` skips the overlapping part
+    pub fn next_block(&self) -> Option<u32> {
+        if self.is_complete() {
+            None
+        } else {
+            Some(self.offset / u32(self.block_size))
+        }
+    }
+
+    /// Hands the data received at `offset` to the Flash writer `f`
+    ///
+    /// `f` is called with the offset of the data within the image and the data itself.
+    /// Retransmissions of data that was already written are ignored. Returns `Err`, without
+    /// calling `f`, if there's a gap between the data written so far and `offset` or if the
+    /// data extends past the end of the image. Errors returned by `f` are propagated and leave
+    /// the download state unchanged
+    pub fn write<F>(&mut self, offset: u32, data: &[u8], f: F) -> Result<(), ()>
+    where
+        F: FnOnce(u32, &[u8]) -> Result<(), ()>,
+    {
+        let end = u64::from(offset) + data.len() as u64;
+
+        if offset > self.offset || end > u64::from(self.size) {
+            return Err(());
+        }
+
+        // NOTE(cast) `end` is not greater than `size`
+        let end = end as u32;
+
+        if end <= self.offset {
+            // duplicate
+            return Ok(());
+        }
+
+        // skip the part that was already written
+        let data = &data[usize(self.offset - offset)..];
+        f(self.offset, data)?;
+
+        self.digest.update(data);
+        self.offset = end;
+
+        Ok(())
+    }
+
+    /// Verifies the digest of the downloaded image
+    ///
+    /// Returns `Err` if the download is not complete or if the digest doesn't match
+    pub fn finish(self) -> Result<(), ()> {
+        if self.is_complete() && self.digest.verify() {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Digest, Downloader};
+
+    const IMAGE: &[u8] = b"0123456789abcdef!";
+
+    // FNV-1a
+    struct Fnv {
+        hash: u32,
+        expected: u32,
+    }
+
+    impl Fnv {
+        fn new(expected: u32) -> Self {
+            Fnv {
+                hash: 0x811c_9dc5,
+                expected,
+            }
+        }
+
+        fn of(bytes: &[u8]) -> u32 {
+            let mut fnv = Fnv::new(0);
+            fnv.update(bytes);
+            fnv.hash
+        }
+    }
+
+    impl Digest for Fnv {
+        fn update(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.hash ^= u32::from(*byte);
+                self.hash = self.hash.wrapping_mul(0x0100_0193);
+            }
+        }
+
+        fn verify(self) -> bool {
+            self.hash == self.expected
+        }
+    }
+
+    fn noop(_: u32, _: &[u8]) -> Result<(), ()> {
+        Ok(())
+    }
+
+    #[test]
+    fn blocks() {
+        let mut ota = Downloader::new(IMAGE.len() as u32, 8, Fnv::new(Fnv::of(IMAGE)));
+
+        assert_eq!(ota.next_range(), Some(0..8));
+        assert_eq!(ota.next_block(), Some(0));
+        ota.write(0, &IMAGE[..8], noop).unwrap();
+
+        // gap
+        assert!(ota.write(16, &IMAGE[16..], noop).is_err());
+
+        // retransmission
+        ota.write(0, &IMAGE[..8], |_, _| panic!()).unwrap();
+
+        // overlapping data: only the new part is written
+        ota.write(4, &IMAGE[4..16], |offset, data| {
+            assert_eq!(offset, 8);
+            assert_eq!(data, &IMAGE[8..16]);
+            Ok(())
+        })
+        .unwrap();
+
+        // a failed Flash write doesn't advance the download
+        assert!(ota.write(16, &IMAGE[16..], |_, _| Err(())).is_err());
+        assert_eq!(ota.offset(), 16);
+
+        // past the end of the image
+        assert!(ota.write(16, b"!!", noop).is_err());
+
+        assert_eq!(ota.next_range(), Some(16..17));
+        assert_eq!(ota.next_block(), Some(2));
+        ota.write(16, &IMAGE[16..], noop).unwrap();
+
+        assert!(ota.is_complete());
+        assert_eq!(ota.next_range(), None);
+        assert!(ota.finish().is_ok());
+    }
+
+    #[test]
+    fn resume() {
+        let size = IMAGE.len() as u32;
+        let expected = Fnv::of(IMAGE);
+
+        assert!(Downloader::resume(size, 8, 24, Fnv::new(expected)).is_err());
+
+        // after a reset the digest is fed what's already in Flash
+        let mut digest = Fnv::new(expected);
+        digest.update(&IMAGE[..12]);
+        let mut ota = Downloader::resume(size, 8, 12, digest).unwrap();
+        assert_eq!(ota.next_range(), Some(12..17));
+        assert_eq!(ota.next_block(), Some(1));
+
+        // the server sends the whole block
+        ota.write(8, &IMAGE[8..16], |offset, data| {
+            assert_eq!(offset, 12);
+            assert_eq!(data, &IMAGE[12..16]);
+            Ok(())
+        })
+        .unwrap();
+        ota.write(16, &IMAGE[16..], noop).unwrap();
+        assert!(ota.finish().is_ok());
+
+        // corrupted image
+        let mut ota = Downloader::new(size, 8, Fnv::new(expected));
+        ota.write(0, b"0123456789abcdef?", noop).unwrap();
+        assert!(ota.finish().is_err());
+
+        // incomplete image
+        let ota = Downloader::new(size, 8, Fnv::new(expected));
+        assert!(ota.finish().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Source of randomness for protocol fields
+//!
+//! Transaction IDs, tokens and message IDs must be hard to predict: a counter that starts at `0`
+//! on every boot collides with other devices (and with the previous boot of the same device) and
+//! makes spoofing responses trivial. This module doesn't ship a random number generator; plug in a
+//! hardware RNG, a CSPRNG seeded from one or, with the `rand_core` feature enabled, any
+//! `rand_core::RngCore` through [`RandCore`].
+//!
+//! [`RandCore`]: struct.RandCore.html
+
+/// A random number generator
+pub trait Rng {
+    /// Returns a random `u32`
+    fn next_u32(&mut self) -> u32;
+
+    /// Fills `dest` with random bytes
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+}
+
+impl<R> Rng for &mut R
+where
+    R: Rng + ?Sized,
+{
+    fn next_u32(&mut self) -> u32 {
+        (**self).next_u32()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        (**self).fill_bytes(dest)
+    }
+}
+
+/// Adapter that turns a `rand_core::RngCore` into an [`Rng`](trait.Rng.html)
+#[cfg(feature = "rand_core")]
+pub struct RandCore<R>(pub R);
+
+#[cfg(feature = "rand_core")]
+impl<R> Rng for RandCore<R>
+where
+    R: rand_core::RngCore,
+{
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::Rng;
+
+    /// Deterministic generator for tests (xorshift32)
+    pub struct XorShift(pub u32);
+
+    impl Rng for XorShift {
+        fn next_u32(&mut self) -> u32 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            self.0 = x;
+            x
+        }
+    }
+
+    #[test]
+    fn fill_bytes() {
+        let mut rng = XorShift(1);
+        let first = rng.next_u32().to_le_bytes();
+        let second = rng.next_u32().to_le_bytes();
+
+        // partial chunks use the lower bytes of a fresh number
+        let mut bytes = [0; 6];
+        XorShift(1).fill_bytes(&mut bytes);
+        assert_eq!(&bytes[..4], &first[..]);
+        assert_eq!(&bytes[4..], &second[..2]);
+
+        // through a reference
+        fn next<R>(mut rng: R) -> u32
+        where
+            R: Rng,
+        {
+            rng.next_u32()
+        }
+
+        let mut rng = XorShift(1);
+        assert_eq!(next(&mut rng).to_le_bytes(), first);
+        assert_eq!(next(&mut rng).to_le_bytes(), second);
+    }
+}
@@ -1,7 +1,21 @@
-use crate::icmp::{EchoReply, EchoRequest};
+use crate::icmp::{AddressMaskReply, AddressMaskRequest, EchoReply, EchoRequest};
 
 // [Type State] EchoReply or EchoRequest
-pub trait Echo: 'static {}
+pub trait Echo: Identified {}
 
 impl Echo for EchoReply {}
 impl Echo for EchoRequest {}
+
+// [Type State] A message with Identifier and Sequence Number fields
+pub trait Identified: 'static {}
+
+impl Identified for EchoReply {}
+impl Identified for EchoRequest {}
+impl Identified for AddressMaskReply {}
+impl Identified for AddressMaskRequest {}
+
+// [Type State] AddressMaskReply or AddressMaskRequest
+pub trait AddressMask: Identified {}
+
+impl AddressMask for AddressMaskReply {}
+impl AddressMask for AddressMaskRequest {}
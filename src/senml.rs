@@ -0,0 +1,419 @@
+//! SenML: Sensor Measurement Lists
+//!
+//! Serialization of SenML Packs (lists of records) into JSON or CBOR. The corresponding CoAP
+//! Content-Formats are `coap::ContentFormat::ApplicationSenmlJson` and
+//! `coap::ContentFormat::ApplicationSenmlCbor`.
+//!
+//! # References
+//!
+//! - [RFC 8428: Sensor Measurement Lists (SenML)][rfc]
+//!
+//! [rfc]: https://tools.ietf.org/html/rfc8428
+//!
+//! # Example
+//!
+//! ```
+//! use jnet::senml::{self, Record, Value};
+//!
+//! let records = [
+//!     Record {
+//!         base_name: Some("urn:dev:ow:10e2073a01080063:"),
+//!         name: Some("temp"),
+//!         unit: Some("Cel"),
+//!         value: Some(Value::Float(23.5)),
+//!         ..Record::default()
+//!     },
+//!     Record {
+//!         name: Some("hum"),
+//!         unit: Some("%RH"),
+//!         value: Some(Value::Integer(41)),
+//!         ..Record::default()
+//!     },
+//! ];
+//!
+//! let mut buf = [0; 128];
+//! assert_eq!(
+//!     senml::to_json(&records, &mut buf),
+//!     Ok(concat!(
+//!         r#"[{"bn":"urn:dev:ow:10e2073a01080063:","n":"temp","u":"Cel","v":23.5},"#,
+//!         r#"{"n":"hum","u":"%RH","v":41}]"#
+//!     ))
+//! );
+//! ```
+
+use core::fmt::{self, Write};
+
+use crate::cbor;
+
+/// A SenML record
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Record<'a> {
+    /// Base Name; prepended to the names of this record and of the following ones
+    pub base_name: Option<&'a str>,
+    /// Base Time, in seconds; added to the times of this record and of the following ones
+    pub base_time: Option<f64>,
+    /// Base Unit; the unit of this record and of the following ones that don't have one
+    pub base_unit: Option<&'a str>,
+    /// Name
+    pub name: Option<&'a str>,
+    /// Unit
+    pub unit: Option<&'a str>,
+    /// Value
+    pub value: Option<Value<'a>>,
+    /// Time, in seconds
+    pub time: Option<f64>,
+}
+
+/// The value of a SenML record
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'a> {
+    /// Numeric value
+    Float(f64),
+    /// Numeric value that's an integer
+    Integer(i64),
+    /// String value
+    String(&'a str),
+    /// Boolean value
+    Bool(bool),
+    /// Data value
+    Data(&'a [u8]),
+}
+
+/* Labels (Section 4.3 and 6) */
+const BASE_NAME: (&str, i64) = ("bn", -2);
+const BASE_TIME: (&str, i64) = ("bt", -3);
+const BASE_UNIT: (&str, i64) = ("bu", -4);
+const NAME: (&str, i64) = ("n", 0);
+const UNIT: (&str, i64) = ("u", 1);
+const VALUE: (&str, i64) = ("v", 2);
+const STRING_VALUE: (&str, i64) = ("vs", 3);
+const BOOLEAN_VALUE: (&str, i64) = ("vb", 4);
+const TIME: (&str, i64) = ("t", 6);
+const DATA_VALUE: (&str, i64) = ("vd", 8);
+
+/// Serializes `records` as a SenML Pack in JSON format into `buffer`
+///
+/// Data values are encoded in base64url without padding
+///
+/// Returns `Err` if `buffer` is too small or a numeric value is not finite
+pub fn to_json<'b>(records: &[Record<'_>], buffer: &'b mut [u8]) -> Result<&'b str, ()> {
+    let mut json = Json { buffer, len: 0 };
+
+    json.raw("[")?;
+    for (i, record) in records.iter().enumerate() {
+        if i != 0 {
+            json.raw(",")?;
+        }
+
+        let mut first = true;
+        let mut key = |json: &mut Json<'_>, label: (&str, i64)| -> Result<(), ()> {
+            json.raw(if first { "{\"" } else { ",\"" })?;
+            first = false;
+            json.raw(label.0)?;
+            json.raw("\":")
+        };
+
+        if let Some(base_name) = record.base_name {
+            key(&mut json, BASE_NAME)?;
+            json.string(base_name)?;
+        }
+
+        if let Some(base_time) = record.base_time {
+            key(&mut json, BASE_TIME)?;
+            json.float(base_time)?;
+        }
+
+        if let Some(base_unit) = record.base_unit {
+            key(&mut json, BASE_UNIT)?;
+            json.string(base_unit)?;
+        }
+
+        if let Some(name) = record.name {
+            key(&mut json, NAME)?;
+            json.string(name)?;
+        }
+
+        if let Some(unit) = record.unit {
+            key(&mut json, UNIT)?;
+            json.string(unit)?;
+        }
+
+        match record.value {
+            Some(Value::Float(value)) => {
+                key(&mut json, VALUE)?;
+                json.float(value)?;
+            }
+            Some(Value::Integer(value)) => {
+                key(&mut json, VALUE)?;
+                write!(json, "{}", value).map_err(|_| ())?;
+            }
+            Some(Value::String(value)) => {
+                key(&mut json, STRING_VALUE)?;
+                json.string(value)?;
+            }
+            Some(Value::Bool(value)) => {
+                key(&mut json, BOOLEAN_VALUE)?;
+                json.raw(if value { "true" } else { "false" })?;
+            }
+            Some(Value::Data(value)) => {
+                key(&mut json, DATA_VALUE)?;
+                json.raw("\"")?;
+                json.base64url(value)?;
+                json.raw("\"")?;
+            }
+            None => {}
+        }
+
+        if let Some(time) = record.time {
+            key(&mut json, TIME)?;
+            json.float(time)?;
+        }
+
+        json.raw(if first { "{}" } else { "}" })?;
+    }
+    json.raw("]")?;
+
+    let Json { buffer, len } = json;
+    // NOTE(unsafe) only valid UTF-8 has been written into `buffer`
+    Ok(unsafe { core::str::from_utf8_unchecked(&buffer[..len]) })
+}
+
+/// Serializes `records` as a SenML Pack in CBOR format into `buffer`
+///
+/// Returns `Err` if `buffer` is too small
+pub fn to_cbor<'b>(records: &[Record<'_>], buffer: &'b mut [u8]) -> Result<&'b [u8], ()> {
+    let mut cbor = cbor::Encoder::new(buffer);
+
+    cbor.head(cbor::ARRAY, records.len() as u64)?;
+    for record in records {
+        let fields = [
+            record.base_name.is_some(),
+            record.base_time.is_some(),
+            record.base_unit.is_some(),
+            record.name.is_some(),
+            record.unit.is_some(),
+            record.value.is_some(),
+            record.time.is_some(),
+        ];
+        let nfields = fields.iter().filter(|field| **field).count();
+        cbor.head(cbor::MAP, nfields as u64)?;
+
+        if let Some(base_name) = record.base_name {
+            cbor.int(BASE_NAME.1)?;
+            cbor.text(base_name)?;
+        }
+
+        if let Some(base_time) = record.base_time {
+            cbor.int(BASE_TIME.1)?;
+            cbor.float(base_time)?;
+        }
+
+        if let Some(base_unit) = record.base_unit {
+            cbor.int(BASE_UNIT.1)?;
+            cbor.text(base_unit)?;
+        }
+
+        if let Some(name) = record.name {
+            cbor.int(NAME.1)?;
+            cbor.text(name)?;
+        }
+
+        if let Some(unit) = record.unit {
+            cbor.int(UNIT.1)?;
+            cbor.text(unit)?;
+        }
+
+        match record.value {
+            Some(Value::Float(value)) => {
+                cbor.int(VALUE.1)?;
+                cbor.float(value)?;
+            }
+            Some(Value::Integer(value)) => {
+                cbor.int(VALUE.1)?;
+                cbor.int(value)?;
+            }
+            Some(Value::String(value)) => {
+                cbor.int(STRING_VALUE.1)?;
+                cbor.text(value)?;
+            }
+            Some(Value::Bool(value)) => {
+                cbor.int(BOOLEAN_VALUE.1)?;
+                cbor.bool(value)?;
+            }
+            Some(Value::Data(value)) => {
+                cbor.int(DATA_VALUE.1)?;
+                cbor.bytes(value)?;
+            }
+            None => {}
+        }
+
+        if let Some(time) = record.time {
+            cbor.int(TIME.1)?;
+            cbor.float(time)?;
+        }
+    }
+
+    Ok(cbor.finish())
+}
+
+// JSON writer
+struct Json<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> Json<'a> {
+    fn raw(&mut self, s: &str) -> Result<(), ()> {
+        let end = self.len + s.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(())?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+
+    fn float(&mut self, value: f64) -> Result<(), ()> {
+        // NaN and infinities can't be represented in JSON
+        if !value.is_finite() {
+            return Err(());
+        }
+
+        write!(self, "{}", value).map_err(|_| ())
+    }
+
+    fn string(&mut self, s: &str) -> Result<(), ()> {
+        self.raw("\"")?;
+        for c in s.chars() {
+            match c {
+                '"' => self.raw("\\\"")?,
+                '\\' => self.raw("\\\\")?,
+                '\u{0}'..='\u{1f}' => write!(self, "\\u{:04x}", u32::from(c)).map_err(|_| ())?,
+                _ => self.raw(c.encode_utf8(&mut [0; 4]))?,
+            }
+        }
+        self.raw("\"")
+    }
+
+    fn base64url(&mut self, data: &[u8]) -> Result<(), ()> {
+        const ALPHABET: &[u8; 64] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+        for chunk in data.chunks(3) {
+            let mut group = [0; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let bits = u32::from(group[0]) << 16 | u32::from(group[1]) << 8 | u32::from(group[2]);
+
+            // no padding: 1 byte -> 2 characters, 2 bytes -> 3 characters, 3 bytes -> 4 characters
+            for i in 0..chunk.len() + 1 {
+                let index = (bits >> (18 - 6 * i)) & 0x3f;
+                let c = [ALPHABET[index as usize]];
+                // NOTE(unsafe) the alphabet is ASCII
+                self.raw(unsafe { core::str::from_utf8_unchecked(&c) })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Write for Json<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.raw(s).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Record, Value};
+    use crate::senml;
+
+    #[test]
+    fn json() {
+        let records = [
+            Record {
+                base_name: Some("dev\"1\"/"),
+                base_time: Some(1_276_020_076.001),
+                base_unit: Some("A"),
+                value: Some(Value::Float(-1.5)),
+                ..Record::default()
+            },
+            Record {
+                name: Some("on"),
+                value: Some(Value::Bool(true)),
+                time: Some(-5.),
+                ..Record::default()
+            },
+            Record {
+                name: Some("label"),
+                value: Some(Value::String("a\nb")),
+                ..Record::default()
+            },
+            Record {
+                name: Some("raw"),
+                value: Some(Value::Data(&[0xfb, 0xff, 0x00, 0x01])),
+                ..Record::default()
+            },
+            Record::default(),
+        ];
+
+        let mut buf = [0; 256];
+        assert_eq!(
+            senml::to_json(&records, &mut buf),
+            Ok(concat!(
+                r#"[{"bn":"dev\"1\"/","bt":1276020076.001,"bu":"A","v":-1.5},"#,
+                r#"{"n":"on","vb":true,"t":-5},"#,
+                r#"{"n":"label","vs":"a\u000ab"},"#,
+                r#"{"n":"raw","vd":"-_8AAQ"},"#,
+                r#"{}]"#
+            ))
+        );
+
+        assert!(senml::to_json(&records, &mut buf[..32]).is_err());
+
+        let nan = [Record {
+            value: Some(Value::Float(core::f64::NAN)),
+            ..Record::default()
+        }];
+        assert!(senml::to_json(&nan, &mut buf).is_err());
+    }
+
+    #[test]
+    fn cbor() {
+        let records = [
+            Record {
+                base_name: Some("a"),
+                name: Some("t"),
+                value: Some(Value::Float(23.5)),
+                ..Record::default()
+            },
+            Record {
+                value: Some(Value::Integer(-300)),
+                time: Some(0.1),
+                ..Record::default()
+            },
+            Record {
+                value: Some(Value::Bool(false)),
+                ..Record::default()
+            },
+        ];
+
+        #[rustfmt::skip]
+        const EXPECTED: &[u8] = &[
+            // array(3)
+            0x83,
+            // map(3) { -2: "a", 0: "t", 2: 23.5 (float32) }
+            0xa3, 0x21, 0x61, b'a', 0x00, 0x61, b't', 0x02, 0xfa, 0x41, 0xbc, 0x00, 0x00,
+            // map(2) { 2: -300, 6: 0.1 (float64) }
+            0xa2, 0x02, 0x39, 0x01, 0x2b,
+            0x06, 0xfb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a,
+            // map(1) { 4: false }
+            0xa1, 0x04, 0xf4,
+        ];
+
+        let mut buf = [0; 64];
+        assert_eq!(senml::to_cbor(&records, &mut buf), Ok(EXPECTED));
+
+        assert!(senml::to_cbor(&records, &mut buf[..8]).is_err());
+    }
+}
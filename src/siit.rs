@@ -0,0 +1,285 @@
+//! SIIT: Stateless IP/ICMP Translation (experimental)
+//!
+//! Translates IPv4 packets into IPv6 packets and vice versa using checksum-neutral address
+//! mapping, so the transport layer (UDP, TCP) checksums don't need to be updated.
+//!
+//! ICMP / ICMPv6 translation and fragmented packets are currently not supported.
+//!
+//! # References
+//!
+//! - [RFC 7915: IP/ICMP Translation Algorithm][rfc7915]
+//!
+//! [rfc7915]: https://tools.ietf.org/html/rfc7915
+//!
+//! - [RFC 6052: IPv6 Addressing of IPv4/IPv6 Translators][rfc6052]
+//!
+//! [rfc6052]: https://tools.ietf.org/html/rfc6052
+
+use as_slice::AsSlice;
+use byteorder::{ByteOrder, NetworkEndian as NE};
+use cast::{u16, usize};
+
+use crate::{checksum, ipv4, ipv6, Valid};
+
+/// An IPv6 /96 prefix used to map IPv4 addresses into IPv6 addresses (see Section 2.2 of RFC
+/// 6052)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Prefix([u8; 12]);
+
+impl Prefix {
+    /// The Well-Known Prefix: `64:ff9b::/96`
+    ///
+    /// This prefix is checksum neutral
+    pub const WELL_KNOWN: Self = Prefix([0x00, 0x64, 0xff, 0x9b, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    /// Uses the given bytes as a prefix, as they are
+    pub fn new(bytes: [u8; 12]) -> Self {
+        Prefix(bytes)
+    }
+
+    /// Turns the given bytes into a checksum neutral prefix
+    ///
+    /// This overwrites the last 16 bits of the prefix so that the one's complement sum of the
+    /// prefix is zero
+    pub fn checksum_neutral(mut bytes: [u8; 12]) -> Self {
+        let mut state = checksum::State::new();
+        state.push(&bytes[..10]);
+        NE::write_u16(&mut bytes[10..], state.finish());
+
+        Prefix(bytes)
+    }
+
+    /// Returns the bytes of this prefix
+    pub fn bytes(&self) -> [u8; 12] {
+        self.0
+    }
+
+    /// Is this prefix checksum neutral?
+    ///
+    /// Mapping IPv4 addresses with a checksum neutral prefix doesn't change the one's complement
+    /// sum of the pseudo-header used to compute the transport layer checksum
+    pub fn is_checksum_neutral(&self) -> bool {
+        let mut state = checksum::State::new();
+        state.push(&self.0);
+
+        // NOTE both 0x0000 and 0xffff are zero in one's complement arithmetic
+        match state.finish() {
+            0 | 0xffff => true,
+            _ => false,
+        }
+    }
+
+    /// Maps the given IPv4 address into an IPv6 address
+    pub fn map(&self, addr: ipv4::Addr) -> ipv6::Addr {
+        let mut bytes = [0; 16];
+        bytes[..12].copy_from_slice(&self.0);
+        bytes[12..].copy_from_slice(&addr.0);
+        ipv6::Addr(bytes)
+    }
+
+    /// Extracts the IPv4 address embedded in the given IPv6 address
+    ///
+    /// Returns `None` if the address doesn't start with this prefix
+    pub fn unmap(&self, addr: ipv6::Addr) -> Option<ipv4::Addr> {
+        if addr.0[..12] == self.0 {
+            Some(ipv4::Addr([addr.0[12], addr.0[13], addr.0[14], addr.0[15]]))
+        } else {
+            None
+        }
+    }
+}
+
+/// Translates an IPv4 packet into an IPv6 packet that's written into `buffer`
+///
+/// The TTL is decremented and the addresses are mapped using `prefix`. The payload is copied
+/// as it is.
+///
+/// This function returns an error if
+///
+/// - `prefix` is not checksum neutral
+/// - the packet is a fragment
+/// - the packet contains an ICMP message or a protocol whose number is an IPv6 extension header
+/// - the packet contains a UDP datagram with a zero checksum
+/// - the TTL would reach zero
+/// - `buffer` is too small
+pub fn ipv4_to_ipv6<'b, B>(
+    ip: &ipv4::Packet<B, Valid>,
+    prefix: &Prefix,
+    buffer: &'b mut [u8],
+) -> Result<ipv6::Packet<&'b mut [u8]>, ()>
+where
+    B: AsSlice<Element = u8>,
+{
+    if !prefix.is_checksum_neutral() {
+        return Err(());
+    }
+
+    if ip.get_mf() || ip.get_fragment_offset() != 0 {
+        // TODO add a Fragment header
+        return Err(());
+    }
+
+    let proto = ip.get_protocol();
+    if proto == ipv4::Protocol::Icmp || proto.is_ipv6_extension_header() {
+        return Err(());
+    }
+
+    let payload = ip.payload();
+    if proto == ipv4::Protocol::Udp && payload.len() >= 8 && payload[6..8] == [0, 0] {
+        // checksum is mandatory in IPv6; we would have to compute it
+        return Err(());
+    }
+
+    if ip.get_ttl() <= 1 {
+        // TODO send ICMP Time Exceeded
+        return Err(());
+    }
+
+    let len = usize(ipv6::HEADER_SIZE) + payload.len();
+    if buffer.len() < len {
+        return Err(());
+    }
+
+    let mut ip6 = ipv6::Packet::new(&mut buffer[..len]);
+    ip6.set_traffic_class(ip.get_dscp() << 2 | ip.get_ecn());
+    ip6.set_flow_label(0);
+    ip6.set_next_header(proto);
+    ip6.set_hop_limit(ip.get_ttl() - 1);
+    ip6.set_source(prefix.map(ip.get_source()));
+    ip6.set_destination(prefix.map(ip.get_destination()));
+    ip6.payload_mut().copy_from_slice(payload);
+
+    Ok(ip6)
+}
+
+/// Translates an IPv6 packet into an IPv4 packet that's written into `buffer`
+///
+/// Both the source and destination addresses must start with `prefix`. The Hop Limit is
+/// decremented and the payload is copied as it is.
+///
+/// This function returns an error if
+///
+/// - `prefix` is not checksum neutral
+/// - any of the addresses doesn't start with `prefix`
+/// - the packet contains an ICMPv6 message or a Fragment header
+/// - the Hop Limit would reach zero
+/// - `buffer` is too small
+pub fn ipv6_to_ipv4<'b, B>(
+    ip: &ipv6::Packet<B>,
+    prefix: &Prefix,
+    buffer: &'b mut [u8],
+) -> Result<ipv4::Packet<&'b mut [u8], Valid>, ()>
+where
+    B: AsSlice<Element = u8>,
+{
+    if !prefix.is_checksum_neutral() {
+        return Err(());
+    }
+
+    let nh = ip.get_next_header();
+    if nh == ipv6::NextHeader::Ipv6Icmp || nh.is_ipv6_extension_header() {
+        return Err(());
+    }
+
+    let src = prefix.unmap(ip.get_source()).ok_or(())?;
+    let dest = prefix.unmap(ip.get_destination()).ok_or(())?;
+
+    if ip.get_hop_limit() <= 1 {
+        // TODO send ICMPv6 Time Exceeded
+        return Err(());
+    }
+
+    let payload = ip.payload();
+    let len = usize(ipv4::MIN_HEADER_SIZE) + payload.len();
+    if buffer.len() < len || u16(len).is_err() {
+        return Err(());
+    }
+
+    // NOTE `new` sets Identification = 0, DF = 1 and Fragment Offset = 0 (see Section 5.1 of
+    // RFC 7915)
+    let mut ip4 = ipv4::Packet::new(&mut buffer[..len]);
+    let tc = ip.get_traffic_class();
+    ip4.set_dscp(tc >> 2);
+    ip4.set_ecn(tc & 0b11);
+    ip4.set_ttl(ip.get_hop_limit() - 1);
+    ip4.set_protocol(nh);
+    ip4.set_source(src);
+    ip4.set_destination(dest);
+    ip4.payload_mut().copy_from_slice(payload);
+
+    Ok(ip4.update_checksum())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ipv4, ipv6, siit, udp};
+
+    const V4_SRC: ipv4::Addr = ipv4::Addr([192, 0, 2, 33]);
+    const V4_DEST: ipv4::Addr = ipv4::Addr([198, 51, 100, 1]);
+
+    #[test]
+    fn prefix() {
+        assert!(siit::Prefix::WELL_KNOWN.is_checksum_neutral());
+
+        let bytes = [0x20, 0x01, 0x0d, 0xb8, 0x12, 0x34, 0, 0, 0, 0, 0, 0];
+        assert!(!siit::Prefix::new(bytes).is_checksum_neutral());
+        assert!(siit::Prefix::checksum_neutral(bytes).is_checksum_neutral());
+
+        let addr = siit::Prefix::WELL_KNOWN.map(V4_SRC);
+        assert_eq!(
+            addr,
+            ipv6::Addr([0, 0x64, 0xff, 0x9b, 0, 0, 0, 0, 0, 0, 0, 0, 192, 0, 2, 33])
+        );
+        assert_eq!(siit::Prefix::WELL_KNOWN.unmap(addr), Some(V4_SRC));
+        assert_eq!(siit::Prefix::WELL_KNOWN.unmap(ipv6::Addr::LOOPBACK), None);
+    }
+
+    #[test]
+    fn roundtrip() {
+        const PAYLOAD: &[u8] = b"Hello, world!";
+
+        let prefix =
+            siit::Prefix::checksum_neutral([0x20, 0x01, 0x0d, 0xb8, 0x12, 0x34, 0, 0, 0, 0, 0, 0]);
+
+        // IPv6 -> IPv4
+        let mut buf6 = [0; 128];
+        let mut ip6 = ipv6::Packet::new(&mut buf6[..]);
+        ip6.set_source(prefix.map(V4_SRC));
+        ip6.set_destination(prefix.map(V4_DEST));
+        ip6.set_traffic_class(0b1011_1001);
+        ip6.set_hop_limit(64);
+        ip6.udp(|udp| {
+            udp.set_source(1337);
+            udp.set_destination(7);
+            udp.set_payload(PAYLOAD);
+        });
+
+        let mut buf4 = [0; 128];
+        let ip4 = siit::ipv6_to_ipv4(&ip6, &prefix, &mut buf4).unwrap();
+        assert_eq!(ip4.get_source(), V4_SRC);
+        assert_eq!(ip4.get_destination(), V4_DEST);
+        assert_eq!(ip4.get_ttl(), 63);
+        assert_eq!(ip4.get_dscp(), 0b10_1110);
+        assert_eq!(ip4.get_ecn(), 0b01);
+        assert_eq!(ip4.get_protocol(), ipv4::Protocol::Udp);
+        assert_eq!(ip4.payload(), ip6.payload());
+
+        // IPv4 -> IPv6
+        let mut buf = [0; 128];
+        let ip6 = siit::ipv4_to_ipv6(&ip4, &prefix, &mut buf).unwrap();
+        assert_eq!(ip6.get_source(), prefix.map(V4_SRC));
+        assert_eq!(ip6.get_destination(), prefix.map(V4_DEST));
+        assert_eq!(ip6.get_hop_limit(), 62);
+        assert_eq!(ip6.get_traffic_class(), 0b1011_1001);
+
+        // checksum neutral: the UDP checksum is still valid
+        let udp = udp::Packet::parse(ip6.payload()).unwrap();
+        assert!(udp.verify_ipv6_checksum(ip6.get_source(), ip6.get_destination()));
+        assert_eq!(udp.payload(), PAYLOAD);
+
+        // not checksum neutral
+        let mut buf4 = [0; 128];
+        let prefix = siit::Prefix::new([0x20, 0x01, 0x0d, 0xb8, 0x12, 0x34, 0, 0, 0, 0, 0, 0]);
+        assert!(siit::ipv6_to_ipv4(&ip6, &prefix, &mut buf4).is_err());
+    }
+}
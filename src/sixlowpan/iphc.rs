@@ -1115,7 +1115,7 @@ mod tests {
         test!(
             Context::empty(),
             ipv6::Addr::UNSPECIFIED,
-            ipv6::Addr([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+            ipv6::Addr::ALL_NODES,
             |packet| {
                 assert!(packet.get_sac());
                 assert_eq!(packet.get_sam(), 0b00);
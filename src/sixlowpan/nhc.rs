@@ -7,6 +7,7 @@ use byteorder::{ByteOrder, NetworkEndian as NE};
 use owning_slice::Truncate;
 
 use crate::{
+    checksum,
     coap::{self, Unset},
     ipv6,
     traits::UncheckedIndex,
@@ -163,45 +164,27 @@ where
     }
 
     fn compute_checksum(&self, src: ipv6::Addr, dest: ipv6::Addr) -> u16 {
-        const NEXT_HEADER: u8 = 17;
-
-        let mut sum: u32 = 0;
-
-        /* Pseudo-header */
-        for chunk in src.0.chunks_exact(2).chain(dest.0.chunks_exact(2)) {
-            sum += u32::from(NE::read_u16(chunk));
-        }
-
-        // length in pseudo-header
         // XXX should this be just `as u16`?
         let udp_len = self.payload().len() as u32 + 8;
-        sum += udp_len >> 16;
-        sum += udp_len & 0xffff;
 
-        sum += u32::from(NEXT_HEADER);
+        /* Pseudo-header */
+        let mut state =
+            checksum::State::ipv6_pseudo_header(src, dest, ipv6::NextHeader::Udp, udp_len);
 
         /* UDP packet */
-        sum += u32::from(self.get_source());
-        sum += u32::from(self.get_destination());
+        state.push_u16(self.get_source());
+        state.push_u16(self.get_destination());
 
         // length in UDP header (yes, again)
-        sum += udp_len >> 16;
-        sum += udp_len & 0xffff;
-
-        for chunk in self.payload().chunks(2) {
-            if chunk.len() == 2 {
-                sum += u32::from(NE::read_u16(chunk));
-            } else {
-                sum += u32::from(chunk[0]) << 8;
-            }
-        }
+        state.push_u16(udp_len as u16);
 
-        // fold carry-over
-        while sum >> 16 != 0 {
-            sum = (sum & 0xffff) + (sum >> 16);
-        }
+        state.push(self.payload());
 
-        !(sum as u16)
+        // a zero checksum is not allowed in IPv6 (Section 8.1 of RFC 8200)
+        match state.finish() {
+            0 => 0xffff,
+            cksum => cksum,
+        }
     }
 
     fn header_(&self) -> u8 {
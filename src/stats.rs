@@ -0,0 +1,206 @@
+//! Integer-only statistics for network measurements (e.g. round-trip time, jitter)
+//!
+//! All the samples are unit-less `u32` values: use whatever unit (microseconds, timer ticks, etc.)
+//! your time source provides.
+
+/// Exponentially weighted moving average
+///
+/// The smoothing factor is `1 / 2^shift`. For example, `shift = 3` gives the `1/8` factor used
+/// by TCP to compute the smoothed round-trip time (see RFC 6298).
+///
+/// The average is internally kept scaled by `2^shift` so no precision is lost to integer
+/// division
+#[derive(Clone, Copy, Debug)]
+pub struct Ewma {
+    // average scaled by `2^shift`; `None` if no sample has been recorded yet
+    scaled: Option<u64>,
+    shift: u8,
+}
+
+impl Ewma {
+    /// Creates a new moving average with a smoothing factor of `1 / 2^shift`
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if `shift` is greater than 16
+    pub fn new(shift: u8) -> Self {
+        assert!(shift <= 16);
+
+        Ewma {
+            scaled: None,
+            shift,
+        }
+    }
+
+    /// Records a new sample
+    ///
+    /// The first sample initializes the average
+    pub fn update(&mut self, sample: u32) {
+        let sample = u64::from(sample);
+
+        self.scaled = Some(match self.scaled {
+            None => sample << self.shift,
+            // avg += (sample - avg) / 2^shift
+            Some(scaled) => scaled - self.round(scaled) + sample,
+        });
+    }
+
+    /// Returns the current average, rounded to the nearest integer
+    ///
+    /// Returns `None` if no sample has been recorded
+    pub fn get(&self) -> Option<u32> {
+        // NOTE(cast) the average can't be greater than the largest sample
+        self.scaled.map(|scaled| self.round(scaled) as u32)
+    }
+
+    /// Forgets all the recorded samples
+    pub fn reset(&mut self) {
+        self.scaled = None;
+    }
+
+    // unscales `scaled`, rounding to the nearest integer; rounding (rather than truncating) keeps
+    // the average from getting stuck above a constant input
+    fn round(&self, scaled: u64) -> u64 {
+        let half = (1 << self.shift) >> 1;
+        (scaled + half) >> self.shift
+    }
+}
+
+/// Minimum, maximum and mean of a series of samples
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinMaxMean {
+    min: u32,
+    max: u32,
+    sum: u64,
+    count: u32,
+}
+
+impl MinMaxMean {
+    /// Creates a new, empty, series
+    pub fn new() -> Self {
+        MinMaxMean {
+            min: 0,
+            max: 0,
+            sum: 0,
+            count: 0,
+        }
+    }
+
+    /// Records a new sample
+    ///
+    /// NOTE once `u32::MAX` samples have been recorded new samples are ignored
+    pub fn update(&mut self, sample: u32) {
+        if self.count == u32::max_value() {
+            return;
+        }
+
+        if self.count == 0 {
+            self.min = sample;
+            self.max = sample;
+        } else {
+            if sample < self.min {
+                self.min = sample;
+            }
+
+            if sample > self.max {
+                self.max = sample;
+            }
+        }
+
+        self.sum += u64::from(sample);
+        self.count += 1;
+    }
+
+    /// Returns the number of recorded samples
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Returns the smallest sample, or `None` if no sample has been recorded
+    pub fn min(&self) -> Option<u32> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.min)
+        }
+    }
+
+    /// Returns the largest sample, or `None` if no sample has been recorded
+    pub fn max(&self) -> Option<u32> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.max)
+        }
+    }
+
+    /// Returns the mean of the samples, rounded down, or `None` if no sample has been recorded
+    pub fn mean(&self) -> Option<u32> {
+        if self.count == 0 {
+            None
+        } else {
+            // NOTE(cast) the mean can't be greater than the largest sample
+            Some((self.sum / u64::from(self.count)) as u32)
+        }
+    }
+
+    /// Forgets all the recorded samples
+    pub fn reset(&mut self) {
+        *self = MinMaxMean::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ewma, MinMaxMean};
+
+    #[test]
+    fn ewma() {
+        let mut avg = Ewma::new(3);
+        assert_eq!(avg.get(), None);
+
+        avg.update(100);
+        assert_eq!(avg.get(), Some(100));
+
+        // 100 + (180 - 100) / 8
+        avg.update(180);
+        assert_eq!(avg.get(), Some(110));
+
+        // converges to a constant input
+        for _ in 0..200 {
+            avg.update(50);
+        }
+        assert_eq!(avg.get(), Some(50));
+
+        // no overflow
+        let mut avg = Ewma::new(16);
+        for _ in 0..10 {
+            avg.update(u32::max_value());
+        }
+        assert_eq!(avg.get(), Some(u32::max_value()));
+
+        avg.reset();
+        assert_eq!(avg.get(), None);
+    }
+
+    #[test]
+    fn min_max_mean() {
+        let mut stats = MinMaxMean::new();
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.max(), None);
+        assert_eq!(stats.mean(), None);
+
+        for sample in &[30, 10, 20, 41] {
+            stats.update(*sample);
+        }
+
+        assert_eq!(stats.count(), 4);
+        assert_eq!(stats.min(), Some(10));
+        assert_eq!(stats.max(), Some(41));
+        assert_eq!(stats.mean(), Some(25));
+
+        stats.reset();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.mean(), None);
+    }
+}
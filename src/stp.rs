@@ -0,0 +1,430 @@
+//! STP: Spanning Tree Protocol
+//!
+//! Bridge Protocol Data Units (BPDUs) are carried in IEEE 802.3 frames with an LLC header whose
+//! DSAP and SSAP are `LLC_SAP`; see `ether::Frame::get_llc` and `ether::Frame::protocol_payload`.
+//!
+//! # References
+//!
+//! - IEEE 802.1D-2004, Section 9 Encoding of Bridge Protocol Data Units (BPDUs)
+
+use core::{fmt, marker::PhantomData, ops::Range};
+
+use as_slice::AsSlice;
+use byteorder::{ByteOrder, NetworkEndian as NE};
+
+use crate::{
+    fmt::Quoted,
+    mac,
+    traits::{TryFrom, TryInto, UncheckedIndex},
+    Unknown,
+};
+
+/// LLC Service Access Point used by STP
+pub const LLC_SAP: u8 = 0x42;
+
+/// Group address to which bridges send BPDUs
+pub const BRIDGE_GROUP_ADDR: mac::Addr = mac::Addr([0x01, 0x80, 0xc2, 0x00, 0x00, 0x00]);
+
+/* BPDU structure */
+const PROTOCOL_ID: Range<usize> = 0..2;
+const VERSION: usize = 2;
+const TYPE: usize = 3;
+
+/// Size of the BPDU header
+pub const HEADER_SIZE: u8 = TYPE as u8 + 1;
+
+// Configuration and RST BPDUs
+const FLAGS: usize = 4;
+mod topology_change {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 0;
+    pub const SIZE: usize = 1;
+}
+mod topology_change_ack {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 7;
+    pub const SIZE: usize = 1;
+}
+const ROOT_ID: Range<usize> = 5..13;
+const ROOT_PATH_COST: Range<usize> = 13..17;
+const BRIDGE_ID: Range<usize> = 17..25;
+const PORT_ID: Range<usize> = 25..27;
+const MESSAGE_AGE: Range<usize> = 27..29;
+const MAX_AGE: Range<usize> = 29..31;
+const HELLO_TIME: Range<usize> = 31..33;
+const FORWARD_DELAY: Range<usize> = 33..35;
+
+const CONFIG_SIZE: usize = FORWARD_DELAY.end;
+// Configuration BPDU + Version 1 Length
+const RST_SIZE: usize = CONFIG_SIZE + 1;
+
+/// [Type state] Configuration BPDU or Rapid Spanning Tree (RST) BPDU
+pub enum Config {}
+
+/// [Type state] Topology Change Notification BPDU
+pub enum Tcn {}
+
+/// Bridge Protocol Data Unit
+pub struct Bpdu<BUFFER, TYPE>
+where
+    BUFFER: AsSlice<Element = u8>,
+{
+    buffer: BUFFER,
+    _type: PhantomData<TYPE>,
+}
+
+impl<B> Bpdu<B, Unknown>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Parses bytes into a BPDU
+    ///
+    /// This rejects BPDUs whose 'Protocol Identifier' is not zero and BPDUs that are too short for
+    /// their 'BPDU Type'
+    pub fn parse(bytes: B) -> Result<Self, B> {
+        let slice = bytes.as_slice();
+
+        if slice.len() < usize::from(HEADER_SIZE) || NE::read_u16(&slice[PROTOCOL_ID]) != 0 {
+            return Err(bytes);
+        }
+
+        let min = match Type::from(slice[TYPE]) {
+            Type::Config => CONFIG_SIZE,
+            Type::Tcn => usize::from(HEADER_SIZE),
+            Type::Rst => RST_SIZE,
+            Type::Unknown(_) => usize::from(HEADER_SIZE),
+        };
+
+        if slice.len() < min {
+            Err(bytes)
+        } else {
+            Ok(Bpdu {
+                buffer: bytes,
+                _type: PhantomData,
+            })
+        }
+    }
+
+    /* Miscellaneous */
+    /// Downcasts this BPDU with unknown type into a specific type
+    pub fn downcast<TYPE>(self) -> Result<Bpdu<B, TYPE>, Self>
+    where
+        Self: TryInto<Bpdu<B, TYPE>, Error = Self>,
+    {
+        self.try_into()
+    }
+}
+
+impl<B, T> Bpdu<B, T>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'Protocol Version Identifier' field
+    pub fn get_version(&self) -> Version {
+        self.header_()[VERSION].into()
+    }
+
+    /// Reads the 'BPDU Type' field
+    pub fn get_type(&self) -> Type {
+        self.header_()[TYPE].into()
+    }
+
+    /// Returns the byte representation of this BPDU
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    /// Frees the underlying buffer
+    pub fn free(self) -> B {
+        self.buffer
+    }
+
+    /* Private */
+    fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    fn header_(&self) -> &[u8; HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
+
+        unsafe { &*(self.as_slice().as_ptr() as *const _) }
+    }
+}
+
+impl<B> TryFrom<Bpdu<B, Unknown>> for Bpdu<B, Config>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Bpdu<B, Unknown>;
+
+    fn try_from(b: Bpdu<B, Unknown>) -> Result<Self, Bpdu<B, Unknown>> {
+        // NOTE `parse` has checked the length
+        match b.get_type() {
+            Type::Config | Type::Rst => Ok(Bpdu {
+                buffer: b.buffer,
+                _type: PhantomData,
+            }),
+            _ => Err(b),
+        }
+    }
+}
+
+impl<B> TryFrom<Bpdu<B, Unknown>> for Bpdu<B, Tcn>
+where
+    B: AsSlice<Element = u8>,
+{
+    type Error = Bpdu<B, Unknown>;
+
+    fn try_from(b: Bpdu<B, Unknown>) -> Result<Self, Bpdu<B, Unknown>> {
+        if b.get_type() == Type::Tcn {
+            Ok(Bpdu {
+                buffer: b.buffer,
+                _type: PhantomData,
+            })
+        } else {
+            Err(b)
+        }
+    }
+}
+
+impl<B> Bpdu<B, Config>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Getters */
+    /// Reads the 'Flags' field
+    ///
+    /// NOTE in RST BPDUs bits 1 to 6 encode the Proposal, Port Role, Learning, Forwarding and
+    /// Agreement flags
+    pub fn get_flags(&self) -> u8 {
+        unsafe { *self.as_slice().gu(FLAGS) }
+    }
+
+    /// Reads the 'Topology Change' flag
+    pub fn get_topology_change(&self) -> bool {
+        get!(self.get_flags(), topology_change) == 1
+    }
+
+    /// Reads the 'Topology Change Acknowledgment' flag
+    pub fn get_topology_change_ack(&self) -> bool {
+        get!(self.get_flags(), topology_change_ack) == 1
+    }
+
+    /// Reads the 'Root Identifier' field
+    pub fn get_root_id(&self) -> BridgeId {
+        BridgeId::from_bytes(unsafe { self.as_slice().r(ROOT_ID) })
+    }
+
+    /// Reads the 'Root Path Cost' field
+    pub fn get_root_path_cost(&self) -> u32 {
+        NE::read_u32(unsafe { self.as_slice().r(ROOT_PATH_COST) })
+    }
+
+    /// Reads the 'Bridge Identifier' field
+    pub fn get_bridge_id(&self) -> BridgeId {
+        BridgeId::from_bytes(unsafe { self.as_slice().r(BRIDGE_ID) })
+    }
+
+    /// Reads the 'Port Identifier' field
+    pub fn get_port_id(&self) -> u16 {
+        NE::read_u16(unsafe { self.as_slice().r(PORT_ID) })
+    }
+
+    /// Reads the 'Message Age' field, in units of 1/256 seconds
+    pub fn get_message_age(&self) -> u16 {
+        NE::read_u16(unsafe { self.as_slice().r(MESSAGE_AGE) })
+    }
+
+    /// Reads the 'Max Age' field, in units of 1/256 seconds
+    pub fn get_max_age(&self) -> u16 {
+        NE::read_u16(unsafe { self.as_slice().r(MAX_AGE) })
+    }
+
+    /// Reads the 'Hello Time' field, in units of 1/256 seconds
+    pub fn get_hello_time(&self) -> u16 {
+        NE::read_u16(unsafe { self.as_slice().r(HELLO_TIME) })
+    }
+
+    /// Reads the 'Forward Delay' field, in units of 1/256 seconds
+    ///
+    /// A bridge port spends this much time in each of the Listening and Learning states before it
+    /// starts forwarding frames
+    pub fn get_forward_delay(&self) -> u16 {
+        NE::read_u16(unsafe { self.as_slice().r(FORWARD_DELAY) })
+    }
+}
+
+impl<B> fmt::Debug for Bpdu<B, Config>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("stp::Bpdu<Config>")
+            .field("version", &self.get_version())
+            .field("type", &self.get_type())
+            .field("flags", &self.get_flags())
+            .field("root_id", &self.get_root_id())
+            .field("root_path_cost", &self.get_root_path_cost())
+            .field("bridge_id", &self.get_bridge_id())
+            .field("port_id", &self.get_port_id())
+            .field("message_age", &self.get_message_age())
+            .field("max_age", &self.get_max_age())
+            .field("hello_time", &self.get_hello_time())
+            .field("forward_delay", &self.get_forward_delay())
+            .finish()
+    }
+}
+
+impl<B> fmt::Debug for Bpdu<B, Tcn>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("stp::Bpdu<Tcn>")
+            .field("version", &self.get_version())
+            .finish()
+    }
+}
+
+impl<B> fmt::Debug for Bpdu<B, Unknown>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("stp::Bpdu")
+            .field("version", &self.get_version())
+            .field("type", &self.get_type())
+            .finish()
+    }
+}
+
+/// Bridge Identifier
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct BridgeId {
+    /// Bridge priority (4 bits) and system ID extension (12 bits)
+    pub priority: u16,
+    /// MAC address of the bridge
+    pub addr: mac::Addr,
+}
+
+impl BridgeId {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let mut addr = [0; 6];
+        addr.copy_from_slice(&bytes[2..8]);
+
+        BridgeId {
+            priority: NE::read_u16(&bytes[..2]),
+            addr: mac::Addr(addr),
+        }
+    }
+}
+
+impl fmt::Debug for BridgeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("stp::BridgeId")
+            .field("priority", &self.priority)
+            .field("addr", &Quoted(self.addr))
+            .finish()
+    }
+}
+
+full_range!(
+    u8,
+    /// Protocol Version Identifier
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Version {
+        /// Spanning Tree Protocol
+        Stp = 0,
+        /// Rapid Spanning Tree Protocol
+        Rstp = 2,
+        /// Multiple Spanning Tree Protocol
+        Mstp = 3,
+    }
+);
+
+full_range!(
+    u8,
+    /// BPDU Type
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum Type {
+        /// Configuration BPDU
+        Config = 0x00,
+        /// Rapid Spanning Tree BPDU (also used by MSTP)
+        Rst = 0x02,
+        /// Topology Change Notification BPDU
+        Tcn = 0x80,
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{ether, mac, stp};
+
+    #[rustfmt::skip]
+    const FRAME: &[u8] = &[
+        0x01, 0x80, 0xc2, 0x00, 0x00, 0x00, // eth: destination
+        0x00, 0x1c, 0x0e, 0x87, 0x85, 0x04, // eth: source
+        0x00, 0x26, // eth: length
+        0x42, 0x42, 0x03, // llc
+        0x00, 0x00, // bpdu: protocol identifier
+        0x00, // bpdu: version
+        0x00, // bpdu: type
+        0x01, // bpdu: flags
+        0x80, 0x64, 0x00, 0x1c, 0x0e, 0x87, 0x78, 0x00, // bpdu: root identifier
+        0x00, 0x00, 0x00, 0x04, // bpdu: root path cost
+        0x80, 0x64, 0x00, 0x1c, 0x0e, 0x87, 0x85, 0x00, // bpdu: bridge identifier
+        0x80, 0x04, // bpdu: port identifier
+        0x01, 0x00, // bpdu: message age
+        0x14, 0x00, // bpdu: max age
+        0x02, 0x00, // bpdu: hello time
+        0x0f, 0x00, // bpdu: forward delay
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // eth: padding
+    ];
+
+    #[test]
+    fn config() {
+        let eth = ether::Frame::parse(FRAME).unwrap();
+        assert_eq!(eth.get_destination(), stp::BRIDGE_GROUP_ADDR);
+        let llc = eth.get_llc().unwrap();
+        assert_eq!(llc.dsap, stp::LLC_SAP);
+        assert_eq!(llc.ssap, stp::LLC_SAP);
+
+        let bpdu = stp::Bpdu::parse(eth.protocol_payload().unwrap())
+            .unwrap()
+            .downcast::<stp::Config>()
+            .unwrap();
+
+        assert_eq!(bpdu.get_version(), stp::Version::Stp);
+        assert_eq!(bpdu.get_type(), stp::Type::Config);
+        assert!(bpdu.get_topology_change());
+        assert!(!bpdu.get_topology_change_ack());
+        assert_eq!(
+            bpdu.get_root_id(),
+            stp::BridgeId {
+                priority: 0x8064,
+                addr: mac::Addr([0x00, 0x1c, 0x0e, 0x87, 0x78, 0x00]),
+            }
+        );
+        assert_eq!(bpdu.get_root_path_cost(), 4);
+        assert_eq!(bpdu.get_port_id(), 0x8004);
+        assert_eq!(bpdu.get_message_age(), 256);
+        assert_eq!(bpdu.get_max_age(), 20 * 256);
+        assert_eq!(bpdu.get_hello_time(), 2 * 256);
+        assert_eq!(bpdu.get_forward_delay(), 15 * 256);
+    }
+
+    #[test]
+    fn tcn() {
+        let bpdu = stp::Bpdu::parse(&[0, 0, 0, 0x80][..]).unwrap();
+        assert_eq!(bpdu.get_type(), stp::Type::Tcn);
+        assert!(bpdu.downcast::<stp::Tcn>().is_ok());
+
+        // truncated configuration BPDU
+        assert!(stp::Bpdu::parse(&[0, 0, 0, 0, 0][..]).is_err());
+
+        // wrong protocol identifier
+        assert!(stp::Bpdu::parse(&[0, 1, 0, 0x80][..]).is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! TCP: Transmission Control Protocol
+//!
+//! NOTE only parsing is supported; there's no TCP state machine in this crate. Options are exposed
+//! as raw bytes and through an iterator that walks them using the Kind / Length encoding, so
+//! options this module doesn't know about (e.g. TCP-MD5 or TCP-AO signatures) are skipped rather
+//! than misparsed.
+//!
+//! # References
+//!
+//! - [RFC 793: Transmission Control Protocol][rfc793]
+//! - [RFC 2385: Protection of BGP Sessions via the TCP MD5 Signature Option][rfc2385]
+//! - [RFC 5925: The TCP Authentication Option][rfc5925]
+//!
+//! [rfc793]: https://tools.ietf.org/html/rfc793
+//! [rfc2385]: https://tools.ietf.org/html/rfc2385
+//! [rfc5925]: https://tools.ietf.org/html/rfc5925
+
+use core::{
+    fmt,
+    ops::{Range, RangeFrom},
+};
+
+use as_slice::AsSlice;
+use byteorder::{ByteOrder, NetworkEndian as NE};
+use cast::{u32, usize};
+
+use crate::{checksum, fmt::Hex, ipv4, ipv6, traits::UncheckedIndex};
+
+/* Segment structure */
+const SOURCE: Range<usize> = 0..2;
+const DESTINATION: Range<usize> = 2..4;
+const SEQUENCE_NUMBER: Range<usize> = 4..8;
+const ACKNOWLEDGMENT_NUMBER: Range<usize> = 8..12;
+const DATA_OFFSET_FLAGS: Range<usize> = 12..14;
+const WINDOW: Range<usize> = 14..16;
+const CHECKSUM: Range<usize> = 16..18;
+const URGENT_POINTER: Range<usize> = 18..20;
+const OPTIONS: RangeFrom<usize> = 20..;
+
+/// Size of the TCP header, without options
+pub const HEADER_SIZE: u8 = OPTIONS.start as u8;
+
+mod data_offset {
+    pub const MASK: u16 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 12;
+    pub const SIZE: usize = 4;
+}
+
+/* Flags */
+/// No more data from sender
+pub const FIN: u16 = 1 << 0;
+/// Synchronize sequence numbers
+pub const SYN: u16 = 1 << 1;
+/// Reset the connection
+pub const RST: u16 = 1 << 2;
+/// Push function
+pub const PSH: u16 = 1 << 3;
+/// Acknowledgment field significant
+pub const ACK: u16 = 1 << 4;
+/// Urgent Pointer field significant
+pub const URG: u16 = 1 << 5;
+/// ECN-Echo (see RFC 3168)
+pub const ECE: u16 = 1 << 6;
+/// Congestion Window Reduced (see RFC 3168)
+pub const CWR: u16 = 1 << 7;
+
+const FLAGS_MASK: u16 = (1 << 9) - 1;
+
+/* Option structure */
+const OPTION_KIND: usize = 0;
+const OPTION_LENGTH: usize = 1;
+const OPTION_HEADER_SIZE: usize = 2;
+
+/// TCP segment
+pub struct Segment<BUFFER>
+where
+    BUFFER: AsSlice<Element = u8>,
+{
+    buffer: BUFFER,
+}
+
+impl<B> Segment<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Parses the bytes as a TCP segment
+    ///
+    /// `bytes` is expected to be the payload of an IP packet. This constructor rejects segments
+    /// whose Data Offset doesn't fit in `bytes` and segments whose options are malformed (an
+    /// option, other than End of Option List and No-Operation, with a Length smaller than 2 or
+    /// that extends past the end of the header)
+    pub fn parse(bytes: B) -> Result<Self, B> {
+        let nbytes = bytes.as_slice().len();
+        if nbytes < usize(HEADER_SIZE) || nbytes > usize(u16::max_value()) {
+            return Err(bytes);
+        }
+
+        let segment = Segment { buffer: bytes };
+        let header_len = usize(segment.get_data_offset()) * 4;
+
+        if header_len < usize(HEADER_SIZE) || header_len > nbytes || !segment.check_options() {
+            Err(segment.buffer)
+        } else {
+            Ok(segment)
+        }
+    }
+
+    /* Getters */
+    /// Returns the Source (port) field of the header
+    pub fn get_source(&self) -> u16 {
+        NE::read_u16(&self.header_()[SOURCE])
+    }
+
+    /// Returns the Destination (port) field of the header
+    pub fn get_destination(&self) -> u16 {
+        NE::read_u16(&self.header_()[DESTINATION])
+    }
+
+    /// Returns the Sequence Number field of the header
+    pub fn get_sequence_number(&self) -> u32 {
+        NE::read_u32(&self.header_()[SEQUENCE_NUMBER])
+    }
+
+    /// Returns the Acknowledgment Number field of the header
+    pub fn get_acknowledgment_number(&self) -> u32 {
+        NE::read_u32(&self.header_()[ACKNOWLEDGMENT_NUMBER])
+    }
+
+    /// Returns the Data Offset field of the header
+    ///
+    /// This is the size of the header, options included, in 32-bit words
+    pub fn get_data_offset(&self) -> u8 {
+        get!(
+            NE::read_u16(&self.header_()[DATA_OFFSET_FLAGS]),
+            data_offset
+        ) as u8
+    }
+
+    /// Returns the control bits (`FIN`, `SYN`, etc.) of the header
+    pub fn get_flags(&self) -> u16 {
+        NE::read_u16(&self.header_()[DATA_OFFSET_FLAGS]) & FLAGS_MASK
+    }
+
+    /// Returns the SYN flag
+    pub fn get_syn(&self) -> bool {
+        self.get_flags() & SYN != 0
+    }
+
+    /// Returns the ACK flag
+    pub fn get_ack(&self) -> bool {
+        self.get_flags() & ACK != 0
+    }
+
+    /// Returns the FIN flag
+    pub fn get_fin(&self) -> bool {
+        self.get_flags() & FIN != 0
+    }
+
+    /// Returns the RST flag
+    pub fn get_rst(&self) -> bool {
+        self.get_flags() & RST != 0
+    }
+
+    /// Returns the Window field of the header
+    pub fn get_window(&self) -> u16 {
+        NE::read_u16(&self.header_()[WINDOW])
+    }
+
+    fn get_checksum(&self) -> u16 {
+        NE::read_u16(&self.header_()[CHECKSUM])
+    }
+
+    /// Returns the Urgent Pointer field of the header
+    pub fn get_urgent_pointer(&self) -> u16 {
+        NE::read_u16(&self.header_()[URGENT_POINTER])
+    }
+
+    /// Returns the length (header + data) of this segment
+    pub fn len(&self) -> u16 {
+        // NOTE(cast) `parse` ensures that the buffer length fits in a `u16`
+        self.as_slice().len() as u16
+    }
+
+    /* Miscellaneous */
+    /// Raw view into the Options field, padding included
+    pub fn options(&self) -> &[u8] {
+        let end = self.header_len();
+        unsafe { self.as_slice().r(OPTIONS.start..end) }
+    }
+
+    /// Returns an iterator over the options
+    ///
+    /// No-Operation options are skipped; the iteration stops at the End of Option List option
+    pub fn options_iter(&self) -> Options<'_> {
+        Options {
+            opts: self.options(),
+        }
+    }
+
+    /// View into the payload
+    pub fn payload(&self) -> &[u8] {
+        let start = self.header_len();
+        unsafe { self.as_slice().rf(start..) }
+    }
+
+    /// Returns the byte representation of this TCP segment
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    /// Verifies the 'Checksum' field using the IPv4 pseudo-header
+    pub fn verify_ipv4_checksum(&self, src: ipv4::Addr, dest: ipv4::Addr) -> bool {
+        let state = checksum::State::ipv4_pseudo_header(src, dest, ipv4::Protocol::Tcp, self.len());
+        self.compute_checksum(state) == self.get_checksum()
+    }
+
+    /// Verifies the 'Checksum' field using the IPv6 pseudo-header
+    pub fn verify_ipv6_checksum(&self, src: ipv6::Addr, dest: ipv6::Addr) -> bool {
+        let state =
+            checksum::State::ipv6_pseudo_header(src, dest, ipv6::NextHeader::Tcp, u32(self.len()));
+        self.compute_checksum(state) == self.get_checksum()
+    }
+
+    /* Private */
+    fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    fn header_(&self) -> &[u8; HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
+
+        unsafe { &*(self.as_slice().as_ptr() as *const _) }
+    }
+
+    fn header_len(&self) -> usize {
+        usize(self.get_data_offset()) * 4
+    }
+
+    // `state` contains the pseudo-header
+    fn compute_checksum(&self, mut state: checksum::State) -> u16 {
+        let bytes = self.as_slice();
+
+        // skip the checksum field
+        state.push(&bytes[..CHECKSUM.start]);
+        state.push(&bytes[CHECKSUM.end..]);
+        state.finish()
+    }
+
+    // NOTE `header_len` must have been checked against the length of the buffer
+    fn check_options(&self) -> bool {
+        let mut opts = &self.as_slice()[OPTIONS.start..self.header_len()];
+
+        while let Some(kind) = opts.get(OPTION_KIND).cloned() {
+            match OptionKind::from(kind) {
+                OptionKind::EndOfOptionList => break,
+                OptionKind::NoOperation => opts = &opts[1..],
+                _ => {
+                    let len = match opts.get(OPTION_LENGTH) {
+                        Some(len) => usize(*len),
+                        None => return false,
+                    };
+
+                    if len < OPTION_HEADER_SIZE || len > opts.len() {
+                        return false;
+                    }
+
+                    opts = &opts[len..];
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// NOTE excludes the payload
+impl<B> fmt::Debug for Segment<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("tcp::Segment")
+            .field("source", &self.get_source())
+            .field("destination", &self.get_destination())
+            .field("sequence_number", &self.get_sequence_number())
+            .field("acknowledgment_number", &self.get_acknowledgment_number())
+            .field("data_offset", &self.get_data_offset())
+            .field("flags", &Hex(self.get_flags()))
+            .field("window", &self.get_window())
+            .field("checksum", &Hex(self.get_checksum()))
+            .field("urgent_pointer", &self.get_urgent_pointer())
+            .finish()
+    }
+}
+
+/// Iterator over the options of a TCP segment
+pub struct Options<'a> {
+    opts: &'a [u8],
+}
+
+impl<'a> Iterator for Options<'a> {
+    type Item = TcpOption<'a>;
+
+    fn next(&mut self) -> Option<TcpOption<'a>> {
+        loop {
+            let kind = OptionKind::from(*self.opts.get(OPTION_KIND)?);
+
+            match kind {
+                OptionKind::EndOfOptionList => {
+                    self.opts = &[];
+                    return None;
+                }
+
+                OptionKind::NoOperation => self.opts = unsafe { self.opts.rf(1..) },
+
+                _ => unsafe {
+                    // NOTE `Segment::parse` validated the option lengths
+                    let end = usize(*self.opts.gu(OPTION_LENGTH));
+                    let contents = self.opts.r(OPTION_HEADER_SIZE..end);
+
+                    self.opts = self.opts.rf(end..);
+
+                    return Some(TcpOption { kind, contents });
+                },
+            }
+        }
+    }
+}
+
+/// A TCP option
+pub struct TcpOption<'a> {
+    kind: OptionKind,
+    contents: &'a [u8],
+}
+
+impl<'a> TcpOption<'a> {
+    /// Returns the option kind
+    pub fn get_kind(&self) -> OptionKind {
+        self.kind
+    }
+
+    /// Returns the contents of the option, without the Kind and Length fields
+    pub fn contents(&self) -> &'a [u8] {
+        self.contents
+    }
+
+    /// Returns the Maximum Segment Size, if this is a well formed MSS option
+    pub fn mss(&self) -> Option<u16> {
+        if self.kind == OptionKind::MaximumSegmentSize && self.contents.len() == 2 {
+            Some(NE::read_u16(self.contents))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the shift count, if this is a well formed Window Scale option
+    pub fn window_scale(&self) -> Option<u8> {
+        if self.kind == OptionKind::WindowScale && self.contents.len() == 1 {
+            Some(self.contents[0])
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> fmt::Debug for TcpOption<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("tcp::Option")
+            .field("kind", &self.kind)
+            .field("contents", &self.contents)
+            .finish()
+    }
+}
+
+full_range!(
+    u8,
+    /// TCP option kind
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum OptionKind {
+        /// End of Option List
+        EndOfOptionList = 0,
+        /// No-Operation
+        NoOperation = 1,
+        /// Maximum Segment Size
+        MaximumSegmentSize = 2,
+        /// Window Scale (see RFC 7323)
+        WindowScale = 3,
+        /// SACK Permitted (see RFC 2018)
+        SackPermitted = 4,
+        /// SACK (see RFC 2018)
+        Sack = 5,
+        /// Timestamps (see RFC 7323)
+        Timestamps = 8,
+        /// MD5 Signature (see RFC 2385)
+        Md5Signature = 19,
+        /// TCP Authentication Option (see RFC 5925)
+        AuthenticationOption = 29,
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::{ether, ipv4, tcp};
+
+    #[rustfmt::skip]
+    const SYN: &[u8] = &[
+        0x20, 0x18, 0x03, 0x01, 0x00, 0x01, // ether: destination
+        0x20, 0x18, 0x03, 0x01, 0x00, 0x00, // ether: source
+        0x08, 0x00, // ether: type
+        0x45, 0x00, 0x00, 0x48, // ipv4: version, IHL, DSCP, ECN, total length
+        0x00, 0x00, 0x40, 0x00, // ipv4: identification, flags, fragment offset
+        0x40, 0x06, 0xb7, 0x3d, // ipv4: TTL, protocol, checksum
+        192, 168, 1, 33, // ipv4: source
+        192, 168, 1, 1, // ipv4: destination
+        0x04, 0xd2, 0x00, 0xb3, // tcp: source, destination
+        0x12, 0x34, 0x56, 0x78, // tcp: sequence number
+        0x00, 0x00, 0x00, 0x00, // tcp: acknowledgment number
+        0xd0, 0x02, 0xff, 0xff, // tcp: data offset, flags, window
+        0x63, 0x06, 0x00, 0x00, // tcp: checksum, urgent pointer
+        0x02, 0x04, 0x05, 0xb4, // tcp: MSS = 1460
+        0x01, // tcp: NOP
+        0x03, 0x03, 0x07, // tcp: window scale = 7
+        0x13, 0x12, // tcp: MD5 signature
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77,
+        0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // tcp: end of option list + padding
+    ];
+
+    const IP_SRC: ipv4::Addr = ipv4::Addr([192, 168, 1, 33]);
+    const IP_DEST: ipv4::Addr = ipv4::Addr([192, 168, 1, 1]);
+
+    #[test]
+    fn parse() {
+        let eth = ether::Frame::parse(SYN).unwrap();
+        let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+        assert_eq!(ip.get_protocol(), ipv4::Protocol::Tcp);
+
+        let tcp = tcp::Segment::parse(ip.payload()).unwrap();
+        assert_eq!(tcp.get_source(), 1234);
+        assert_eq!(tcp.get_destination(), 179);
+        assert_eq!(tcp.get_sequence_number(), 0x1234_5678);
+        assert_eq!(tcp.get_data_offset(), 13);
+        assert_eq!(tcp.get_flags(), tcp::SYN);
+        assert!(tcp.get_syn() && !tcp.get_ack());
+        assert_eq!(tcp.get_window(), 0xffff);
+        assert_eq!(tcp.options().len(), 32);
+        assert_eq!(tcp.payload(), &[][..]);
+        assert!(tcp.verify_ipv4_checksum(IP_SRC, IP_DEST));
+
+        let mut opts = tcp.options_iter();
+
+        let mss = opts.next().unwrap();
+        assert_eq!(mss.mss(), Some(1460));
+
+        let wscale = opts.next().unwrap();
+        assert_eq!(wscale.window_scale(), Some(7));
+        assert_eq!(wscale.mss(), None);
+
+        // the signature is skipped as a whole
+        let md5 = opts.next().unwrap();
+        assert_eq!(md5.get_kind(), tcp::OptionKind::Md5Signature);
+        assert_eq!(md5.contents().len(), 16);
+
+        assert!(opts.next().is_none());
+    }
+
+    #[test]
+    fn malformed_options() {
+        let mut bytes = [0; 24];
+        bytes[12] = 6 << 4; // data offset
+
+        // option length of zero would loop forever
+        bytes[20..24].copy_from_slice(&[0x1d, 0x00, 0x00, 0x00]);
+        assert!(tcp::Segment::parse(&bytes[..]).is_err());
+
+        // option extends past the header
+        bytes[20..24].copy_from_slice(&[0x1d, 0x05, 0x00, 0x00]);
+        assert!(tcp::Segment::parse(&bytes[..]).is_err());
+
+        // unknown but well formed options are fine
+        bytes[20..24].copy_from_slice(&[0xfe, 0x04, 0xab, 0xcd]);
+        let tcp = tcp::Segment::parse(&bytes[..]).unwrap();
+        let opt = tcp.options_iter().next().unwrap();
+        assert_eq!(opt.get_kind(), tcp::OptionKind::Unknown(0xfe));
+        assert_eq!(opt.contents(), &[0xab, 0xcd][..]);
+
+        // data offset past the end of the buffer
+        bytes[12] = 7 << 4;
+        assert!(tcp::Segment::parse(&bytes[..]).is_err());
+
+        // data offset smaller than the fixed header
+        bytes[12] = 4 << 4;
+        assert!(tcp::Segment::parse(&bytes[..]).is_err());
+    }
+}
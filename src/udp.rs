@@ -9,8 +9,9 @@ use cast::{u16, usize};
 use owning_slice::Truncate;
 
 use crate::{
+    checksum,
     coap::{self, Unset},
-    ipv6,
+    ipv4, ipv6,
     traits::UncheckedIndex,
 };
 
@@ -24,6 +25,23 @@ const PAYLOAD: RangeFrom<usize> = 8..;
 /// Size of the UDP header
 pub const HEADER_SIZE: u8 = PAYLOAD.start as u8;
 
+/// Returns the maximum payload size of an UDP packet given the maximum payload size of the IP
+/// packet that contains it
+///
+/// # Example
+///
+/// ```
+/// use jnet::{ether, ipv4, udp};
+///
+/// const MAX_PAYLOAD: u16 = udp::max_payload(ipv4::max_payload(ether::MTU));
+///
+/// let buffer = [0u8; MAX_PAYLOAD as usize];
+/// assert_eq!(buffer.len(), 1472);
+/// ```
+pub const fn max_payload(ip_payload: u16) -> u16 {
+    ip_payload - HEADER_SIZE as u16
+}
+
 /// UDP packet
 pub struct Packet<BUFFER>
 where
@@ -92,42 +110,52 @@ where
 
     /* Miscellaneous */
     pub(crate) fn compute_checksum(&self, src: ipv6::Addr, dest: ipv6::Addr) -> u16 {
-        const NEXT_HEADER: u8 = 17;
+        // XXX should this be just `as u16`?
+        let len = self.as_slice().len() as u32;
 
-        let mut sum: u32 = 0;
+        self.finish_checksum(checksum::State::ipv6_pseudo_header(
+            src,
+            dest,
+            ipv6::NextHeader::Udp,
+            len,
+        ))
+    }
 
-        // Pseudo-header
-        for chunk in src.0.chunks_exact(2).chain(dest.0.chunks_exact(2)) {
-            sum += u32::from(NE::read_u16(chunk));
-        }
+    fn compute_ipv4_checksum(&self, src: ipv4::Addr, dest: ipv4::Addr) -> u16 {
+        // NOTE(cast) the packet fits in an IPv4 packet
+        let len = self.as_slice().len() as u16;
 
-        // XXX should this be just `as u16`?
-        let len = self.as_slice().len() as u32;
-        sum += len >> 16;
-        sum += len & 0xffff;
-
-        sum += u32::from(NEXT_HEADER);
-
-        // UDP message
-        for (i, chunk) in self.as_slice().chunks(2).enumerate() {
-            if i == 3 {
-                // this is the checksum field, skip
-                continue;
-            }
-
-            if chunk.len() == 1 {
-                sum += u32::from(chunk[0]) << 8;
-            } else {
-                sum += u32::from(NE::read_u16(chunk));
-            }
-        }
+        self.finish_checksum(checksum::State::ipv4_pseudo_header(
+            src,
+            dest,
+            ipv4::Protocol::Udp,
+            len,
+        ))
+    }
+
+    fn finish_checksum(&self, mut state: checksum::State) -> u16 {
+        let bytes = self.as_slice();
 
-        // fold carry-over
-        while sum >> 16 != 0 {
-            sum = (sum & 0xffff) + (sum >> 16);
+        // UDP message; skip the checksum field
+        state.push(&bytes[..CHECKSUM.start]);
+        state.push(&bytes[CHECKSUM.end..]);
+
+        // "If the computed checksum is zero, it is transmitted as all ones" (RFC 768); in IPv6 a
+        // zero checksum is not allowed (Section 8.1 of RFC 8200)
+        match state.finish() {
+            0 => 0xffff,
+            cksum => cksum,
         }
+    }
+
+    /// Verifies the 'Checksum' field using the IPv4 pseudo-header
+    ///
+    /// NOTE a zero 'Checksum' field means that the sender didn't compute the checksum; this method
+    /// returns `true` in that case
+    pub fn verify_ipv4_checksum(&self, src: ipv4::Addr, dest: ipv4::Addr) -> bool {
+        let checksum = self.get_checksum();
 
-        !(sum as u16)
+        checksum == 0 || self.compute_ipv4_checksum(src, dest) == checksum
     }
 
     /// Verifies the 'Checksum' field
@@ -186,6 +214,12 @@ where
         &mut self.as_mut_slice()[PAYLOAD]
     }
 
+    /// Recomputes and updates the 'Checksum' field using the IPv4 pseudo-header
+    pub fn update_ipv4_checksum(&mut self, src: ipv4::Addr, dest: ipv4::Addr) {
+        let cksum = self.compute_ipv4_checksum(src, dest);
+        self.set_checksum(cksum)
+    }
+
     /// Recomputes and updates the 'Checksum' field
     pub fn update_ipv6_checksum(&mut self, src: ipv6::Addr, dest: ipv6::Addr) {
         let cksum = self.compute_checksum(src, dest);
@@ -252,6 +286,16 @@ where
         self.truncate(len);
     }
 
+    /// Returns a writer that fills the payload in place
+    ///
+    /// The packet is truncated to fit the written data when `Writer::finish` is called
+    pub fn writer(&mut self) -> Writer<'_, B> {
+        Writer {
+            packet: self,
+            pos: 0,
+        }
+    }
+
     /// Truncates the *payload* to the specified length
     pub fn truncate(&mut self, len: u16) {
         if len < self.payload_len() {
@@ -262,6 +306,88 @@ where
     }
 }
 
+/// Writer that fills the payload of a UDP packet in place
+///
+/// This implements `core::fmt::Write` so it can be used with the `write!` macro. Serializers that
+/// work on byte slices can use `unfilled` and `advance`.
+pub struct Writer<'a, B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    packet: &'a mut Packet<B>,
+    pos: u16,
+}
+
+impl<'a, B> Writer<'a, B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /// Appends `bytes` to the payload
+    ///
+    /// Returns `Err` (and writes nothing) if `bytes` doesn't fit in the remaining space
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), ()> {
+        let len = bytes.len();
+        let unfilled = self.unfilled();
+
+        if unfilled.len() < len {
+            return Err(());
+        }
+
+        unfilled[..len].copy_from_slice(bytes);
+        // NOTE(cast) `len` is smaller than the payload, which fits in a `u16`
+        self.pos += len as u16;
+        Ok(())
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn len(&self) -> u16 {
+        self.pos
+    }
+
+    /// Returns the number of bytes that can still be written
+    pub fn remaining(&self) -> u16 {
+        self.packet.payload_len() - self.pos
+    }
+
+    /// Mutable view into the part of the payload that has not been written yet
+    ///
+    /// NOTE the payload ends where the 'Length' field says; any padding past it is not writable
+    pub fn unfilled(&mut self) -> &mut [u8] {
+        let start = usize(self.pos);
+        let end = usize(self.packet.payload_len());
+        &mut self.packet.payload_mut()[start..end]
+    }
+
+    /// Marks the first `n` bytes of `unfilled` as written
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `n` is greater than `remaining`
+    pub fn advance(&mut self, n: u16) {
+        assert!(n <= self.remaining());
+
+        self.pos += n;
+    }
+
+    /// Truncates the packet to fit the written data and updates the 'Length' field
+    ///
+    /// NOTE the checksum is *not* updated. The IPv6 `udp` builder computes it after the closure
+    /// returns. The IPv4 `udp` builder leaves it zeroed, which means "no checksum" in IPv4; call
+    /// `update_ipv4_checksum` after `finish` to send one
+    pub fn finish(self) {
+        self.packet.truncate(self.pos);
+    }
+}
+
+impl<'a, B> fmt::Write for Writer<'a, B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
 /// NOTE excludes the payload
 impl<B> fmt::Debug for Packet<B>
 where
@@ -283,7 +409,7 @@ mod tests {
     use cast::u16;
     use rand::{self, RngCore};
 
-    use crate::{ether, ipv4, mac, udp};
+    use crate::{ether, ipv4, ipv6, mac, udp};
 
     const SIZE: usize = 56;
 
@@ -309,7 +435,7 @@ mod tests {
     ];
 
     const MAC_SRC: mac::Addr = mac::Addr([0x01; 6]);
-    const MAC_DST: mac::Addr = mac::Addr([0xff; 6]);
+    const MAC_DST: mac::Addr = mac::Addr::BROADCAST;
 
     const IP_SRC: ipv4::Addr = ipv4::Addr([192, 168, 0, 33]);
     const IP_DST: ipv4::Addr = ipv4::Addr([192, 168, 0, 1]);
@@ -375,4 +501,112 @@ mod tests {
         );
         assert_eq!(udp.payload(), MESSAGE);
     }
+
+    #[test]
+    fn writer() {
+        use core::fmt::Write;
+
+        let mut array: [u8; SIZE] = [0; SIZE];
+
+        let mut eth = ether::Frame::new(&mut array[..]);
+
+        eth.set_destination(MAC_DST);
+        eth.set_source(MAC_SRC);
+
+        eth.ipv4(|ip| {
+            ip.set_destination(IP_DST);
+            ip.set_source(IP_SRC);
+
+            ip.udp(|udp| {
+                udp.set_source(0);
+                udp.set_destination(UDP_DST);
+
+                let mut w = udp.writer();
+                w.write(b"Hello").unwrap();
+                write!(w, ", {}!", "world").unwrap();
+                w.unfilled()[0] = b'\n';
+                w.advance(1);
+                assert_eq!(w.len(), MESSAGE.len() as u16);
+                assert_eq!(w.remaining(), 0);
+                assert!(w.write(b"?").is_err());
+                w.finish();
+            });
+        });
+
+        assert_eq!(eth.as_bytes(), &BYTES[..]);
+    }
+
+    #[test]
+    fn writer_padded() {
+        // 'Length' covers 4 bytes of payload; the buffer has 4 more bytes of padding
+        let mut bytes = [0, 1, 0, 2, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut udp = udp::Packet::parse(&mut bytes[..]).unwrap();
+
+        let mut w = udp.writer();
+        assert_eq!(w.remaining(), 4);
+        assert_eq!(w.unfilled().len(), 4);
+        assert!(w.write(b"Hello").is_err());
+        w.write(b"Hel").unwrap();
+        assert_eq!(w.remaining(), 1);
+        assert_eq!(w.unfilled().len(), 1);
+        w.advance(1);
+        assert_eq!(w.remaining(), 0);
+        assert!(w.write(b"l").is_err());
+        w.finish();
+
+        assert_eq!(udp.len(), 12);
+        assert_eq!(&udp.as_bytes()[8..], b"Hel\0\0\0\0\0");
+    }
+
+    #[test]
+    fn checksum() {
+        use core::fmt::Write;
+
+        // IPv4: only computed on request
+        let mut array: [u8; SIZE] = [0; SIZE];
+        let mut eth = ether::Frame::new(&mut array[..]);
+        eth.ipv4(|ip| {
+            ip.set_destination(IP_DST);
+            ip.set_source(IP_SRC);
+
+            ip.udp(|udp| {
+                udp.set_source(0);
+                udp.set_destination(UDP_DST);
+
+                let mut w = udp.writer();
+                write!(w, "Hello, world!\n").unwrap();
+                w.finish();
+
+                assert_eq!(udp.get_checksum(), 0);
+                udp.update_ipv4_checksum(IP_SRC, IP_DST);
+            });
+        });
+
+        let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+        let udp = udp::Packet::parse(ip.payload()).unwrap();
+        assert_eq!(&udp.as_bytes()[6..8], &[55, 192]);
+        assert!(udp.verify_ipv4_checksum(IP_SRC, IP_DST));
+        assert!(!udp.verify_ipv4_checksum(IP_SRC, ipv4::Addr::BROADCAST));
+
+        // IPv6: computed by the builder
+        let src = ipv6::Addr([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        let dest = ipv6::Addr::ALL_NODES;
+
+        let mut array = [0; 128];
+        let mut ip = ipv6::Packet::new(&mut array[..]);
+        ip.set_source(src);
+        ip.set_destination(dest);
+        ip.udp(|udp| {
+            udp.set_source(0);
+            udp.set_destination(UDP_DST);
+
+            let mut w = udp.writer();
+            write!(w, "Hello, world!\n").unwrap();
+            w.finish();
+        });
+
+        let udp = udp::Packet::parse(ip.payload()).unwrap();
+        assert_eq!(udp.payload(), MESSAGE);
+        assert!(udp.verify_ipv6_checksum(src, dest));
+    }
 }
@@ -0,0 +1,420 @@
+//! UDP-Lite: Lightweight User Datagram Protocol
+//!
+//! # References
+//!
+//! - [RFC 3828: The Lightweight User Datagram Protocol (UDP-Lite)][rfc]
+//!
+//! [rfc]: https://tools.ietf.org/html/rfc3828
+
+use core::ops::{Range, RangeFrom};
+use core::{fmt, u16};
+
+use as_slice::{AsMutSlice, AsSlice};
+use byteorder::{ByteOrder, NetworkEndian as NE};
+use cast::{u16, u32, usize};
+use owning_slice::Truncate;
+
+use crate::{checksum, fmt::Hex, ipv4, ipv6, traits::UncheckedIndex};
+
+/* Packet structure */
+const SOURCE: Range<usize> = 0..2;
+const DESTINATION: Range<usize> = 2..4;
+const CHECKSUM_COVERAGE: Range<usize> = 4..6;
+const CHECKSUM: Range<usize> = 6..8;
+const PAYLOAD: RangeFrom<usize> = 8..;
+
+/// Size of the UDP-Lite header
+pub const HEADER_SIZE: u8 = PAYLOAD.start as u8;
+
+/// Returns the maximum payload size of an UDP-Lite packet given the maximum payload size of the IP
+/// packet that contains it
+pub const fn max_payload(ip_payload: u16) -> u16 {
+    ip_payload - HEADER_SIZE as u16
+}
+
+/// UDP-Lite packet
+///
+/// Unlike UDP, UDP-Lite has no Length field; the length of the packet is derived from the length
+/// of the IP payload. The Checksum Coverage field indicates how many bytes, starting from the
+/// first byte of the header, are covered by the checksum. A value of `0` means that the whole
+/// packet is covered.
+pub struct Packet<BUFFER>
+where
+    BUFFER: AsSlice<Element = u8>,
+{
+    buffer: BUFFER,
+}
+
+impl<B> Packet<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Parses the bytes as an UDP-Lite packet
+    ///
+    /// `bytes` is expected to be the payload of an IP packet. This constructor rejects packets
+    /// whose Checksum Coverage field is neither `0` nor in the range `8..=bytes.len()`, as
+    /// required by Section 3.1 of RFC 3828.
+    pub fn parse(bytes: B) -> Result<Self, B> {
+        let nbytes = bytes.as_slice().len();
+        if nbytes < usize(HEADER_SIZE) || nbytes > usize(u16::MAX) {
+            return Err(bytes);
+        }
+
+        let packet = Packet { buffer: bytes };
+        let coverage = usize(packet.get_checksum_coverage());
+
+        if coverage != 0 && (coverage < usize(HEADER_SIZE) || coverage > nbytes) {
+            Err(packet.buffer)
+        } else {
+            Ok(packet)
+        }
+    }
+
+    /* Getters */
+    /// Returns the Source (port) field of the header
+    pub fn get_source(&self) -> u16 {
+        NE::read_u16(&self.header_()[SOURCE])
+    }
+
+    /// Returns the Destination (port) field of the header
+    pub fn get_destination(&self) -> u16 {
+        NE::read_u16(&self.header_()[DESTINATION])
+    }
+
+    /// Returns the Checksum Coverage field of the header
+    pub fn get_checksum_coverage(&self) -> u16 {
+        NE::read_u16(&self.header_()[CHECKSUM_COVERAGE])
+    }
+
+    fn get_checksum(&self) -> u16 {
+        NE::read_u16(&self.header_()[CHECKSUM])
+    }
+
+    /// Returns the length (header + data) of this packet
+    pub fn len(&self) -> u16 {
+        // NOTE(cast) `parse` and `new` ensure that the buffer length fits in a `u16`
+        self.as_slice().len() as u16
+    }
+
+    /* Miscellaneous */
+    /// View into the payload
+    pub fn payload(&self) -> &[u8] {
+        unsafe { self.as_slice().rf(PAYLOAD) }
+    }
+
+    /// View into the part of the packet (header included) that's covered by the checksum
+    pub fn covered(&self) -> &[u8] {
+        let end = self.coverage();
+        unsafe { self.as_slice().rt(..end) }
+    }
+
+    /// Returns the byte representation of this UDP-Lite packet
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    /// Verifies the 'Checksum' field using the IPv4 pseudo-header
+    pub fn verify_ipv4_checksum(&self, src: ipv4::Addr, dest: ipv4::Addr) -> bool {
+        self.compute_ipv4_checksum(src, dest) == self.get_checksum()
+    }
+
+    /// Verifies the 'Checksum' field using the IPv6 pseudo-header
+    pub fn verify_ipv6_checksum(&self, src: ipv6::Addr, dest: ipv6::Addr) -> bool {
+        self.compute_ipv6_checksum(src, dest) == self.get_checksum()
+    }
+
+    /* Private */
+    fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    fn header_(&self) -> &[u8; HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
+
+        unsafe { &*(self.as_slice().as_ptr() as *const _) }
+    }
+
+    fn payload_len(&self) -> u16 {
+        self.len() - u16(HEADER_SIZE)
+    }
+
+    // Number of bytes covered by the checksum
+    fn coverage(&self) -> usize {
+        let coverage = usize(self.get_checksum_coverage());
+
+        if coverage == 0 {
+            self.as_slice().len()
+        } else {
+            coverage
+        }
+    }
+
+    fn compute_ipv4_checksum(&self, src: ipv4::Addr, dest: ipv4::Addr) -> u16 {
+        self.compute_checksum(checksum::State::ipv4_pseudo_header(
+            src,
+            dest,
+            ipv4::Protocol::UdpLite,
+            self.len(),
+        ))
+    }
+
+    fn compute_ipv6_checksum(&self, src: ipv6::Addr, dest: ipv6::Addr) -> u16 {
+        self.compute_checksum(checksum::State::ipv6_pseudo_header(
+            src,
+            dest,
+            ipv6::NextHeader::UdpLite,
+            u32(self.len()),
+        ))
+    }
+
+    // `state` contains the pseudo-header
+    fn compute_checksum(&self, mut state: checksum::State) -> u16 {
+        // UDP-Lite message; only the covered part, skipping the checksum field
+        let covered = self.covered();
+        state.push(&covered[..CHECKSUM.start]);
+        state.push(&covered[CHECKSUM.end..]);
+
+        // Section 3.1 "If the computed checksum is 0, it is transmitted as all ones"
+        match state.finish() {
+            0 => 0xffff,
+            cksum => cksum,
+        }
+    }
+}
+
+impl<B> Packet<B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8>,
+{
+    /* Setters */
+    /// Sets the Source (port) field of the header
+    pub fn set_source(&mut self, port: u16) {
+        NE::write_u16(&mut self.header_mut_()[SOURCE], port)
+    }
+
+    /// Sets the Destination (port) field of the header
+    pub fn set_destination(&mut self, port: u16) {
+        NE::write_u16(&mut self.header_mut_()[DESTINATION], port)
+    }
+
+    /// Sets the Checksum Coverage field of the header
+    ///
+    /// A value of `0` means that the whole packet is covered by the checksum
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `coverage` is not `0` and it's either smaller than `HEADER_SIZE` or
+    /// larger than the length of the packet
+    pub fn set_checksum_coverage(&mut self, coverage: u16) {
+        assert!(coverage == 0 || (coverage >= u16(HEADER_SIZE) && coverage <= self.len()));
+
+        unsafe { self.set_checksum_coverage_unchecked(coverage) }
+    }
+
+    fn set_checksum(&mut self, checksum: u16) {
+        NE::write_u16(&mut self.header_mut_()[CHECKSUM], checksum)
+    }
+
+    // NOTE(unsafe) this doesn't check that `coverage` is a valid value
+    unsafe fn set_checksum_coverage_unchecked(&mut self, coverage: u16) {
+        NE::write_u16(&mut self.header_mut_()[CHECKSUM_COVERAGE], coverage)
+    }
+
+    /* Miscellaneous */
+    /// Mutable view into the payload
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        &mut self.as_mut_slice()[PAYLOAD]
+    }
+
+    /// Recomputes and updates the 'Checksum' field using the IPv4 pseudo-header
+    pub fn update_ipv4_checksum(&mut self, src: ipv4::Addr, dest: ipv4::Addr) {
+        let cksum = self.compute_ipv4_checksum(src, dest);
+        self.set_checksum(cksum)
+    }
+
+    /// Recomputes and updates the 'Checksum' field using the IPv6 pseudo-header
+    pub fn update_ipv6_checksum(&mut self, src: ipv6::Addr, dest: ipv6::Addr) {
+        let cksum = self.compute_ipv6_checksum(src, dest);
+        self.set_checksum(cksum)
+    }
+
+    /* Private */
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.buffer.as_mut_slice()
+    }
+
+    fn header_mut_(&mut self) -> &mut [u8; HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
+
+        unsafe { &mut *(self.as_mut_slice().as_mut_ptr() as *mut _) }
+    }
+}
+
+impl<B> Packet<B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /* Constructors */
+    /// Transforms the given buffer into an UDP-Lite packet
+    ///
+    /// NOTE The UDP-Lite packet will span the whole buffer, the Checksum Coverage field will be set
+    /// to `0` (full coverage) and the Checksum field will be zeroed.
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the given `buffer` is not large enough to contain the UDP-Lite
+    /// header.
+    pub fn new(mut buffer: B) -> Self {
+        assert!(buffer.as_slice().len() >= usize(HEADER_SIZE));
+
+        let len = u16(buffer.as_slice().len()).unwrap_or(u16::MAX);
+        buffer.truncate(len);
+        let mut packet = Packet { buffer };
+
+        packet.set_checksum(0);
+        unsafe { packet.set_checksum_coverage_unchecked(0) }
+
+        packet
+    }
+
+    /* Setters */
+    /// Fills the payload with the given data and adjusts the length of the UDP-Lite packet
+    pub fn set_payload(&mut self, data: &[u8]) {
+        let len = u16(data.len()).unwrap();
+        assert!(self.payload_len() >= len);
+
+        self.truncate(len);
+        self.payload_mut().copy_from_slice(data);
+    }
+
+    /* Miscellaneous */
+    /// Truncates the *payload* to the specified length
+    ///
+    /// If the Checksum Coverage field exceeds the new length of the packet it will be clamped to
+    /// the new length
+    pub fn truncate(&mut self, len: u16) {
+        if len < self.payload_len() {
+            let total_len = len + u16(HEADER_SIZE);
+            self.buffer.truncate(total_len);
+
+            if self.get_checksum_coverage() > total_len {
+                unsafe { self.set_checksum_coverage_unchecked(total_len) }
+            }
+        }
+    }
+}
+
+/// NOTE excludes the payload
+impl<B> fmt::Debug for Packet<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("udplite::Packet")
+            .field("source", &self.get_source())
+            .field("destination", &self.get_destination())
+            .field("checksum_coverage", &self.get_checksum_coverage())
+            .field("checksum", &Hex(self.get_checksum()))
+            // .field("payload", &self.payload())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{self, RngCore};
+
+    use crate::{ether, ipv6, mac, udplite};
+
+    const MAC_SRC: mac::Addr = mac::Addr([0x01; 6]);
+    const MAC_DST: mac::Addr = mac::Addr::BROADCAST;
+
+    const IP_SRC: ipv6::Addr = ipv6::Addr([
+        0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0x03, 0x01, 0x01, 0xff, 0xfe, 0x01, 0x01, 0x01,
+    ]);
+    const IP_DST: ipv6::Addr = ipv6::Addr::ALL_NODES;
+
+    const UDP_DST: u16 = 1337;
+
+    const MESSAGE: &[u8] = b"Hello, world!\n";
+
+    #[test]
+    fn roundtrip() {
+        // NOTE start with randomized array to make sure we set *everything* correctly
+        let mut array = [0; 128];
+        rand::thread_rng().fill_bytes(&mut array);
+
+        let mut eth = ether::Frame::new(&mut array[..]);
+
+        eth.set_destination(MAC_DST);
+        eth.set_source(MAC_SRC);
+
+        eth.ipv6(|ip| {
+            ip.set_destination(IP_DST);
+            ip.set_source(IP_SRC);
+
+            ip.udplite(|udp| {
+                udp.set_source(0);
+                udp.set_destination(UDP_DST);
+                udp.set_payload(MESSAGE);
+                // cover the header and the first 4 bytes of the payload
+                udp.set_checksum_coverage(udplite::HEADER_SIZE as u16 + 4);
+            });
+        });
+
+        let mut bytes = [0; 128];
+        let len = eth.as_bytes().len();
+        bytes[..len].copy_from_slice(eth.as_bytes());
+
+        {
+            let eth = ether::Frame::parse(&bytes[..len]).unwrap();
+            let ip = ipv6::Packet::parse(eth.payload()).unwrap();
+            assert_eq!(ip.get_next_header(), ipv6::NextHeader::UdpLite);
+
+            let udp = udplite::Packet::parse(ip.payload()).unwrap();
+            assert_eq!(udp.get_source(), 0);
+            assert_eq!(udp.get_destination(), UDP_DST);
+            assert_eq!(udp.get_checksum_coverage(), 12);
+            assert_eq!(
+                udp.len(),
+                udplite::HEADER_SIZE as u16 + MESSAGE.len() as u16
+            );
+            assert_eq!(udp.payload(), MESSAGE);
+            assert!(udp.verify_ipv6_checksum(IP_SRC, IP_DST));
+        }
+
+        // corrupting a byte that's not covered by the checksum is tolerated
+        bytes[len - 1] ^= 0xff;
+        {
+            let eth = ether::Frame::parse(&bytes[..len]).unwrap();
+            let ip = ipv6::Packet::parse(eth.payload()).unwrap();
+            let udp = udplite::Packet::parse(ip.payload()).unwrap();
+            assert!(udp.verify_ipv6_checksum(IP_SRC, IP_DST));
+        }
+
+        // but corrupting a covered byte is not
+        bytes[len - MESSAGE.len()] ^= 0xff;
+        {
+            let eth = ether::Frame::parse(&bytes[..len]).unwrap();
+            let ip = ipv6::Packet::parse(eth.payload()).unwrap();
+            let udp = udplite::Packet::parse(ip.payload()).unwrap();
+            assert!(!udp.verify_ipv6_checksum(IP_SRC, IP_DST));
+        }
+    }
+
+    #[test]
+    fn invalid_coverage() {
+        // coverage = 4 (smaller than the header)
+        let bytes = [0, 0, 0, 0, 0, 4, 0, 0];
+        assert!(udplite::Packet::parse(&bytes[..]).is_err());
+
+        // coverage = 9 (larger than the packet)
+        let bytes = [0, 0, 0, 0, 0, 9, 0, 0];
+        assert!(udplite::Packet::parse(&bytes[..]).is_err());
+
+        // coverage = 8
+        let bytes = [0, 0, 0, 0, 0, 8, 0, 0];
+        assert!(udplite::Packet::parse(&bytes[..]).is_ok());
+    }
+}
@@ -0,0 +1,757 @@
+//! VRRP: Virtual Router Redundancy Protocol (version 2)
+//!
+//! `Router` implements the master election state machine (Section 6 of RFC 3768). It doesn't own
+//! a clock or send anything: the application passes the current time, in milliseconds, to its
+//! methods and performs the returned `Action`s.
+//!
+//! # References
+//!
+//! - [RFC 3768: Virtual Router Redundancy Protocol (VRRP)][rfc]
+//!
+//! [rfc]: https://tools.ietf.org/html/rfc3768
+
+use core::{fmt, ops::Range, slice::ChunksExact};
+
+use as_slice::{AsMutSlice, AsSlice};
+use byteorder::{ByteOrder, NetworkEndian as NE};
+use cast::usize;
+use owning_slice::Truncate;
+
+use crate::{checksum, ipv4, mac, traits::UncheckedIndex};
+
+/// IPv4 multicast address to which advertisements are sent
+pub const MULTICAST_ADDR: ipv4::Addr = ipv4::Addr([224, 0, 0, 18]);
+
+/// TTL of the IPv4 packets that carry advertisements; packets with a different TTL must be
+/// discarded
+pub const TTL: u8 = 255;
+
+/// Priority of the router that owns the virtual router's IP addresses
+pub const PRIORITY_OWNER: u8 = 255;
+
+/// Default priority of backup routers
+pub const PRIORITY_DEFAULT: u8 = 100;
+
+/// Priority sent by the master to signal that it stopped participating in the virtual router
+pub const PRIORITY_STOP: u8 = 0;
+
+/// Returns the virtual router MAC address of the virtual router `vrid`
+pub fn virtual_mac(vrid: u8) -> mac::Addr {
+    mac::Addr([0x00, 0x00, 0x5e, 0x00, 0x01, vrid])
+}
+
+/* Packet structure */
+const VERSION_TYPE: usize = 0;
+mod type_ {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = 0;
+    pub const SIZE: usize = 4;
+}
+mod version {
+    pub const MASK: u8 = (1 << SIZE) - 1;
+    pub const OFFSET: usize = super::type_::OFFSET + super::type_::SIZE;
+    pub const SIZE: usize = 4;
+}
+const VRID: usize = 1;
+const PRIORITY: usize = 2;
+const COUNT: usize = 3;
+const AUTH_TYPE: usize = 4;
+const ADVER_INT: usize = 5;
+const CHECKSUM: Range<usize> = 6..8;
+const IP_ADDRS: usize = 8;
+
+/// Size of the fixed part of the VRRP header
+pub const HEADER_SIZE: u8 = IP_ADDRS as u8;
+
+/// Size of the 'Authentication Data' field
+pub const AUTH_DATA_SIZE: u8 = 8;
+
+const VERSION: u8 = 2;
+const ADVERTISEMENT: u8 = 1;
+
+/// VRRP advertisement
+pub struct Packet<BUFFER>
+where
+    BUFFER: AsSlice<Element = u8>,
+{
+    buffer: BUFFER,
+}
+
+impl<B> Packet<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    /* Constructors */
+    /// Parses bytes into a VRRP advertisement
+    ///
+    /// This rejects packets whose version is not 2, whose type is not Advertisement and packets
+    /// that are too short to contain all the IP addresses and the authentication data. The
+    /// checksum is *not* verified; use `verify_checksum` for that
+    pub fn parse(bytes: B) -> Result<Self, B> {
+        let slice = bytes.as_slice();
+
+        if slice.len() < usize(HEADER_SIZE) {
+            return Err(bytes);
+        }
+
+        let packet = Packet { buffer: bytes };
+
+        if packet.get_version() != VERSION || packet.get_type() != ADVERTISEMENT {
+            return Err(packet.buffer);
+        }
+
+        if packet.as_slice().len() < packet.size() {
+            Err(packet.buffer)
+        } else {
+            Ok(packet)
+        }
+    }
+
+    /* Getters */
+    /// Reads the 'Version' field
+    pub fn get_version(&self) -> u8 {
+        get!(self.header_()[VERSION_TYPE], version)
+    }
+
+    /// Reads the 'Type' field
+    pub fn get_type(&self) -> u8 {
+        get!(self.header_()[VERSION_TYPE], type_)
+    }
+
+    /// Reads the 'Virtual Rtr ID' field
+    pub fn get_vrid(&self) -> u8 {
+        self.header_()[VRID]
+    }
+
+    /// Reads the 'Priority' field
+    pub fn get_priority(&self) -> u8 {
+        self.header_()[PRIORITY]
+    }
+
+    /// Reads the 'Count IP Addrs' field
+    pub fn get_count(&self) -> u8 {
+        self.header_()[COUNT]
+    }
+
+    /// Reads the 'Auth Type' field
+    pub fn get_auth_type(&self) -> AuthType {
+        self.header_()[AUTH_TYPE].into()
+    }
+
+    /// Reads the 'Advertisement Interval' field, in seconds
+    pub fn get_advertisement_interval(&self) -> u8 {
+        self.header_()[ADVER_INT]
+    }
+
+    /// Returns an iterator over the IP addresses associated with the virtual router
+    pub fn ip_addrs(&self) -> IpAddrs<'_> {
+        IpAddrs {
+            chunks: unsafe { self.as_slice().r(IP_ADDRS..self.auth_data_start()) }.chunks_exact(4),
+        }
+    }
+
+    /// Returns the 'Authentication Data' field
+    pub fn get_auth_data(&self) -> [u8; AUTH_DATA_SIZE as usize] {
+        let mut data = [0; AUTH_DATA_SIZE as usize];
+        let start = self.auth_data_start();
+        data.copy_from_slice(unsafe { self.as_slice().r(start..self.size()) });
+        data
+    }
+
+    /// Returns the length (header + IP addresses + authentication data) of this packet
+    pub fn len(&self) -> u16 {
+        // NOTE(cast) the size can't exceed `8 + 4 * 255 + 8`
+        self.size() as u16
+    }
+
+    /// Returns the byte representation of this packet
+    pub fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+
+    /// Verifies the 'Checksum' field
+    pub fn verify_checksum(&self) -> bool {
+        let mut state = checksum::State::new();
+        state.push(unsafe { self.as_slice().rt(..self.size()) });
+        state.finish() == 0
+    }
+
+    /// Returns the underlying buffer
+    pub fn free(self) -> B {
+        self.buffer
+    }
+
+    /* Private */
+    fn as_slice(&self) -> &[u8] {
+        self.buffer.as_slice()
+    }
+
+    fn header_(&self) -> &[u8; HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
+
+        unsafe { &*(self.as_slice().as_ptr() as *const _) }
+    }
+
+    fn auth_data_start(&self) -> usize {
+        IP_ADDRS + 4 * usize(self.get_count())
+    }
+
+    // size of the advertisement, as implied by the 'Count IP Addrs' field
+    fn size(&self) -> usize {
+        self.auth_data_start() + usize(AUTH_DATA_SIZE)
+    }
+}
+
+impl<B> Packet<B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8> + Truncate<u16>,
+{
+    /* Constructors */
+    /// Transforms the given buffer into an advertisement that carries `count` IP addresses
+    ///
+    /// The advertisement will have the following fields: Version = 2, Type = Advertisement,
+    /// Priority = `PRIORITY_DEFAULT`, Auth Type = `NoAuthentication`, Advertisement Interval = 1.
+    /// The IP addresses and the authentication data will be zeroed. The buffer will be truncated
+    /// to the size of the advertisement
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the given buffer is not large enough to contain the
+    /// advertisement
+    pub fn new(buffer: B, count: u8) -> Self {
+        let size = usize(HEADER_SIZE) + 4 * usize(count) + usize(AUTH_DATA_SIZE);
+        assert!(buffer.as_slice().len() >= size);
+
+        let mut packet = Packet { buffer };
+        // NOTE(cast) `size` can't exceed `8 + 4 * 255 + 8`
+        packet.buffer.truncate(size as u16);
+        packet.header_mut_()[COUNT] = count;
+
+        set!(packet.header_mut_()[VERSION_TYPE], version, VERSION);
+        set!(packet.header_mut_()[VERSION_TYPE], type_, ADVERTISEMENT);
+        packet.set_vrid(0);
+        packet.set_priority(PRIORITY_DEFAULT);
+        packet.set_auth_type(AuthType::NoAuthentication);
+        packet.set_advertisement_interval(1);
+        for byte in &mut packet.as_mut_slice()[CHECKSUM.start..] {
+            *byte = 0;
+        }
+
+        packet
+    }
+}
+
+impl<B> Packet<B>
+where
+    B: AsSlice<Element = u8> + AsMutSlice<Element = u8>,
+{
+    /* Setters */
+    /// Sets the 'Virtual Rtr ID' field
+    pub fn set_vrid(&mut self, vrid: u8) {
+        self.header_mut_()[VRID] = vrid;
+    }
+
+    /// Sets the 'Priority' field
+    pub fn set_priority(&mut self, priority: u8) {
+        self.header_mut_()[PRIORITY] = priority;
+    }
+
+    /// Sets the 'Auth Type' field
+    pub fn set_auth_type(&mut self, auth_type: AuthType) {
+        self.header_mut_()[AUTH_TYPE] = auth_type.into();
+    }
+
+    /// Sets the 'Advertisement Interval' field, in seconds
+    pub fn set_advertisement_interval(&mut self, secs: u8) {
+        self.header_mut_()[ADVER_INT] = secs;
+    }
+
+    /// Sets the `index`-th IP address
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `index` is not less than the 'Count IP Addrs' field
+    pub fn set_ip_addr(&mut self, index: u8, addr: ipv4::Addr) {
+        assert!(index < self.get_count());
+
+        let start = IP_ADDRS + 4 * usize(index);
+        self.as_mut_slice()[start..start + 4].copy_from_slice(&addr.0);
+    }
+
+    /// Sets the 'Authentication Data' field
+    pub fn set_auth_data(&mut self, data: &[u8; AUTH_DATA_SIZE as usize]) {
+        let start = self.auth_data_start();
+        let end = self.size();
+        self.as_mut_slice()[start..end].copy_from_slice(data);
+    }
+
+    /// Recomputes and updates the 'Checksum' field
+    pub fn update_checksum(&mut self) {
+        let end = self.size();
+        let bytes = self.as_slice();
+
+        let mut state = checksum::State::new();
+        state.push(&bytes[..CHECKSUM.start]);
+        state.push(&bytes[CHECKSUM.end..end]);
+        let cksum = state.finish();
+
+        NE::write_u16(&mut self.header_mut_()[CHECKSUM], cksum);
+    }
+
+    /* Private */
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.buffer.as_mut_slice()
+    }
+
+    fn header_mut_(&mut self) -> &mut [u8; HEADER_SIZE as usize] {
+        debug_assert!(self.as_slice().len() >= HEADER_SIZE as usize);
+
+        unsafe { &mut *(self.as_mut_slice().as_mut_ptr() as *mut _) }
+    }
+}
+
+impl<B> fmt::Debug for Packet<B>
+where
+    B: AsSlice<Element = u8>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("vrrp::Packet")
+            .field("vrid", &self.get_vrid())
+            .field("priority", &self.get_priority())
+            .field("count", &self.get_count())
+            .field("auth_type", &self.get_auth_type())
+            .field("advertisement_interval", &self.get_advertisement_interval())
+            .finish()
+    }
+}
+
+/// Iterator over the IP addresses of an advertisement
+pub struct IpAddrs<'a> {
+    chunks: ChunksExact<'a, u8>,
+}
+
+impl<'a> Iterator for IpAddrs<'a> {
+    type Item = ipv4::Addr;
+
+    fn next(&mut self) -> Option<ipv4::Addr> {
+        self.chunks
+            .next()
+            .map(|chunk| ipv4::Addr([chunk[0], chunk[1], chunk[2], chunk[3]]))
+    }
+}
+
+full_range!(
+    u8,
+    /// Authentication type
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum AuthType {
+        /// No Authentication
+        NoAuthentication = 0,
+        /// Simple Text Password (reserved; see Section 5.3.6 of RFC 3768)
+        SimpleText = 1,
+        /// IP Authentication Header (reserved; see Section 5.3.6 of RFC 3768)
+        IpAuthenticationHeader = 2,
+    }
+);
+
+/// State of a virtual router (see Section 6.4 of RFC 3768)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum State {
+    /// Waiting for a `startup` event
+    Initialize,
+    /// Monitoring the availability of the master
+    Backup,
+    /// Forwarding packets sent to the virtual router's addresses
+    Master,
+}
+
+/// What the application must do after feeding an event to a `Router`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    /// Nothing
+    None,
+    /// Send an advertisement with the router's priority
+    Advertise,
+    /// Send an advertisement with the router's priority, broadcast a gratuitous ARP for each IP
+    /// address of the virtual router (using the virtual MAC address) and start accepting packets
+    /// sent to the virtual MAC address
+    BecomeMaster,
+    /// Stop accepting packets sent to the virtual MAC address and stop answering ARP requests for
+    /// the IP addresses of the virtual router
+    BecomeBackup,
+    /// Send an advertisement with priority `PRIORITY_STOP` and stop accepting packets sent to the
+    /// virtual MAC address
+    Resign,
+}
+
+/// Master election state machine of a virtual router
+///
+/// Times are in milliseconds and come from a free running counter that may wrap around; deadlines
+/// must be less than 2^31 milliseconds (~24 days) away.
+pub struct Router {
+    addr: ipv4::Addr,
+    advertisement_interval: u8,
+    deadline: u32,
+    preempt: bool,
+    priority: u8,
+    state: State,
+    vrid: u8,
+}
+
+impl Router {
+    /// Creates the virtual router `vrid` in the Initialize state
+    ///
+    /// `addr` is the primary IP address of the interface; it breaks ties between routers of equal
+    /// priority. `advertisement_interval` is in seconds. When `preempt` is set a higher priority
+    /// backup takes over from a lower priority master
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if `priority` or `advertisement_interval` is `0`
+    pub fn new(
+        vrid: u8,
+        addr: ipv4::Addr,
+        priority: u8,
+        advertisement_interval: u8,
+        preempt: bool,
+    ) -> Self {
+        assert!(priority != PRIORITY_STOP && advertisement_interval != 0);
+
+        Router {
+            addr,
+            advertisement_interval,
+            deadline: 0,
+            preempt,
+            priority,
+            state: State::Initialize,
+            vrid,
+        }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns the priority to put in the advertisements
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Returns the advertisement interval to put in the advertisements, in seconds
+    pub fn advertisement_interval(&self) -> u8 {
+        self.advertisement_interval
+    }
+
+    /// Returns the time at which `advance` must be called next, or `None` in the Initialize state
+    ///
+    /// This is the Adver_Timer in the Master state and the Master_Down_Timer in the Backup state
+    pub fn deadline(&self) -> Option<u32> {
+        if self.state == State::Initialize {
+            None
+        } else {
+            Some(self.deadline)
+        }
+    }
+
+    /// Returns the Skew_Time, in milliseconds
+    pub fn skew_time(&self) -> u32 {
+        (256 - u32::from(self.priority)) * 1_000 / 256
+    }
+
+    /// Returns the Master_Down_Interval, in milliseconds
+    pub fn master_down_interval(&self) -> u32 {
+        3 * self.advertisement_interval_ms() + self.skew_time()
+    }
+
+    /// Handles the Startup event
+    ///
+    /// The owner of the IP addresses (`PRIORITY_OWNER`) becomes master right away; other routers
+    /// become backups. Does nothing if the router is not in the Initialize state
+    pub fn startup(&mut self, now: u32) -> Action {
+        if self.state != State::Initialize {
+            return Action::None;
+        }
+
+        if self.priority == PRIORITY_OWNER {
+            self.become_master(now)
+        } else {
+            self.become_backup(now);
+            Action::None
+        }
+    }
+
+    /// Handles the Shutdown event
+    pub fn shutdown(&mut self) -> Action {
+        let state = self.state;
+        self.state = State::Initialize;
+
+        if state == State::Master {
+            Action::Resign
+        } else {
+            Action::None
+        }
+    }
+
+    /// Handles the expiration of the timers
+    ///
+    /// In the Master state this asks for an advertisement every advertisement interval; in the
+    /// Backup state the router becomes master if no advertisement was received within the
+    /// Master_Down_Interval
+    pub fn advance(&mut self, now: u32) -> Action {
+        if self.state == State::Initialize || !expired(now, self.deadline) {
+            return Action::None;
+        }
+
+        if self.state == State::Master {
+            self.deadline = now.wrapping_add(self.advertisement_interval_ms());
+            Action::Advertise
+        } else {
+            self.become_master(now)
+        }
+    }
+
+    /// Handles an advertisement sent by `src`
+    ///
+    /// The advertisement must have been validated (TTL, checksum, authentication, etc.; see
+    /// Section 7.1 of RFC 3768) beforehand. Advertisements for other virtual routers are ignored
+    pub fn receive<B>(&mut self, now: u32, src: ipv4::Addr, packet: &Packet<B>) -> Action
+    where
+        B: AsSlice<Element = u8>,
+    {
+        if packet.get_vrid() != self.vrid {
+            return Action::None;
+        }
+
+        let priority = packet.get_priority();
+        match self.state {
+            State::Initialize => Action::None,
+
+            State::Backup => {
+                if priority == PRIORITY_STOP {
+                    // the master is leaving; take over quickly
+                    self.deadline = now.wrapping_add(self.skew_time());
+                } else if !self.preempt || priority >= self.priority {
+                    self.deadline = now.wrapping_add(self.master_down_interval());
+                }
+
+                Action::None
+            }
+
+            State::Master => {
+                if priority == PRIORITY_STOP {
+                    self.deadline = now.wrapping_add(self.advertisement_interval_ms());
+                    Action::Advertise
+                } else if priority > self.priority
+                    || (priority == self.priority && src.0 > self.addr.0)
+                {
+                    self.become_backup(now);
+                    Action::BecomeBackup
+                } else {
+                    Action::None
+                }
+            }
+        }
+    }
+
+    fn advertisement_interval_ms(&self) -> u32 {
+        u32::from(self.advertisement_interval) * 1_000
+    }
+
+    fn become_backup(&mut self, now: u32) {
+        self.deadline = now.wrapping_add(self.master_down_interval());
+        self.state = State::Backup;
+    }
+
+    fn become_master(&mut self, now: u32) -> Action {
+        self.deadline = now.wrapping_add(self.advertisement_interval_ms());
+        self.state = State::Master;
+        Action::BecomeMaster
+    }
+}
+
+// has the `deadline` been reached?
+fn expired(now: u32, deadline: u32) -> bool {
+    now.wrapping_sub(deadline) < 1 << 31
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ipv4, mac, vrrp};
+
+    const ADDR1: ipv4::Addr = ipv4::Addr([192, 168, 1, 1]);
+    const ADDR2: ipv4::Addr = ipv4::Addr([192, 168, 1, 2]);
+
+    #[test]
+    fn new() {
+        let mut buf = [0xff; 64];
+        let mut vrrp = vrrp::Packet::new(&mut buf[..], 2);
+        vrrp.set_vrid(7);
+        vrrp.set_priority(vrrp::PRIORITY_OWNER);
+        vrrp.set_ip_addr(0, ADDR1);
+        vrrp.set_ip_addr(1, ADDR2);
+        vrrp.update_checksum();
+
+        assert_eq!(vrrp.len(), 24);
+
+        let vrrp = vrrp::Packet::parse(vrrp.as_bytes()).unwrap();
+        assert!(vrrp.verify_checksum());
+        assert_eq!(vrrp.get_version(), 2);
+        assert_eq!(vrrp.get_vrid(), 7);
+        assert_eq!(vrrp.get_priority(), 255);
+        assert_eq!(vrrp.get_count(), 2);
+        assert_eq!(vrrp.get_auth_type(), vrrp::AuthType::NoAuthentication);
+        assert_eq!(vrrp.get_advertisement_interval(), 1);
+        assert_eq!(vrrp.get_auth_data(), [0; 8]);
+
+        let mut addrs = vrrp.ip_addrs();
+        assert_eq!(addrs.next(), Some(ADDR1));
+        assert_eq!(addrs.next(), Some(ADDR2));
+        assert_eq!(addrs.next(), None);
+    }
+
+    #[test]
+    fn ipv4() {
+        let mut buf = [0; 64];
+        let mut ip = ipv4::Packet::new(&mut buf[..]);
+        ip.set_ttl(vrrp::TTL);
+        ip.set_destination(vrrp::MULTICAST_ADDR);
+        ip.vrrp(1, |vrrp| {
+            vrrp.set_vrid(1);
+            vrrp.set_ip_addr(0, ADDR1);
+        });
+        let ip = ip.update_checksum();
+
+        assert_eq!(ip.get_protocol(), ipv4::Protocol::Vrrp);
+        assert_eq!(ip.len(), 20 + 20);
+
+        let vrrp = vrrp::Packet::parse(ip.payload()).unwrap();
+        assert!(vrrp.verify_checksum());
+        assert_eq!(vrrp.ip_addrs().next(), Some(ADDR1));
+    }
+
+    #[test]
+    fn parse() {
+        let bytes = [
+            0x21, 0x01, 0x64, 0x01, 0x00, 0x01, 0xba, 0x52, // header
+            0xc0, 0xa8, 0x00, 0x01, // IP address
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // authentication data
+        ];
+
+        let vrrp = vrrp::Packet::parse(&bytes[..]).unwrap();
+        assert!(vrrp.verify_checksum());
+        assert_eq!(vrrp.get_vrid(), 1);
+        assert_eq!(vrrp.get_priority(), vrrp::PRIORITY_DEFAULT);
+        assert_eq!(vrrp.ip_addrs().next(), Some(ipv4::Addr([192, 168, 0, 1])));
+
+        // truncated authentication data
+        assert!(vrrp::Packet::parse(&bytes[..19]).is_err());
+
+        // VRRPv3
+        let mut v3 = bytes;
+        v3[0] = 0x31;
+        assert!(vrrp::Packet::parse(&v3[..]).is_err());
+
+        assert_eq!(
+            vrrp::virtual_mac(1),
+            mac::Addr([0x00, 0x00, 0x5e, 0x00, 0x01, 0x01])
+        );
+    }
+
+    fn advertisement(priority: u8) -> [u8; 20] {
+        let mut buf = [0; 20];
+        let mut vrrp = vrrp::Packet::new(&mut buf[..], 1);
+        vrrp.set_vrid(1);
+        vrrp.set_priority(priority);
+        vrrp.set_ip_addr(0, ADDR1);
+        buf
+    }
+
+    #[test]
+    fn election() {
+        use crate::vrrp::{Action, Router, State};
+
+        let lower = advertisement(50);
+        let lower = vrrp::Packet::parse(&lower[..]).unwrap();
+        let equal = advertisement(vrrp::PRIORITY_DEFAULT);
+        let equal = vrrp::Packet::parse(&equal[..]).unwrap();
+        let higher = advertisement(200);
+        let higher = vrrp::Packet::parse(&higher[..]).unwrap();
+        let stop = advertisement(vrrp::PRIORITY_STOP);
+        let stop = vrrp::Packet::parse(&stop[..]).unwrap();
+
+        let mut r = Router::new(1, ADDR2, vrrp::PRIORITY_DEFAULT, 1, true);
+        assert_eq!(r.state(), State::Initialize);
+        assert_eq!(r.deadline(), None);
+        assert_eq!(r.advance(0), Action::None);
+
+        // Skew_Time = (256 - 100) / 256 s
+        assert_eq!(r.skew_time(), 609);
+        assert_eq!(r.master_down_interval(), 3_609);
+
+        assert_eq!(r.startup(0), Action::None);
+        assert_eq!(r.state(), State::Backup);
+        assert_eq!(r.deadline(), Some(3_609));
+
+        // the master keeps advertising
+        assert_eq!(r.advance(3_000), Action::None);
+        assert_eq!(r.receive(3_000, ADDR1, &higher), Action::None);
+        assert_eq!(r.deadline(), Some(6_609));
+
+        // lower priority masters are preempted
+        assert_eq!(r.receive(4_000, ADDR1, &lower), Action::None);
+        assert_eq!(r.deadline(), Some(6_609));
+
+        // the master goes away
+        assert_eq!(r.advance(6_609), Action::BecomeMaster);
+        assert_eq!(r.state(), State::Master);
+        assert_eq!(r.deadline(), Some(7_609));
+        assert_eq!(r.advance(7_000), Action::None);
+        assert_eq!(r.advance(7_609), Action::Advertise);
+        assert_eq!(r.deadline(), Some(8_609));
+
+        // another backup takes over after a master shut down
+        assert_eq!(r.receive(8_000, ADDR1, &stop), Action::Advertise);
+        assert_eq!(r.deadline(), Some(9_000));
+
+        // ties are broken by the primary IP address
+        assert_eq!(r.receive(8_100, ADDR1, &lower), Action::None);
+        assert_eq!(r.receive(8_100, ADDR1, &equal), Action::None);
+        assert_eq!(
+            r.receive(8_200, ipv4::Addr([192, 168, 1, 3]), &equal),
+            Action::BecomeBackup
+        );
+        assert_eq!(r.state(), State::Backup);
+        assert_eq!(r.deadline(), Some(11_809));
+
+        // the master is shutting down
+        assert_eq!(r.receive(9_000, ADDR1, &stop), Action::None);
+        assert_eq!(r.deadline(), Some(9_609));
+        assert_eq!(r.advance(9_609), Action::BecomeMaster);
+
+        assert_eq!(r.shutdown(), Action::Resign);
+        assert_eq!(r.state(), State::Initialize);
+        assert_eq!(r.receive(10_000, ADDR1, &higher), Action::None);
+
+        // without preemption any advertisement keeps the master alive
+        let mut r = Router::new(1, ADDR2, vrrp::PRIORITY_DEFAULT, 1, false);
+        r.startup(u32::max_value() - 1_000);
+        assert_eq!(r.receive(u32::max_value(), ADDR1, &lower), Action::None);
+        assert_eq!(r.deadline(), Some(3_608));
+        assert_eq!(r.advance(3_607), Action::None);
+        assert_eq!(r.advance(3_608), Action::BecomeMaster);
+
+        // other virtual routers are ignored
+        let mut other = advertisement(200);
+        other[1] = 2;
+        let other = vrrp::Packet::parse(&other[..]).unwrap();
+        assert_eq!(r.receive(3_700, ADDR1, &other), Action::None);
+        assert_eq!(r.state(), State::Master);
+
+        // the owner becomes master right away
+        let mut r = Router::new(1, ADDR1, vrrp::PRIORITY_OWNER, 1, true);
+        assert_eq!(r.skew_time(), 3);
+        assert_eq!(r.startup(0), Action::BecomeMaster);
+        assert_eq!(r.startup(0), Action::None);
+    }
+}
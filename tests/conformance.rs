@@ -0,0 +1,154 @@
+//! Replays captures of unusual but legal frames through the parsers
+//!
+//! The captures live in `tests/pcap` and use the classic (libpcap) file format with Ethernet link
+//! type. New fixtures can be added by dropping a `.pcap` file in that directory and writing a test
+//! that asserts on how each of its frames is parsed.
+
+use jnet::{arp, checksum, ether, ipv4, mac, udp};
+
+const SMAC: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x00]);
+const DMAC: mac::Addr = mac::Addr([0x20, 0x18, 0x03, 0x01, 0x00, 0x01]);
+
+const IP_SRC: ipv4::Addr = ipv4::Addr([192, 168, 1, 33]);
+const IP_DEST: ipv4::Addr = ipv4::Addr([192, 168, 1, 1]);
+
+// Ethernet frames are padded to this size
+const MIN_FRAME_SIZE: usize = 60;
+
+/// Returns the frames stored in a pcap file
+fn frames(pcap: &[u8]) -> Vec<&[u8]> {
+    fn u32le(bytes: &[u8]) -> usize {
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+    }
+
+    // global header: magic number, version, time zone, sigfigs, snaplen, link type
+    assert_eq!(
+        u32le(&pcap[..4]),
+        0xa1b2_c3d4,
+        "not a little-endian pcap file"
+    );
+    assert_eq!(u32le(&pcap[20..24]), 1, "link type is not Ethernet");
+
+    let mut frames = vec![];
+    let mut rest = &pcap[24..];
+    while !rest.is_empty() {
+        // record header: seconds, microseconds, captured length, original length
+        let incl_len = u32le(&rest[8..12]);
+        let orig_len = u32le(&rest[12..16]);
+        assert_eq!(incl_len, orig_len, "truncated capture");
+
+        frames.push(&rest[16..16 + incl_len]);
+        rest = &rest[16 + incl_len..];
+    }
+    frames
+}
+
+// the checksum of a UDP packet with a valid checksum field is zero
+fn udp_checksum(src: ipv4::Addr, dest: ipv4::Addr, udp: &udp::Packet<&[u8]>) -> u16 {
+    let mut state =
+        checksum::State::ipv4_pseudo_header(src, dest, ipv4::Protocol::Udp, udp.get_length());
+    state.push(udp.as_bytes());
+    state.finish()
+}
+
+#[test]
+fn ipv4_options() {
+    let frames = frames(include_bytes!("pcap/ipv4-options.pcap"));
+    assert_eq!(frames.len(), 1);
+
+    let eth = ether::Frame::parse(frames[0]).unwrap();
+    assert_eq!(eth.as_bytes().len(), MIN_FRAME_SIZE);
+    assert_eq!(eth.get_source(), SMAC);
+    assert_eq!(eth.get_destination(), DMAC);
+    assert_eq!(eth.get_type(), ether::Type::Ipv4);
+
+    // the Ethernet padding is not part of the IP packet
+    let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+    assert_eq!(ip.get_ihl(), 6);
+    assert_eq!(ip.get_total_length(), 24 + 8 + 2);
+    assert_eq!(ip.get_protocol(), ipv4::Protocol::Udp);
+    assert_eq!(ip.get_source(), IP_SRC);
+    assert_eq!(ip.get_destination(), IP_DEST);
+
+    // the payload starts after the options
+    let udp = udp::Packet::parse(ip.payload()).unwrap();
+    assert_eq!(udp.get_source(), 1234);
+    assert_eq!(udp.get_destination(), 5683);
+    assert_eq!(udp.payload(), b"hi");
+    assert_eq!(udp_checksum(IP_SRC, IP_DEST, &udp), 0);
+}
+
+#[test]
+fn ipv4_fragments() {
+    let frames = frames(include_bytes!("pcap/ipv4-fragments.pcap"));
+    assert_eq!(frames.len(), 2);
+
+    let mut datagram = vec![];
+    for (i, frame) in frames.iter().enumerate() {
+        let eth = ether::Frame::parse(*frame).unwrap();
+        let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+
+        assert_eq!(ip.get_identification(), 0x1337);
+        assert!(!ip.get_df());
+        assert_eq!(ip.get_mf(), i == 0);
+        assert_eq!(usize::from(ip.get_fragment_offset()) * 8, datagram.len());
+
+        datagram.extend_from_slice(ip.payload());
+    }
+
+    // only the first fragment contains the UDP header
+    let udp = udp::Packet::parse(&datagram[..]).unwrap();
+    assert_eq!(usize::from(udp.get_length()), datagram.len());
+    assert_eq!(udp.payload(), &(0..24).collect::<Vec<u8>>()[..]);
+}
+
+#[test]
+fn vlan() {
+    let frames = frames(include_bytes!("pcap/vlan.pcap"));
+    assert_eq!(frames.len(), 1);
+
+    // 802.1Q tags are not parsed; the tag shows up as an unknown Ether Type
+    let eth = ether::Frame::parse(frames[0]).unwrap();
+    assert_eq!(eth.get_type(), ether::Type::Unknown(0x8100));
+
+    let tag = eth.payload();
+    let vid = u16::from_be_bytes([tag[0], tag[1]]) & 0x0fff;
+    assert_eq!(vid, 100);
+    assert_eq!(u16::from_be_bytes([tag[2], tag[3]]), 0x0800);
+
+    let ip = ipv4::Packet::parse(&tag[4..]).unwrap();
+    let udp = udp::Packet::parse(ip.payload()).unwrap();
+    assert_eq!(udp.payload(), b"vlan");
+}
+
+#[test]
+fn udp_zero_checksum() {
+    let frames = frames(include_bytes!("pcap/udp-zero-checksum.pcap"));
+    assert_eq!(frames.len(), 1);
+
+    let eth = ether::Frame::parse(frames[0]).unwrap();
+    let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+
+    // over IPv4 a zero checksum means "no checksum"
+    let udp = udp::Packet::parse(ip.payload()).unwrap();
+    assert_eq!(udp.as_bytes()[6..8], [0, 0]);
+    assert!(udp.verify_ipv4_checksum(ip.get_source(), ip.get_destination()));
+    assert_eq!(udp.payload(), b"no checksum");
+}
+
+#[test]
+fn arp_padded() {
+    let frames = frames(include_bytes!("pcap/arp-padded.pcap"));
+    assert_eq!(frames.len(), 1);
+
+    let eth = ether::Frame::parse(frames[0]).unwrap();
+    assert_eq!(eth.as_bytes().len(), MIN_FRAME_SIZE);
+    assert_eq!(eth.get_type(), ether::Type::Arp);
+
+    let arp = arp::Packet::parse(eth.payload()).unwrap();
+    let arp = arp.downcast().unwrap();
+    assert_eq!(arp.get_oper(), arp::Operation::Request);
+    assert_eq!(arp.get_sha(), SMAC);
+    assert_eq!(arp.get_spa(), IP_SRC);
+    assert_eq!(arp.get_tpa(), IP_DEST);
+}
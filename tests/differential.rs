@@ -0,0 +1,278 @@
+//! Differential testing against smoltcp's wire types
+//!
+//! Random (but plausible) frames are parsed with both jnet and smoltcp. Both must accept or reject
+//! each frame, except in the few documented cases where their validation differs, and whenever
+//! both accept a frame the header fields they extract must agree.
+
+use jnet::{arp, checksum, ether, ipv4, ipv6, udp};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use smoltcp::wire;
+
+// fixed seed so failures can be reproduced
+const SEED: u64 = 0x6a6e_6574;
+const ITERATIONS: usize = 20_000;
+
+// makes random bytes look like the header of `ether_type` most of the time
+fn fix_up(rng: &mut StdRng, ether_type: u16, payload: &mut [u8]) {
+    match ether_type {
+        0x0800 if payload.len() >= 20 => {
+            let ihl = if rng.gen_bool(0.8) {
+                5
+            } else {
+                rng.gen_range(0, 16)
+            };
+            payload[0] = 4 << 4 | ihl;
+
+            let max = payload.len() as u16;
+            let total_len = if rng.gen_bool(0.8) {
+                rng.gen_range(0, max + 1)
+            } else {
+                rng.gen()
+            };
+            payload[2..4].copy_from_slice(&total_len.to_be_bytes());
+
+            if rng.gen_bool(0.5) {
+                payload[9] = 17;
+            }
+
+            // valid header checksum
+            let header_len = usize::from(ihl) * 4;
+            if header_len <= payload.len() {
+                payload[10..12].copy_from_slice(&[0, 0]);
+                let mut state = checksum::State::new();
+                state.push(&payload[..header_len]);
+                let cksum = state.finish();
+                payload[10..12].copy_from_slice(&cksum.to_be_bytes());
+            }
+        }
+        0x86dd if payload.len() >= 40 => {
+            payload[0] = 6 << 4 | payload[0] & 0x0f;
+
+            let max = payload.len() as u16 - 40;
+            let len = rng.gen_range(0, max + 1);
+            payload[4..6].copy_from_slice(&len.to_be_bytes());
+
+            payload[6] = if rng.gen_bool(0.5) { 17 } else { 58 };
+        }
+        0x0806 if payload.len() >= 8 => {
+            if rng.gen_bool(0.8) {
+                payload[..6].copy_from_slice(&[0, 1, 0x08, 0x00, 6, 4]);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn compare_udp(bytes: &[u8]) -> bool {
+    let jnet = udp::Packet::parse(bytes);
+    let smol = wire::UdpPacket::new_checked(bytes);
+
+    assert_eq!(jnet.is_ok(), smol.is_ok(), "UDP: {:?}", bytes);
+    if let (Ok(jnet), Ok(smol)) = (jnet, smol) {
+        assert_eq!(jnet.get_source(), smol.src_port());
+        assert_eq!(jnet.get_destination(), smol.dst_port());
+        assert_eq!(jnet.get_length(), smol.len());
+        // NOTE jnet's payload extends to the end of the buffer; smoltcp's stops at Length
+        assert!(jnet.payload().starts_with(smol.payload()));
+        true
+    } else {
+        false
+    }
+}
+
+fn compare_ipv4(bytes: &[u8]) -> bool {
+    let jnet = ipv4::Packet::parse(bytes);
+    let smol = wire::Ipv4Packet::new_checked(bytes);
+    // `new_checked` only checks lengths; `Ipv4Repr::parse` also checks the version and the
+    // header checksum. (`Ipv4Repr::parse` itself is not used because it rejects fragments)
+    let smol_ok = smol
+        .as_ref()
+        .map(|smol| smol.version() == 4 && smol.verify_checksum())
+        .unwrap_or(false);
+
+    // NOTE documented divergences
+    if bytes.len() >= 20 && bytes[0] & 0x0f < 5 {
+        // jnet rejects IHL < 5; smoltcp's `new_checked` doesn't look at it
+        assert!(jnet.is_err(), "IPv4: {:?}", bytes);
+    } else if bytes.len() >= 20
+        && usize::from(u16::from_be_bytes([bytes[2], bytes[3]])) > bytes.len()
+    {
+        // jnet accepts packets whose Total Length exceeds the input; smoltcp doesn't
+        assert!(!smol_ok, "IPv4: {:?}", bytes);
+    } else {
+        assert_eq!(jnet.is_ok(), smol_ok, "IPv4: {:?}", bytes);
+    }
+
+    let (jnet, smol) = match (jnet, smol) {
+        (Ok(jnet), Ok(smol)) if smol_ok => (jnet, smol),
+        _ => return false,
+    };
+
+    assert_eq!(jnet.get_version(), smol.version());
+    assert_eq!(jnet.get_ihl() * 4, smol.header_len());
+    assert_eq!(jnet.get_total_length(), smol.total_len());
+    assert_eq!(jnet.get_identification(), smol.ident());
+    assert_eq!(jnet.get_df(), smol.dont_frag());
+    assert_eq!(jnet.get_mf(), smol.more_frags());
+    assert_eq!(jnet.get_fragment_offset() * 8, smol.frag_offset());
+    assert_eq!(jnet.get_ttl(), smol.hop_limit());
+    assert_eq!(u8::from(jnet.get_protocol()), u8::from(smol.next_header()));
+    assert_eq!(jnet.get_source().0, smol.src_addr().0);
+    assert_eq!(jnet.get_destination().0, smol.dst_addr().0);
+    assert_eq!(jnet.payload(), smol.payload());
+
+    if jnet.get_protocol() == ipv4::Protocol::Udp {
+        compare_udp(jnet.payload());
+    }
+
+    true
+}
+
+fn compare_ipv6(bytes: &[u8]) -> bool {
+    let jnet = ipv6::Packet::parse(bytes);
+    let smol = wire::Ipv6Packet::new_checked(bytes);
+    let smol_ok = smol
+        .as_ref()
+        .map(|smol| wire::Ipv6Repr::parse(smol).is_ok())
+        .unwrap_or(false);
+
+    // NOTE documented divergences
+    let next_header = bytes.get(6).map(|nh| ipv6::NextHeader::from(*nh));
+    if bytes.len() >= 40
+        && bytes[0] >> 4 == 6
+        && next_header.map_or(false, |nh| {
+            nh.is_ipv6_extension_header() && nh != ipv6::NextHeader::Ipv6Frag
+        })
+    {
+        // jnet rejects extension headers other than the Fragment header; smoltcp doesn't
+        assert!(jnet.is_err(), "IPv6: {:?}", bytes);
+    } else if bytes.len() >= 40
+        && usize::from(u16::from_be_bytes([bytes[4], bytes[5]])) > bytes.len() - 40
+    {
+        // jnet doesn't check the Payload Length against the input; smoltcp does
+        assert!(!smol_ok, "IPv6: {:?}", bytes);
+    } else {
+        assert_eq!(jnet.is_ok(), smol_ok, "IPv6: {:?}", bytes);
+    }
+
+    let (jnet, smol) = match (jnet, smol) {
+        (Ok(jnet), Ok(smol)) if smol_ok => (jnet, smol),
+        _ => return false,
+    };
+
+    assert_eq!(jnet.get_version(), smol.version());
+    assert_eq!(jnet.get_traffic_class(), smol.traffic_class());
+    assert_eq!(jnet.get_flow_label(), smol.flow_label());
+    assert_eq!(jnet.get_length(), smol.payload_len());
+    assert_eq!(
+        u8::from(jnet.get_next_header()),
+        u8::from(smol.next_header())
+    );
+    assert_eq!(jnet.get_hop_limit(), smol.hop_limit());
+    assert_eq!(jnet.get_source().0, smol.src_addr().0);
+    assert_eq!(jnet.get_destination().0, smol.dst_addr().0);
+    assert!(jnet.payload().starts_with(smol.payload()));
+
+    if jnet.get_next_header() == ipv6::NextHeader::Udp {
+        compare_udp(smol.payload());
+    }
+
+    true
+}
+
+fn compare_arp(bytes: &[u8]) -> bool {
+    let jnet = arp::Packet::parse(bytes);
+    let smol = wire::ArpPacket::new_checked(bytes);
+
+    // NOTE documented divergence: jnet rejects empty addresses and address lengths that don't
+    // match the hardware / protocol type; smoltcp only checks that the addresses fit
+    let inconsistent = bytes.len() >= 8 && {
+        let htype = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let ptype = u16::from_be_bytes([bytes[2], bytes[3]]);
+        let (hlen, plen) = (bytes[4], bytes[5]);
+
+        hlen == 0
+            || plen == 0
+            || (htype == 1 && hlen != 6)
+            || (ptype == 0x0800 && plen != 4)
+            || (ptype == 0x86dd && plen != 16)
+    };
+    if inconsistent {
+        assert!(jnet.is_err(), "ARP: {:?}", bytes);
+    } else {
+        assert_eq!(jnet.is_ok(), smol.is_ok(), "ARP: {:?}", bytes);
+    }
+
+    let (jnet, smol) = match (jnet, smol) {
+        (Ok(jnet), Ok(smol)) => (jnet, smol),
+        _ => return false,
+    };
+
+    assert_eq!(u16::from(jnet.get_htype()), u16::from(smol.hardware_type()));
+    assert_eq!(u16::from(jnet.get_ptype()), u16::from(smol.protocol_type()));
+    assert_eq!(jnet.get_hlen(), smol.hardware_len());
+    assert_eq!(jnet.get_plen(), smol.protocol_len());
+    assert_eq!(jnet.get_oper_raw(), u16::from(smol.operation()));
+    assert_eq!(jnet.get_sha(), smol.source_hardware_addr());
+    assert_eq!(jnet.get_spa(), smol.source_protocol_addr());
+    assert_eq!(jnet.get_tha(), smol.target_hardware_addr());
+    assert_eq!(jnet.get_tpa(), smol.target_protocol_addr());
+
+    true
+}
+
+#[test]
+fn smoltcp() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut buffer = [0; 128];
+    // number of frames whose network layer header was compared
+    let mut compared = 0;
+
+    for _ in 0..ITERATIONS {
+        let len = rng.gen_range(0, buffer.len() + 1);
+        let frame = &mut buffer[..len];
+        rng.fill(frame);
+
+        let ether_type: u16 = match rng.gen_range(0, 4) {
+            0 => 0x0800,
+            1 => 0x86dd,
+            2 => 0x0806,
+            _ => rng.gen(),
+        };
+        if len >= 14 {
+            frame[12..14].copy_from_slice(&ether_type.to_be_bytes());
+            fix_up(&mut rng, ether_type, &mut frame[14..]);
+        }
+
+        let frame = &*frame;
+        let jnet = ether::Frame::parse(frame);
+        let smol = wire::EthernetFrame::new_checked(frame);
+        assert_eq!(jnet.is_ok(), smol.is_ok());
+
+        if let (Ok(jnet), Ok(smol)) = (jnet, smol) {
+            assert_eq!(jnet.get_destination().0, smol.dst_addr().0);
+            assert_eq!(jnet.get_source().0, smol.src_addr().0);
+            assert_eq!(u16::from(jnet.get_type()), u16::from(smol.ethertype()));
+            assert_eq!(jnet.payload(), smol.payload());
+
+            let payload = jnet.payload();
+            let both_accepted = match ether_type {
+                0x0800 => compare_ipv4(payload),
+                0x86dd => compare_ipv6(payload),
+                0x0806 => compare_arp(payload),
+                _ => false,
+            };
+
+            if both_accepted {
+                compared += 1;
+            }
+        }
+    }
+
+    // make sure the generator produces enough valid headers
+    assert!(
+        compared > ITERATIONS / 10,
+        "only {} frames compared",
+        compared
+    );
+}
@@ -0,0 +1,184 @@
+//! Zero-length and maximal-length payloads
+
+use jnet::{ether, icmp, ipv4, ipv6, mac, udp};
+
+const MTU: usize = 1500;
+const MAX_FRAME_SIZE: usize = ether::HEADER_SIZE as usize + MTU;
+
+const IPV4_SRC: ipv4::Addr = ipv4::Addr([192, 168, 1, 33]);
+const IPV4_DEST: ipv4::Addr = ipv4::Addr([192, 168, 1, 1]);
+
+const IPV6_SRC: ipv6::Addr = ipv6::Addr([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+const IPV6_DEST: ipv6::Addr = ipv6::Addr([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+
+fn udp4(buffer: &mut [u8], payload: &[u8]) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(mac::Addr::BROADCAST);
+    eth.set_destination(mac::Addr::BROADCAST);
+
+    eth.ipv4(|ip| {
+        ip.set_source(IPV4_SRC);
+        ip.set_destination(IPV4_DEST);
+
+        ip.udp(|udp| {
+            udp.set_source(1337);
+            udp.set_destination(1338);
+            udp.set_payload(payload);
+        });
+    });
+
+    let len = eth.as_bytes().len();
+
+    let eth = ether::Frame::parse(eth.as_bytes()).unwrap();
+    let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+    assert_eq!(usize::from(ip.get_total_length()), len - 14);
+    let udp = udp::Packet::parse(ip.payload()).unwrap();
+    assert_eq!(usize::from(udp.get_length()), payload.len() + 8);
+    assert_eq!(udp.payload(), payload);
+
+    len
+}
+
+fn udp6(buffer: &mut [u8], payload: &[u8]) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(mac::Addr::BROADCAST);
+    eth.set_destination(mac::Addr::BROADCAST);
+
+    eth.ipv6(|ip| {
+        ip.set_source(IPV6_SRC);
+        ip.set_destination(IPV6_DEST);
+
+        ip.udp(|udp| {
+            udp.set_source(1337);
+            udp.set_destination(1338);
+            udp.set_payload(payload);
+        });
+    });
+
+    let len = eth.as_bytes().len();
+
+    let eth = ether::Frame::parse(eth.as_bytes()).unwrap();
+    let ip = ipv6::Packet::parse(eth.payload()).unwrap();
+    assert_eq!(usize::from(ip.get_length()), len - 14 - 40);
+    let udp = udp::Packet::parse(ip.payload()).unwrap();
+    assert_eq!(usize::from(udp.get_length()), payload.len() + 8);
+    assert_eq!(udp.payload(), payload);
+    assert!(udp.verify_ipv6_checksum(IPV6_SRC, IPV6_DEST));
+
+    len
+}
+
+#[test]
+fn udp4_empty_payload() {
+    let mut buffer = [0; MAX_FRAME_SIZE];
+    assert_eq!(udp4(&mut buffer, &[]), 14 + 20 + 8);
+}
+
+#[test]
+fn udp4_max_payload() {
+    let payload = [0xaa; MTU - 20 - 8];
+    let mut buffer = [0; MAX_FRAME_SIZE];
+    assert_eq!(udp4(&mut buffer, &payload), MAX_FRAME_SIZE);
+}
+
+#[test]
+fn udp6_empty_payload() {
+    let mut buffer = [0; MAX_FRAME_SIZE];
+    assert_eq!(udp6(&mut buffer, &[]), 14 + 40 + 8);
+}
+
+#[test]
+fn udp6_max_payload() {
+    let payload = [0xaa; MTU - 40 - 8];
+    let mut buffer = [0; MAX_FRAME_SIZE];
+    assert_eq!(udp6(&mut buffer, &payload), MAX_FRAME_SIZE);
+}
+
+#[test]
+fn udp_every_length() {
+    let payload = [0x55; MTU];
+    let mut buffer = [0; MAX_FRAME_SIZE];
+
+    for len in 0..=MTU - 20 - 8 {
+        assert_eq!(udp4(&mut buffer, &payload[..len]), 14 + 20 + 8 + len);
+    }
+
+    for len in 0..=MTU - 40 - 8 {
+        assert_eq!(udp6(&mut buffer, &payload[..len]), 14 + 40 + 8 + len);
+    }
+}
+
+#[test]
+fn udp6_checksum_never_zero() {
+    // this payload makes the one's complement sum of the pseudo-header + UDP header + payload
+    // 0xffff, so the checksum would be 0x0000, which means "no checksum"; it must be sent as 0xffff
+    let mut buffer = [0; MAX_FRAME_SIZE];
+
+    for x in 0..=u16::max_value() {
+        let payload = x.to_be_bytes();
+        udp6(&mut buffer, &payload);
+
+        let eth = ether::Frame::parse(&buffer[..]).unwrap();
+        let ip = ipv6::Packet::parse(eth.payload()).unwrap();
+        let udp = udp::Packet::parse(ip.payload()).unwrap();
+        assert_ne!(udp.as_bytes()[6..8], [0, 0]);
+    }
+}
+
+fn echo_request(buffer: &mut [u8], payload_len: u16) -> usize {
+    let mut eth = ether::Frame::new(buffer);
+    eth.set_source(mac::Addr::BROADCAST);
+    eth.set_destination(mac::Addr::BROADCAST);
+
+    eth.ipv4(|ip| {
+        ip.set_source(IPV4_SRC);
+        ip.set_destination(IPV4_DEST);
+
+        ip.echo_request(|icmp| {
+            icmp.set_identifier(1);
+            icmp.set_sequence_number(2);
+            icmp.set_payload_pattern(icmp::Pattern::Incrementing, payload_len);
+        });
+    });
+
+    let len = eth.as_bytes().len();
+
+    let eth = ether::Frame::parse(eth.as_bytes()).unwrap();
+    let ip = ipv4::Packet::parse(eth.payload()).unwrap();
+    // NOTE `parse` verifies the checksum
+    let icmp = icmp::Message::parse(ip.payload())
+        .unwrap()
+        .downcast::<icmp::EchoRequest>()
+        .unwrap();
+    assert_eq!(usize::from(icmp.len()), usize::from(payload_len) + 8);
+    assert!(icmp.matches_pattern(icmp::Pattern::Incrementing));
+
+    len
+}
+
+#[test]
+fn icmp_header_only() {
+    let mut buffer = [0; MAX_FRAME_SIZE];
+    assert_eq!(echo_request(&mut buffer, 0), 14 + 20 + 8);
+}
+
+#[test]
+fn icmp_every_length() {
+    let mut buffer = [0; MAX_FRAME_SIZE];
+
+    for len in 0..=(MTU - 20 - 8) as u16 {
+        assert_eq!(
+            echo_request(&mut buffer, len),
+            14 + 20 + 8 + usize::from(len)
+        );
+    }
+}
+
+#[test]
+fn frame_too_small() {
+    assert!(ether::Frame::parse(&[0; 13][..]).is_err());
+    assert!(ipv4::Packet::parse(&[0x45; 19][..]).is_err());
+    assert!(ipv6::Packet::parse(&[0x60; 39][..]).is_err());
+    assert!(udp::Packet::parse(&[0; 7][..]).is_err());
+    assert!(icmp::Message::parse(&[0; 7][..]).is_err());
+}